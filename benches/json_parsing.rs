@@ -0,0 +1,123 @@
+// benches/json_parsing.rs
+//
+// NDJSON parsing throughput. Run against a real file; with the `simd` feature the
+// simd-json borrowed value (`parsers::simd::SimdParser`) is measured too:
+//
+//     cargo bench --bench json_parsing -- /var/log/app.ndjson
+//     cargo bench --bench json_parsing --features simd -- /var/log/app.ndjson
+//
+// Without a path, a synthetic file of 200,000 mixed application and access-log lines is
+// generated in memory.
+
+use loglens_core::parsers::{self, json};
+use loglens_core::query::Query;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SYNTHETIC_LINES: usize = 200_000;
+const ROUNDS: usize = 5;
+
+fn main() {
+    let path = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+    let input = match &path {
+        Some(path) => std::fs::read_to_string(path).unwrap_or_else(|e| panic!("reading {}: {}", path, e)),
+        None => synthetic(SYNTHETIC_LINES),
+    };
+    let lines: Vec<&str> = input.lines().filter(|line| !line.trim().is_empty()).collect();
+    println!(
+        "{} lines, {:.1} MiB from {}",
+        lines.len(),
+        mib(input.len()),
+        path.as_deref().unwrap_or("synthetic input")
+    );
+
+    bench("serde_json::from_str", &lines, |line| {
+        black_box(serde_json::from_str::<serde_json::Value>(line).is_ok());
+    });
+    bench("json::parse_json_line", &lines, |line| {
+        black_box(json::parse_json_line(line).is_ok());
+    });
+    #[cfg(feature = "simd")]
+    {
+        let mut parser = loglens_core::parsers::simd::SimdParser::new();
+        bench("SimdParser::parse", &lines, |line| {
+            black_box(parser.parse(line).is_some());
+        });
+    }
+    bench("parse_log_line", &lines, |line| {
+        black_box(parsers::parse_log_line(line));
+    });
+    let query = Query::parse("level == error && status >= 500").expect("benchmark query");
+    bench("parse_log_line + matches", &lines, |line| {
+        black_box(parsers::parse_log_line(line).matches(&query).unwrap_or(false));
+    });
+}
+
+/// Times `ROUNDS` passes over `lines` and reports the fastest.
+fn bench(name: &str, lines: &[&str], mut parse: impl FnMut(&str)) {
+    let bytes: usize = lines.iter().map(|line| line.len() + 1).sum();
+    let best = (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            for line in lines {
+                parse(line);
+            }
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or(Duration::ZERO);
+    let secs = best.as_secs_f64().max(f64::EPSILON);
+    println!(
+        "{:<28} {:>9.1} ms  {:>8.1} MiB/s  {:>10.0} lines/s",
+        name,
+        secs * 1000.0,
+        mib(bytes) / secs,
+        lines.len() as f64 / secs
+    );
+}
+
+fn mib(bytes: usize) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+/// Application logs with nested context, interleaved with access-log style records.
+fn synthetic(lines: usize) -> String {
+    const LEVELS: &[&str] = &["debug", "info", "info", "info", "warn", "error"];
+    const PATHS: &[&str] = &["/api/users", "/api/orders/42", "/health", "/login?next=%2Fhome", "/static/app.js"];
+    const STATUSES: &[u16] = &[200, 200, 201, 304, 404, 500, 503];
+    let mut out = String::with_capacity(lines * 260);
+    for i in 0..lines {
+        let (day, hour, minute, second) = (1 + i / 86_400 % 28, i / 3600 % 24, i / 60 % 60, i % 60);
+        let ts = format!("2024-03-{:02}T{:02}:{:02}:{:02}.{:03}Z", day, hour, minute, second, i % 1000);
+        let level = LEVELS[i % LEVELS.len()];
+        let error = match level {
+            "error" => serde_json::Value::from("connection reset by peer"),
+            _ => serde_json::Value::Null,
+        };
+        let line = if i % 3 == 0 {
+            serde_json::json!({
+                "ts": ts,
+                "remote_addr": format!("10.0.{}.{}", i % 256, i * 7 % 256),
+                "method": if i % 5 == 0 { "POST" } else { "GET" },
+                "path": PATHS[i % PATHS.len()],
+                "status": STATUSES[i % STATUSES.len()],
+                "bytes": i * 37 % 50_000,
+                "duration_ms": (i % 997) as f64 / 3.0,
+                "user_agent": "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko)",
+            })
+        } else {
+            serde_json::json!({
+                "timestamp": ts,
+                "level": level,
+                "msg": format!("request {} handled by worker {}", i, i % 16),
+                "service": "checkout",
+                "trace_id": format!("{:032x}", i as u128 * 0x9e37_79b9_7f4a_7c15),
+                "context": {"user": {"id": i % 5000, "plan": "pro"}, "retries": i % 4, "tags": ["a", "b", "c"]},
+                "error": error,
+            })
+        };
+        out.push_str(&line.to_string());
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,147 @@
+// File: src/aggregate/distinct.rs
+
+use super::group::key_value;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+/// Distinct values counted exactly before switching to HyperLogLog.
+pub const DEFAULT_EXACT_LIMIT: usize = 10_000;
+
+/// HyperLogLog precision: 2^14 registers, about 0.8% standard error in 16 KiB.
+const PRECISION: u32 = 14;
+const REGISTERS: usize = 1 << PRECISION;
+
+/// Result of a distinct count.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DistinctResult {
+    pub count: u64,
+    /// False once the count is a HyperLogLog estimate.
+    pub exact: bool,
+}
+
+#[derive(Debug, Clone)]
+enum State {
+    Exact(HashSet<String>),
+    Approximate(Box<[u8; REGISTERS]>),
+}
+
+/// Counts distinct values of a field ("how many unique user_ids hit this error").
+///
+/// Small cardinalities are counted exactly; past the exact limit the set is
+/// folded into a HyperLogLog sketch with bounded memory.
+#[derive(Debug, Clone)]
+pub struct DistinctCount {
+    field: String,
+    exact_limit: usize,
+    state: State,
+}
+
+impl DistinctCount {
+    pub fn new(field: impl Into<String>) -> Self {
+        DistinctCount {
+            field: field.into(),
+            exact_limit: DEFAULT_EXACT_LIMIT,
+            state: State::Exact(HashSet::new()),
+        }
+    }
+
+    /// Changes the exact-counting threshold. `0` goes straight to HyperLogLog.
+    pub fn set_exact_limit(&mut self, exact_limit: usize) {
+        self.exact_limit = exact_limit;
+        self.spill_if_needed();
+    }
+
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Records one entry. Entries without the field are ignored.
+    pub fn observe(&mut self, entry: &Value) {
+        if crate::query::resolve_field(entry, &self.field).is_none_or(Value::is_null) {
+            return;
+        }
+        self.insert(key_value(entry, &self.field));
+    }
+
+    pub fn insert(&mut self, value: String) {
+        match &mut self.state {
+            State::Exact(set) => {
+                set.insert(value);
+                self.spill_if_needed();
+            }
+            State::Approximate(registers) => add_hash(registers, hash(&value)),
+        }
+    }
+
+    fn spill_if_needed(&mut self) {
+        if let State::Exact(set) = &self.state {
+            if set.len() > self.exact_limit {
+                let mut registers = Box::new([0u8; REGISTERS]);
+                for value in set {
+                    add_hash(&mut registers, hash(value));
+                }
+                self.state = State::Approximate(registers);
+            }
+        }
+    }
+
+    /// Folds another counter over the same field into this one.
+    pub fn merge(&mut self, other: DistinctCount) {
+        match other.state {
+            State::Exact(set) => {
+                for value in set {
+                    self.insert(value);
+                }
+            }
+            State::Approximate(theirs) => {
+                if let State::Exact(_) = self.state {
+                    self.exact_limit = 0;
+                    self.spill_if_needed();
+                }
+                if let State::Approximate(mine) = &mut self.state {
+                    for (m, t) in mine.iter_mut().zip(theirs.iter()) {
+                        *m = (*m).max(*t);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn result(&self) -> DistinctResult {
+        match &self.state {
+            State::Exact(set) => DistinctResult { count: set.len() as u64, exact: true },
+            State::Approximate(registers) => DistinctResult { count: estimate(registers), exact: false },
+        }
+    }
+}
+
+fn hash(value: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn add_hash(registers: &mut [u8; REGISTERS], hash: u64) {
+    let index = (hash >> (64 - PRECISION)) as usize;
+    let rest = hash << PRECISION;
+    let rank = (rest.leading_zeros() + 1).min(64 - PRECISION + 1) as u8;
+    registers[index] = registers[index].max(rank);
+}
+
+fn estimate(registers: &[u8; REGISTERS]) -> u64 {
+    let m = REGISTERS as f64;
+    let alpha = 0.7213 / (1.0 + 1.079 / m);
+    let sum: f64 = registers.iter().map(|&r| 2f64.powi(-(r as i32))).sum();
+    let raw = alpha * m * m / sum;
+
+    // Small-range correction (linear counting)
+    let zeros = registers.iter().filter(|&&r| r == 0).count();
+    if raw <= 2.5 * m && zeros > 0 {
+        (m * (m / zeros as f64).ln()).round() as u64
+    } else {
+        raw.round() as u64
+    }
+}
@@ -0,0 +1,120 @@
+// File: src/aggregate/group.rs
+
+use super::{Aggregator, Stats};
+use crate::query;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Default cap on distinct groups before new ones spill into the "other" bucket.
+pub const DEFAULT_MAX_GROUPS: usize = 10_000;
+
+/// Placeholder used in a group key when the entry lacks that field.
+pub const MISSING_KEY: &str = "-";
+
+/// Results for a single group.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupStats {
+    /// One value per grouping field, in the order the fields were given.
+    pub key: Vec<String>,
+    pub stats: Stats,
+}
+
+/// Results of a grouped aggregation, largest groups first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupedStats {
+    pub groups: Vec<GroupStats>,
+    /// Entries that arrived after the group cap was reached.
+    pub other: Option<Stats>,
+}
+
+/// `group by status, path`: one [`Aggregator`] per distinct combination of key values.
+///
+/// Memory is bounded by a cardinality cap; once reached, entries for unseen
+/// groups are folded into a single "other" bucket.
+#[derive(Debug, Clone)]
+pub struct GroupBy {
+    keys: Vec<String>,
+    field: Option<String>,
+    max_groups: usize,
+    groups: HashMap<Vec<String>, Aggregator>,
+    other: Option<Aggregator>,
+}
+
+impl GroupBy {
+    /// Groups by `keys`. With a `field`, each group also gets numeric stats over it;
+    /// without one, groups only count entries.
+    pub fn new(keys: Vec<String>, field: Option<String>) -> Self {
+        GroupBy {
+            keys,
+            field,
+            max_groups: DEFAULT_MAX_GROUPS,
+            groups: HashMap::new(),
+            other: None,
+        }
+    }
+
+    pub fn set_max_groups(&mut self, max_groups: usize) {
+        self.max_groups = max_groups;
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    fn new_aggregator(&self) -> Aggregator {
+        Aggregator::new(self.field.clone().unwrap_or_default())
+    }
+
+    /// The group key for an entry.
+    pub fn key_for(&self, entry: &Value) -> Vec<String> {
+        self.keys.iter().map(|k| key_value(entry, k)).collect()
+    }
+
+    pub fn observe(&mut self, entry: &Value) {
+        let key = self.key_for(entry);
+        self.bucket(key).observe(entry);
+    }
+
+    fn bucket(&mut self, key: Vec<String>) -> &mut Aggregator {
+        let fresh = self.new_aggregator();
+        if !self.groups.contains_key(&key) && self.groups.len() >= self.max_groups {
+            return self.other.get_or_insert(fresh);
+        }
+        self.groups.entry(key).or_insert(fresh)
+    }
+
+    /// Folds another grouping's state into this one, respecting this cap.
+    pub fn merge(&mut self, other: GroupBy) {
+        for (key, aggregator) in other.groups {
+            self.bucket(key).merge(aggregator);
+        }
+        if let Some(rest) = other.other {
+            let fresh = self.new_aggregator();
+            self.other.get_or_insert(fresh).merge(rest);
+        }
+    }
+
+    pub fn stats(&self) -> GroupedStats {
+        let mut groups: Vec<GroupStats> = self
+            .groups
+            .iter()
+            .map(|(key, aggregator)| GroupStats { key: key.clone(), stats: aggregator.stats() })
+            .collect();
+        groups.sort_by(|a, b| b.stats.count.cmp(&a.stats.count).then_with(|| a.key.cmp(&b.key)));
+
+        GroupedStats {
+            groups,
+            other: self.other.as_ref().map(Aggregator::stats),
+        }
+    }
+}
+
+/// Renders a field as a group key component.
+pub(crate) fn key_value(entry: &Value, field: &str) -> String {
+    match query::resolve_field(entry, field) {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => MISSING_KEY.to_string(),
+        Some(other) => other.to_string(),
+    }
+}
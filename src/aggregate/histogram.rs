@@ -0,0 +1,132 @@
+// File: src/aggregate/histogram.rs
+
+use super::group::key_value;
+use crate::time as time_parser;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Default cap on the buckets `result()` fills in between the first and last entry.
+pub const DEFAULT_MAX_BUCKETS: usize = 10_000;
+
+/// One fixed-width time bucket.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bucket {
+    pub start: DateTime<Utc>,
+    pub count: u64,
+    /// Per-group counts, when the histogram is grouped.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, u64>,
+}
+
+/// Time-bucketed counts, ready for sparklines and charts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistogramResult {
+    pub interval_secs: u64,
+    /// Contiguous buckets from the earliest to the latest entry; empty ones have a zero count.
+    /// When that span would exceed the histogram's bucket limit (an outlier timestamp such
+    /// as epoch 0), only the non-empty buckets are listed.
+    pub buckets: Vec<Bucket>,
+    /// Entries without a parseable timestamp.
+    pub untimed: u64,
+}
+
+/// Buckets entries into fixed time intervals (1m, 5m, 1h, ...) by their extracted timestamp,
+/// optionally counting per value of a grouping field.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    interval_secs: i64,
+    max_buckets: usize,
+    group_by: Option<String>,
+    group_counts: BTreeMap<i64, BTreeMap<String, u64>>,
+    counts: BTreeMap<i64, u64>,
+    untimed: u64,
+}
+
+impl Histogram {
+    /// Creates a histogram with the given bucket width (rounded down to whole seconds, minimum 1s).
+    pub fn new(interval: Duration) -> Self {
+        Histogram {
+            interval_secs: (interval.as_secs() as i64).max(1),
+            max_buckets: DEFAULT_MAX_BUCKETS,
+            group_by: None,
+            group_counts: BTreeMap::new(),
+            counts: BTreeMap::new(),
+            untimed: 0,
+        }
+    }
+
+    /// Like [`Histogram::new`], but also counts per value of `field` in every bucket.
+    pub fn grouped(interval: Duration, field: impl Into<String>) -> Self {
+        Histogram { group_by: Some(field.into()), ..Histogram::new(interval) }
+    }
+
+    /// Caps how many buckets the result may span before empty ones are left out
+    /// (default [`DEFAULT_MAX_BUCKETS`]).
+    pub fn with_max_buckets(mut self, max_buckets: usize) -> Self {
+        self.max_buckets = max_buckets;
+        self
+    }
+
+    pub fn observe(&mut self, entry: &Value) {
+        let Some(ts) = time_parser::extract_and_parse_timestamp(entry) else {
+            self.untimed += 1;
+            return;
+        };
+        self.observe_at(ts, entry);
+    }
+
+    /// Records an entry at an already-extracted timestamp.
+    pub fn observe_at(&mut self, ts: DateTime<Utc>, entry: &Value) {
+        let interval = Duration::from_secs(self.interval_secs as u64);
+        let bucket = time_parser::round_to_bucket(ts, interval).timestamp();
+        *self.counts.entry(bucket).or_insert(0) += 1;
+        if let Some(field) = &self.group_by {
+            let group = key_value(entry, field);
+            *self.group_counts.entry(bucket).or_default().entry(group).or_insert(0) += 1;
+        }
+    }
+
+    /// Folds another histogram with the same interval into this one.
+    pub fn merge(&mut self, other: Histogram) {
+        for (bucket, count) in other.counts {
+            *self.counts.entry(bucket).or_insert(0) += count;
+        }
+        for (bucket, groups) in other.group_counts {
+            let mine = self.group_counts.entry(bucket).or_default();
+            for (group, count) in groups {
+                *mine.entry(group).or_insert(0) += count;
+            }
+        }
+        self.untimed += other.untimed;
+    }
+
+    pub fn result(&self) -> HistogramResult {
+        let bucket = |start: i64| Bucket {
+            start: Utc.timestamp_opt(start, 0).single().unwrap_or_default(),
+            count: self.counts.get(&start).copied().unwrap_or(0),
+            groups: self.group_counts.get(&start).cloned().unwrap_or_default(),
+        };
+        let mut buckets = Vec::new();
+        if let (Some(&first), Some(&last)) = (self.counts.keys().next(), self.counts.keys().next_back()) {
+            let span = (last - first) / self.interval_secs + 1;
+            if span <= self.max_buckets as i64 {
+                let mut start = first;
+                while start <= last {
+                    buckets.push(bucket(start));
+                    start += self.interval_secs;
+                }
+            } else {
+                buckets.extend(self.counts.keys().map(|&start| bucket(start)));
+            }
+        }
+
+        HistogramResult {
+            interval_secs: self.interval_secs as u64,
+            buckets,
+            untimed: self.untimed,
+        }
+    }
+}
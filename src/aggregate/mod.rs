@@ -0,0 +1,173 @@
+// File: src/aggregate/mod.rs
+
+pub mod distinct;
+pub mod group;
+pub mod histogram;
+pub mod top_k;
+
+use crate::query;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Summary statistics for one numeric field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    /// Entries observed, whether or not they carried a numeric value.
+    pub count: u64,
+    /// Entries whose field held a number (or a numeric string).
+    pub numeric_count: u64,
+    pub sum: f64,
+    pub avg: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub p50: Option<f64>,
+    pub p90: Option<f64>,
+    pub p99: Option<f64>,
+}
+
+/// Consumes matching entries and computes count, sum, avg, min/max and percentiles
+/// over a single numeric field.
+///
+/// Percentiles are exact (nearest-rank), so every numeric value is retained.
+#[derive(Debug, Clone)]
+pub struct Aggregator {
+    field: String,
+    count: u64,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+    values: Vec<f64>,
+}
+
+impl Aggregator {
+    /// Creates an aggregator over `field` (a key, dotted path, or JSON pointer).
+    pub fn new(field: impl Into<String>) -> Self {
+        Aggregator {
+            field: field.into(),
+            count: 0,
+            sum: 0.0,
+            min: None,
+            max: None,
+            values: Vec::new(),
+        }
+    }
+
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// Records one (already filtered) entry.
+    pub fn observe(&mut self, entry: &Value) {
+        self.count += 1;
+        if let Some(n) = query::resolve_field(entry, &self.field).and_then(numeric_value) {
+            self.record(n);
+        }
+    }
+
+    fn record(&mut self, n: f64) {
+        self.sum += n;
+        self.min = Some(self.min.map_or(n, |m| m.min(n)));
+        self.max = Some(self.max.map_or(n, |m| m.max(n)));
+        self.values.push(n);
+    }
+
+    /// Folds another aggregator's state into this one (e.g. from another thread).
+    pub fn merge(&mut self, other: Aggregator) {
+        self.count += other.count;
+        for n in other.values {
+            self.record(n);
+        }
+    }
+
+    pub fn stats(&self) -> Stats {
+        let mut sorted = self.values.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let numeric_count = sorted.len() as u64;
+
+        Stats {
+            count: self.count,
+            numeric_count,
+            sum: self.sum,
+            avg: (numeric_count > 0).then(|| self.sum / numeric_count as f64),
+            min: self.min,
+            max: self.max,
+            p50: percentile(&sorted, 50.0),
+            p90: percentile(&sorted, 90.0),
+            p99: percentile(&sorted, 99.0),
+        }
+    }
+}
+
+/// Aggregation state that can be built in pieces (e.g. one per thread) and recombined.
+pub trait Mergeable: Send {
+    fn observe(&mut self, entry: &Value);
+    fn merge(&mut self, other: Self);
+}
+
+impl Mergeable for Aggregator {
+    fn observe(&mut self, entry: &Value) {
+        Aggregator::observe(self, entry);
+    }
+
+    fn merge(&mut self, other: Self) {
+        Aggregator::merge(self, other);
+    }
+}
+
+impl Mergeable for group::GroupBy {
+    fn observe(&mut self, entry: &Value) {
+        group::GroupBy::observe(self, entry);
+    }
+
+    fn merge(&mut self, other: Self) {
+        group::GroupBy::merge(self, other);
+    }
+}
+
+impl Mergeable for histogram::Histogram {
+    fn observe(&mut self, entry: &Value) {
+        histogram::Histogram::observe(self, entry);
+    }
+
+    fn merge(&mut self, other: Self) {
+        histogram::Histogram::merge(self, other);
+    }
+}
+
+impl Mergeable for top_k::TopK {
+    fn observe(&mut self, entry: &Value) {
+        top_k::TopK::observe(self, entry);
+    }
+
+    fn merge(&mut self, other: Self) {
+        top_k::TopK::merge(self, other);
+    }
+}
+
+impl Mergeable for distinct::DistinctCount {
+    fn observe(&mut self, entry: &Value) {
+        distinct::DistinctCount::observe(self, entry);
+    }
+
+    fn merge(&mut self, other: Self) {
+        distinct::DistinctCount::merge(self, other);
+    }
+}
+
+/// Nearest-rank percentile over sorted values.
+pub fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+/// Reads a number, or a string holding one (logfmt values are untyped).
+pub(crate) fn numeric_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok().filter(|n| n.is_finite()),
+        _ => None,
+    }
+}
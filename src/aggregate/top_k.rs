@@ -0,0 +1,95 @@
+// File: src/aggregate/top_k.rs
+
+use super::group::key_value;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// How many counters are kept per requested result; more counters mean tighter estimates.
+const COUNTERS_PER_RESULT: usize = 10;
+
+/// One heavy hitter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopValue {
+    pub value: String,
+    /// Estimated occurrences. Never an underestimate.
+    pub count: u64,
+    /// Upper bound on how much `count` may overestimate the true count.
+    pub error: u64,
+}
+
+/// `top_k(field, k)`: the most frequent values of a field, tracked with the
+/// Space-Saving heavy-hitters algorithm so memory stays bounded regardless of
+/// the field's cardinality.
+#[derive(Debug, Clone)]
+pub struct TopK {
+    field: String,
+    k: usize,
+    capacity: usize,
+    counters: HashMap<String, (u64, u64)>,
+}
+
+impl TopK {
+    pub fn new(field: impl Into<String>, k: usize) -> Self {
+        let k = k.max(1);
+        TopK {
+            field: field.into(),
+            k,
+            capacity: k * COUNTERS_PER_RESULT,
+            counters: HashMap::new(),
+        }
+    }
+
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    pub fn observe(&mut self, entry: &Value) {
+        let value = key_value(entry, &self.field);
+        self.add(value, 1, 0);
+    }
+
+    fn add(&mut self, value: String, count: u64, error: u64) {
+        if let Some(counter) = self.counters.get_mut(&value) {
+            counter.0 += count;
+            counter.1 += error;
+            return;
+        }
+        if self.counters.len() < self.capacity {
+            self.counters.insert(value, (count, error));
+            return;
+        }
+
+        // Evict the smallest counter; the newcomer inherits its count as error bound.
+        let Some((evicted, (min_count, _))) = self
+            .counters
+            .iter()
+            .min_by_key(|(_, (c, _))| *c)
+            .map(|(v, counter)| (v.clone(), *counter))
+        else {
+            return;
+        };
+        self.counters.remove(&evicted);
+        self.counters.insert(value, (min_count + count, min_count + error));
+    }
+
+    /// Folds another tracker's counters into this one. The result stays an
+    /// approximation with the same guarantees.
+    pub fn merge(&mut self, other: TopK) {
+        for (value, (count, error)) in other.counters {
+            self.add(value, count, error);
+        }
+    }
+
+    /// The top `k` values, most frequent first.
+    pub fn result(&self) -> Vec<TopValue> {
+        let mut values: Vec<TopValue> = self
+            .counters
+            .iter()
+            .map(|(value, (count, error))| TopValue { value: value.clone(), count: *count, error: *error })
+            .collect();
+        values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+        values.truncate(self.k);
+        values
+    }
+}
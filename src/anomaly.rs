@@ -0,0 +1,161 @@
+// src/anomaly.rs
+
+use crate::aggregate::histogram::{Bucket, HistogramResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_SIGMA: f64 = 3.0;
+const DEFAULT_WINDOW: usize = 12;
+/// Buckets of history needed before a bucket can be judged.
+const DEFAULT_MIN_HISTORY: usize = 3;
+
+/// How the expected count for a bucket is derived from the buckets before it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Baseline {
+    /// Mean and standard deviation of the previous `window` buckets.
+    Rolling { window: usize },
+    /// Exponentially weighted mean and variance; larger `alpha` forgets faster.
+    Ewma { alpha: f64 },
+}
+
+/// A bucket whose count deviated from its baseline by more than the configured sigma.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Anomaly {
+    pub start: DateTime<Utc>,
+    /// The group the series belongs to, for grouped histograms.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub count: u64,
+    pub expected: f64,
+    /// Deviations from the baseline; positive for spikes, negative for drops.
+    pub score: f64,
+}
+
+/// Flags time buckets whose counts deviate from recent history, answering
+/// "when did error volume spike".
+///
+/// The deviation is measured in standard deviations of the baseline, floored at the
+/// Poisson noise of the expected count (and at 1), so a perfectly flat history
+/// doesn't turn every small wobble into an anomaly.
+#[derive(Debug, Clone)]
+pub struct AnomalyDetector {
+    baseline: Baseline,
+    sigma: f64,
+    min_history: usize,
+}
+
+impl Default for AnomalyDetector {
+    fn default() -> Self {
+        AnomalyDetector::new(Baseline::Rolling { window: DEFAULT_WINDOW })
+    }
+}
+
+impl AnomalyDetector {
+    pub fn new(baseline: Baseline) -> Self {
+        AnomalyDetector { baseline, sigma: DEFAULT_SIGMA, min_history: DEFAULT_MIN_HISTORY }
+    }
+
+    /// Deviations, in standard deviations, beyond which a bucket is flagged.
+    pub fn with_sigma(mut self, sigma: f64) -> Self {
+        self.sigma = sigma;
+        self
+    }
+
+    pub fn with_min_history(mut self, min_history: usize) -> Self {
+        self.min_history = min_history.max(1);
+        self
+    }
+
+    /// Scores every bucket of `counts` against the ones before it.
+    /// `None` while there is not enough history yet.
+    pub fn scores(&self, counts: &[u64]) -> Vec<Option<(f64, f64)>> {
+        match self.baseline {
+            Baseline::Rolling { window } => self.rolling_scores(counts, window.max(1)),
+            Baseline::Ewma { alpha } => self.ewma_scores(counts, alpha.clamp(f64::EPSILON, 1.0)),
+        }
+    }
+
+    /// Indices of the anomalous buckets in `counts`, with expected value and score.
+    pub fn detect(&self, counts: &[u64]) -> Vec<(usize, f64, f64)> {
+        self.scores(counts)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, score)| score.map(|(expected, score)| (i, expected, score)))
+            .filter(|(_, _, score)| score.abs() > self.sigma)
+            .collect()
+    }
+
+    /// Anomalies in a histogram's total counts and, when grouped, in every group's counts.
+    pub fn detect_histogram(&self, histogram: &HistogramResult) -> Vec<Anomaly> {
+        let mut anomalies = self.anomalies(histogram, None, |bucket| bucket.count);
+
+        let mut groups: Vec<&String> = histogram.buckets.iter().flat_map(|b| b.groups.keys()).collect();
+        groups.sort();
+        groups.dedup();
+        for group in groups {
+            let series = |bucket: &Bucket| bucket.groups.get(group).copied().unwrap_or(0);
+            anomalies.extend(self.anomalies(histogram, Some(group), series));
+        }
+        anomalies.sort_by(|a, b| a.start.cmp(&b.start).then_with(|| a.group.cmp(&b.group)));
+        anomalies
+    }
+
+    fn anomalies<F>(&self, histogram: &HistogramResult, group: Option<&String>, count: F) -> Vec<Anomaly>
+    where
+        F: Fn(&Bucket) -> u64,
+    {
+        let counts: Vec<u64> = histogram.buckets.iter().map(&count).collect();
+        self.detect(&counts)
+            .into_iter()
+            .map(|(i, expected, score)| Anomaly {
+                start: histogram.buckets[i].start,
+                group: group.cloned(),
+                count: counts[i],
+                expected,
+                score,
+            })
+            .collect()
+    }
+
+    fn rolling_scores(&self, counts: &[u64], window: usize) -> Vec<Option<(f64, f64)>> {
+        (0..counts.len())
+            .map(|i| {
+                let history = &counts[i.saturating_sub(window)..i];
+                if history.len() < self.min_history {
+                    return None;
+                }
+                let n = history.len() as f64;
+                let mean = history.iter().sum::<u64>() as f64 / n;
+                let variance = history.iter().map(|&c| (c as f64 - mean).powi(2)).sum::<f64>() / n;
+                Some((mean, score(counts[i], mean, variance)))
+            })
+            .collect()
+    }
+
+    fn ewma_scores(&self, counts: &[u64], alpha: f64) -> Vec<Option<(f64, f64)>> {
+        let mut mean = 0.0;
+        let mut variance = 0.0;
+        counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| {
+                let x = count as f64;
+                let result = (i >= self.min_history).then(|| (mean, score(count, mean, variance)));
+                if i == 0 {
+                    mean = x;
+                } else {
+                    let diff = x - mean;
+                    mean += alpha * diff;
+                    variance = (1.0 - alpha) * (variance + alpha * diff * diff);
+                }
+                result
+            })
+            .collect()
+    }
+}
+
+fn score(count: u64, mean: f64, variance: f64) -> f64 {
+    let stddev = variance.sqrt().max(mean.sqrt()).max(1.0);
+    (count as f64 - mean) / stddev
+}
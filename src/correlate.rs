@@ -0,0 +1,254 @@
+// src/correlate.rs
+
+use crate::level::Level;
+use crate::parsers::{Format, LogEntry};
+use crate::pipeline::{Mark, Record, Transform};
+use crate::query::{self, Query, QueryOptions};
+use crate::time as time_parser;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+
+/// Fields tried, in order, for the id tying entries together.
+const DEFAULT_KEYS: &[&str] = &["trace_id", "traceId", "trace.id", "request_id", "requestId", "x_request_id"];
+/// Fields naming the service an entry came from.
+const SERVICE_FIELDS: &[&str] = &["service", "service.name", "app", "component"];
+/// Traces held open at most; past it, the least recently active one is closed early.
+pub const DEFAULT_MAX_OPEN: usize = 10_000;
+
+/// What one trace (or request) looked like.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceSummary {
+    pub key: String,
+    pub first: Option<DateTime<Utc>>,
+    pub last: Option<DateTime<Utc>>,
+    /// Milliseconds between the first and last timestamped entry.
+    pub duration_ms: Option<i64>,
+    pub entries: u64,
+    /// Entries at level `error` or above, or with an HTTP status of 500 or more.
+    pub errors: u64,
+    pub services: BTreeSet<String>,
+}
+
+enum Mode {
+    Summaries,
+    Filter(Box<(Query, QueryOptions)>),
+}
+
+struct OpenTrace {
+    summary: TraceSummary,
+    first_line: usize,
+    /// Stream position of the latest entry, for idle detection and eviction.
+    last_active: u64,
+    records: Vec<Record>,
+    matched: bool,
+}
+
+/// Groups entries sharing a trace or request id, closing a group once no entry for it
+/// has been seen for the time window.
+///
+/// Two modes:
+/// - [`Correlate::summaries`] replaces entries with one [`TraceSummary`] record per trace.
+/// - [`Correlate::filter`] passes whole traces where any entry matches a query,
+///   e.g. every log line of the requests that hit an error. Add it with
+///   [`Pipeline::with_transform`](crate::pipeline::Pipeline::with_transform) and no
+///   pipeline query, so it sees every entry.
+///
+/// Window time follows the entries' own timestamps. Entries without an id pass
+/// through untouched in filter mode (if they match) and are dropped in summary mode.
+pub struct Correlate {
+    mode: Mode,
+    keys: Vec<String>,
+    window: chrono::Duration,
+    max_open: usize,
+    open: HashMap<String, OpenTrace>,
+    watermark: Option<DateTime<Utc>>,
+    next_sweep: Option<DateTime<Utc>>,
+    seen: u64,
+}
+
+impl Correlate {
+    /// Emits a summary record for every trace.
+    pub fn summaries(window: Duration) -> Self {
+        Correlate::with_mode(Mode::Summaries, window)
+    }
+
+    /// Emits every entry of the traces where at least one entry matches `query`.
+    pub fn filter(query: Query, window: Duration) -> Self {
+        Correlate::with_mode(Mode::Filter(Box::new((query, QueryOptions::default()))), window)
+    }
+
+    fn with_mode(mode: Mode, window: Duration) -> Self {
+        Correlate {
+            mode,
+            keys: DEFAULT_KEYS.iter().map(|k| k.to_string()).collect(),
+            window: chrono::Duration::from_std(window).unwrap_or(chrono::Duration::MAX),
+            max_open: DEFAULT_MAX_OPEN,
+            open: HashMap::new(),
+            watermark: None,
+            next_sweep: None,
+            seen: 0,
+        }
+    }
+
+    /// Reads the correlation id from these fields instead of the defaults.
+    pub fn with_keys(mut self, keys: Vec<String>) -> Self {
+        self.keys = keys;
+        self
+    }
+
+    /// Options for the filter query.
+    pub fn with_options(mut self, options: QueryOptions) -> Self {
+        if let Mode::Filter(filter) = &mut self.mode {
+            filter.1 = options;
+        }
+        self
+    }
+
+    pub fn set_max_open(&mut self, max_open: usize) {
+        self.max_open = max_open.max(1);
+    }
+
+    fn key_for(&self, entry: &Value) -> Option<String> {
+        self.keys.iter().find_map(|key| match query::resolve_field(entry, key)? {
+            Value::String(s) if !s.is_empty() && s != "-" => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
+    }
+
+    fn is_match(&self, record: &Record) -> bool {
+        match &self.mode {
+            Mode::Filter(filter) => {
+                let (query, options) = filter.as_ref();
+                record.entry.matches_with_options(query, options).unwrap_or(false)
+            }
+            Mode::Summaries => false,
+        }
+    }
+
+    /// Closes traces idle for longer than the window, at most a few times per window.
+    fn sweep(&mut self, out: &mut Vec<Record>) {
+        let Some(watermark) = self.watermark else { return };
+        if self.next_sweep.is_some_and(|next| watermark < next) {
+            return;
+        }
+        let step = (self.window / 4).max(chrono::Duration::seconds(1));
+        self.next_sweep = watermark.checked_add_signed(step);
+
+        let idle: Vec<String> = self
+            .open
+            .iter()
+            .filter(|(_, trace)| {
+                trace.summary.last.is_some_and(|last| {
+                    last.checked_add_signed(self.window).is_some_and(|end| end < watermark)
+                })
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        self.close(idle, out);
+    }
+
+    /// Emits the given traces in the order they started.
+    fn close(&mut self, keys: Vec<String>, out: &mut Vec<Record>) {
+        let mut traces: Vec<OpenTrace> = keys.iter().filter_map(|key| self.open.remove(key)).collect();
+        traces.sort_by_key(|trace| trace.first_line);
+        for trace in traces {
+            match self.mode {
+                Mode::Summaries => out.push(summary_record(trace)),
+                Mode::Filter(_) if trace.matched => out.extend(trace.records),
+                Mode::Filter(_) => {}
+            }
+        }
+    }
+}
+
+impl Transform for Correlate {
+    fn process(&mut self, record: Record, out: &mut Vec<Record>) {
+        self.seen += 1;
+        let timestamp = time_parser::extract_and_parse_timestamp(record.value());
+        if let Some(ts) = timestamp {
+            self.watermark = Some(self.watermark.map_or(ts, |w| w.max(ts)));
+        }
+
+        let Some(key) = self.key_for(record.value()) else {
+            if self.is_match(&record) {
+                out.push(record);
+            }
+            self.sweep(out);
+            return;
+        };
+
+        if !self.open.contains_key(&key) && self.open.len() >= self.max_open {
+            let evicted = self.open.iter().min_by_key(|(_, trace)| trace.last_active).map(|(k, _)| k.clone());
+            self.close(evicted.into_iter().collect(), out);
+        }
+
+        let matched = self.is_match(&record);
+        let keep_records = matches!(self.mode, Mode::Filter(_));
+        let seen = self.seen;
+        let trace = self.open.entry(key.clone()).or_insert_with(|| OpenTrace {
+            summary: TraceSummary {
+                key,
+                first: None,
+                last: None,
+                duration_ms: None,
+                entries: 0,
+                errors: 0,
+                services: BTreeSet::new(),
+            },
+            first_line: record.line_number,
+            last_active: seen,
+            records: Vec::new(),
+            matched: false,
+        });
+
+        observe(&mut trace.summary, record.value(), timestamp);
+        trace.last_active = seen;
+        trace.matched |= matched;
+        if keep_records {
+            trace.records.push(record);
+        }
+        self.sweep(out);
+    }
+
+    fn finish(&mut self, out: &mut Vec<Record>) {
+        let keys: Vec<String> = self.open.keys().cloned().collect();
+        self.close(keys, out);
+    }
+}
+
+fn observe(summary: &mut TraceSummary, entry: &Value, timestamp: Option<DateTime<Utc>>) {
+    summary.entries += 1;
+    if let Some(ts) = timestamp {
+        summary.first = Some(summary.first.map_or(ts, |first| first.min(ts)));
+        summary.last = Some(summary.last.map_or(ts, |last| last.max(ts)));
+    }
+    if let (Some(first), Some(last)) = (summary.first, summary.last) {
+        summary.duration_ms = Some((last - first).num_milliseconds());
+    }
+    if is_error(entry) {
+        summary.errors += 1;
+    }
+    if let Some(service) = SERVICE_FIELDS.iter().find_map(|field| query::resolve_field(entry, field)?.as_str()) {
+        summary.services.insert(service.to_string());
+    }
+}
+
+fn is_error(entry: &Value) -> bool {
+    let level = ["level", "severity"].iter().find_map(|key| Level::from_value(entry.get(key)?));
+    let status = entry.get("status").and_then(crate::aggregate::numeric_value);
+    level.is_some_and(|level| level >= Level::Error) || status.is_some_and(|status| status >= 500.0)
+}
+
+fn summary_record(trace: OpenTrace) -> Record {
+    let value = serde_json::to_value(&trace.summary).unwrap_or_default();
+    let raw = value.to_string();
+    Record {
+        line_number: trace.first_line,
+        entry: LogEntry::structured(value, raw, Format::Json).with_line_number(trace.first_line),
+        mark: Mark::Match,
+    }
+}
@@ -0,0 +1,658 @@
+// File: src/dialect/jq.rs
+
+use crate::pipeline::{Record, Transform};
+use crate::query::QueryError;
+use regex::Regex;
+use serde_json::{Map, Number, Value};
+use std::cmp::Ordering;
+
+/// A compiled jq expression, for projections (`.spans[] | select(.duration > 100)`)
+/// and conditions over structured entries.
+///
+/// Supported: `.`, `.a.b`, `."key"`, `.[0]`, `.[-1]`, `.["key"]`, `.[]`, `|`, `,`,
+/// literals, `[...]` and `{key: f, key}` construction, `== != < <= > >=` (with jq's
+/// cross-type ordering), `and`, `or`, parentheses, and the builtins `select`, `map`,
+/// `not`, `empty`, `length`, `keys`, `has`, `test`, `startswith`, `endswith`,
+/// `contains`, `ascii_downcase`, `ascii_upcase`, `tostring` and `tonumber`.
+///
+/// Type errors never abort: `.a` on a non-object and `.[]` on a scalar simply yield
+/// nothing, as if every step carried jq's `?`.
+#[derive(Debug, Clone)]
+pub struct JqFilter {
+    filter: Filter,
+}
+
+#[derive(Debug, Clone)]
+enum Filter {
+    Identity,
+    Literal(Value),
+    Field(Box<Filter>, String),
+    Index(Box<Filter>, i64),
+    Iterate(Box<Filter>),
+    Pipe(Box<Filter>, Box<Filter>),
+    Comma(Box<Filter>, Box<Filter>),
+    Compare(Box<Filter>, &'static str, Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Array(Option<Box<Filter>>),
+    Object(Vec<(String, Filter)>),
+    Call(Builtin, Vec<Filter>),
+}
+
+#[derive(Debug, Clone)]
+enum Builtin {
+    Select,
+    Map,
+    Not,
+    Empty,
+    Length,
+    Keys,
+    Has,
+    /// Compiled once when the pattern is a literal.
+    Test(Option<Regex>),
+    StartsWith,
+    EndsWith,
+    Contains,
+    AsciiDowncase,
+    AsciiUpcase,
+    ToString,
+    ToNumber,
+}
+
+/// Name and argument count of every builtin.
+const BUILTINS: &[(&str, usize)] = &[
+    ("select", 1),
+    ("map", 1),
+    ("not", 0),
+    ("empty", 0),
+    ("length", 0),
+    ("keys", 0),
+    ("has", 1),
+    ("test", 1),
+    ("startswith", 1),
+    ("endswith", 1),
+    ("contains", 1),
+    ("ascii_downcase", 0),
+    ("ascii_upcase", 0),
+    ("tostring", 0),
+    ("tonumber", 0),
+];
+
+impl JqFilter {
+    pub fn parse(expr: &str) -> Result<Self, QueryError> {
+        let mut parser = Parser { tokens: tokenize(expr)?, pos: 0 };
+        let filter = parser.pipe()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(JqFilter { filter }),
+            Some(token) => Err(error(format!("Unexpected {:?} in jq expression '{}'", token, expr))),
+        }
+    }
+
+    /// Every value the expression yields for `input`.
+    pub fn apply(&self, input: &Value) -> Vec<Value> {
+        let mut out = Vec::new();
+        self.filter.eval(input, &mut out);
+        out
+    }
+
+    /// True when the expression yields at least one value other than `false` / `null`.
+    pub fn matches(&self, input: &Value) -> bool {
+        self.apply(input).iter().any(truthy)
+    }
+}
+
+/// Replaces each record's value with every value the expression yields, one record
+/// per output; records yielding nothing are dropped. Unstructured lines are `null`.
+impl Transform for JqFilter {
+    fn process(&mut self, record: Record, out: &mut Vec<Record>) {
+        for value in self.apply(record.value()) {
+            let mut projected = record.clone();
+            projected.entry.set_value(value);
+            out.push(projected);
+        }
+    }
+}
+
+fn error(message: impl Into<String>) -> QueryError {
+    QueryError::InvalidFormat(message.into())
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+impl Filter {
+    fn eval(&self, input: &Value, out: &mut Vec<Value>) {
+        match self {
+            Filter::Identity => out.push(input.clone()),
+            Filter::Literal(value) => out.push(value.clone()),
+            Filter::Field(base, name) => {
+                for value in base.outputs(input) {
+                    match value {
+                        Value::Object(mut map) => out.push(map.remove(name).unwrap_or(Value::Null)),
+                        Value::Null => out.push(Value::Null),
+                        _ => {}
+                    }
+                }
+            }
+            Filter::Index(base, index) => {
+                for value in base.outputs(input) {
+                    match value {
+                        Value::Array(items) => {
+                            let at = if *index < 0 { items.len() as i64 + index } else { *index };
+                            let item = usize::try_from(at).ok().and_then(|at| items.get(at));
+                            out.push(item.cloned().unwrap_or(Value::Null));
+                        }
+                        Value::Null => out.push(Value::Null),
+                        _ => {}
+                    }
+                }
+            }
+            Filter::Iterate(base) => {
+                for value in base.outputs(input) {
+                    match value {
+                        Value::Array(items) => out.extend(items),
+                        Value::Object(map) => out.extend(map.into_iter().map(|(_, v)| v)),
+                        _ => {}
+                    }
+                }
+            }
+            Filter::Pipe(left, right) => {
+                for value in left.outputs(input) {
+                    right.eval(&value, out);
+                }
+            }
+            Filter::Comma(left, right) => {
+                left.eval(input, out);
+                right.eval(input, out);
+            }
+            Filter::Compare(left, op, right) => {
+                // Every combination of outputs, left side varying fastest (as in jq)
+                for r in right.outputs(input) {
+                    for l in left.outputs(input) {
+                        let ordering = compare(&l, &r);
+                        let result = match *op {
+                            "==" => ordering == Ordering::Equal,
+                            "!=" => ordering != Ordering::Equal,
+                            "<" => ordering == Ordering::Less,
+                            "<=" => ordering != Ordering::Greater,
+                            ">" => ordering == Ordering::Greater,
+                            _ => ordering != Ordering::Less,
+                        };
+                        out.push(Value::Bool(result));
+                    }
+                }
+            }
+            Filter::And(left, right) => {
+                for l in left.outputs(input) {
+                    if !truthy(&l) {
+                        out.push(Value::Bool(false));
+                        continue;
+                    }
+                    out.extend(right.outputs(input).iter().map(|r| Value::Bool(truthy(r))));
+                }
+            }
+            Filter::Or(left, right) => {
+                for l in left.outputs(input) {
+                    if truthy(&l) {
+                        out.push(Value::Bool(true));
+                        continue;
+                    }
+                    out.extend(right.outputs(input).iter().map(|r| Value::Bool(truthy(r))));
+                }
+            }
+            Filter::Array(inner) => {
+                out.push(Value::Array(inner.as_ref().map(|f| f.outputs(input)).unwrap_or_default()));
+            }
+            Filter::Object(entries) => {
+                let mut objects = vec![Map::new()];
+                for (key, filter) in entries {
+                    let values = filter.outputs(input);
+                    objects = objects
+                        .into_iter()
+                        .flat_map(|object| {
+                            values.iter().map(move |value| {
+                                let mut object = object.clone();
+                                object.insert(key.clone(), value.clone());
+                                object
+                            })
+                        })
+                        .collect();
+                }
+                out.extend(objects.into_iter().map(Value::Object));
+            }
+            Filter::Call(builtin, args) => builtin.eval(args, input, out),
+        }
+    }
+
+    fn outputs(&self, input: &Value) -> Vec<Value> {
+        let mut out = Vec::new();
+        self.eval(input, &mut out);
+        out
+    }
+}
+
+impl Builtin {
+    fn parse(name: &str, args: &[Filter]) -> Result<Builtin, QueryError> {
+        Ok(match name {
+            "select" => Builtin::Select,
+            "map" => Builtin::Map,
+            "not" => Builtin::Not,
+            "empty" => Builtin::Empty,
+            "length" => Builtin::Length,
+            "keys" => Builtin::Keys,
+            "has" => Builtin::Has,
+            "test" => Builtin::Test(match &args[0] {
+                Filter::Literal(Value::String(pattern)) => Some(
+                    Regex::new(pattern).map_err(|e| error(format!("Invalid regex '{}': {}", pattern, e)))?,
+                ),
+                _ => None,
+            }),
+            "startswith" => Builtin::StartsWith,
+            "endswith" => Builtin::EndsWith,
+            "contains" => Builtin::Contains,
+            "ascii_downcase" => Builtin::AsciiDowncase,
+            "ascii_upcase" => Builtin::AsciiUpcase,
+            "tostring" => Builtin::ToString,
+            _ => Builtin::ToNumber,
+        })
+    }
+
+    fn eval(&self, args: &[Filter], input: &Value, out: &mut Vec<Value>) {
+        // Builtins taking a value use every output of their argument
+        let with_arg = |out: &mut Vec<Value>, f: &dyn Fn(&Value) -> Option<Value>| {
+            out.extend(args[0].outputs(input).iter().filter_map(f));
+        };
+        match self {
+            Builtin::Select => {
+                if args[0].outputs(input).iter().any(truthy) {
+                    out.push(input.clone());
+                }
+            }
+            Builtin::Map => {
+                if let Value::Array(items) = input {
+                    out.push(Value::Array(items.iter().flat_map(|item| args[0].outputs(item)).collect()));
+                }
+            }
+            Builtin::Not => out.push(Value::Bool(!truthy(input))),
+            Builtin::Empty => {}
+            Builtin::Length => out.extend(match input {
+                Value::Null => Some(Value::from(0)),
+                Value::String(s) => Some(Value::from(s.chars().count())),
+                Value::Array(items) => Some(Value::from(items.len())),
+                Value::Object(map) => Some(Value::from(map.len())),
+                Value::Number(n) => n.as_f64().and_then(|n| Number::from_f64(n.abs())).map(Value::Number),
+                Value::Bool(_) => None,
+            }),
+            Builtin::Keys => out.extend(match input {
+                Value::Object(map) => {
+                    let mut keys: Vec<&String> = map.keys().collect();
+                    keys.sort();
+                    Some(Value::Array(keys.into_iter().map(|k| Value::from(k.as_str())).collect()))
+                }
+                Value::Array(items) => Some(Value::Array((0..items.len()).map(Value::from).collect())),
+                _ => None,
+            }),
+            Builtin::Has => with_arg(out, &|key| match (input, key) {
+                (Value::Object(map), Value::String(key)) => Some(Value::Bool(map.contains_key(key))),
+                (Value::Array(items), Value::Number(n)) => {
+                    Some(Value::Bool(n.as_u64().is_some_and(|i| (i as usize) < items.len())))
+                }
+                _ => None,
+            }),
+            Builtin::Test(compiled) => with_arg(out, &|pattern| {
+                let Value::String(text) = input else { return None };
+                match compiled {
+                    Some(regex) => Some(Value::Bool(regex.is_match(text))),
+                    None => Regex::new(pattern.as_str()?).ok().map(|regex| Value::Bool(regex.is_match(text))),
+                }
+            }),
+            Builtin::StartsWith | Builtin::EndsWith => with_arg(out, &|affix| match (input, affix) {
+                (Value::String(s), Value::String(affix)) => Some(Value::Bool(match self {
+                    Builtin::StartsWith => s.starts_with(affix.as_str()),
+                    _ => s.ends_with(affix.as_str()),
+                })),
+                _ => None,
+            }),
+            Builtin::Contains => with_arg(out, &|needle| Some(Value::Bool(contains(input, needle)))),
+            Builtin::AsciiDowncase | Builtin::AsciiUpcase => {
+                if let Value::String(s) = input {
+                    out.push(Value::from(match self {
+                        Builtin::AsciiDowncase => s.to_ascii_lowercase(),
+                        _ => s.to_ascii_uppercase(),
+                    }));
+                }
+            }
+            Builtin::ToString => out.push(match input {
+                Value::String(_) => input.clone(),
+                other => Value::from(other.to_string()),
+            }),
+            Builtin::ToNumber => out.extend(match input {
+                Value::Number(_) => Some(input.clone()),
+                Value::String(s) => s.trim().parse::<f64>().ok().and_then(Number::from_f64).map(Value::Number),
+                _ => None,
+            }),
+        }
+    }
+}
+
+/// jq's `contains`: substrings for strings, recursive containment for arrays and objects.
+fn contains(haystack: &Value, needle: &Value) -> bool {
+    match (haystack, needle) {
+        (Value::String(h), Value::String(n)) => h.contains(n.as_str()),
+        (Value::Array(h), Value::Array(n)) => n.iter().all(|n| h.iter().any(|h| contains(h, n))),
+        (Value::Object(h), Value::Object(n)) => n.iter().all(|(k, n)| h.get(k).is_some_and(|h| contains(h, n))),
+        _ => compare(haystack, needle) == Ordering::Equal,
+    }
+}
+
+/// jq's total order: null < false < true < numbers < strings < arrays < objects.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) => 4,
+            Value::Array(_) => 5,
+            Value::Object(_) => 6,
+        }
+    }
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => {
+            x.as_f64().unwrap_or(0.0).partial_cmp(&y.as_f64().unwrap_or(0.0)).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => x
+            .iter()
+            .zip(y)
+            .map(|(x, y)| compare(x, y))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        (Value::Object(x), Value::Object(y)) => {
+            let mut xk: Vec<&String> = x.keys().collect();
+            let mut yk: Vec<&String> = y.keys().collect();
+            xk.sort();
+            yk.sort();
+            xk.cmp(&yk).then_with(|| {
+                xk.iter().map(|k| compare(&x[*k], &y[*k])).find(|o| o.is_ne()).unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// `.` on its own.
+    Dot,
+    /// `.name` or `."name"`.
+    Field(String),
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Op(&'static str),
+}
+
+/// Longer operators first.
+const OPERATORS: &[&str] = &["==", "!=", "<=", ">=", "<", ">", "|", ",", "(", ")", "[", "]", "{", "}", ":", ";", "?"];
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut rest = expr.trim_start();
+    let ident_len = |s: &str| s.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(s.len());
+    while let Some(c) = rest.chars().next() {
+        if c == '.' {
+            let after = &rest[1..];
+            if after.starts_with('"') {
+                let (name, len) = lex_string(after)?;
+                tokens.push(Token::Field(name));
+                rest = &after[len..];
+            } else if after.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+                let len = ident_len(after);
+                tokens.push(Token::Field(after[..len].to_string()));
+                rest = &after[len..];
+            } else {
+                tokens.push(Token::Dot);
+                rest = after;
+            }
+        } else if c == '"' {
+            let (text, len) = lex_string(rest)?;
+            tokens.push(Token::Str(text));
+            rest = &rest[len..];
+        } else if c.is_ascii_digit() || (c == '-' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) {
+            let len = 1 + rest[1..].find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len() - 1);
+            let number = rest[..len].parse().map_err(|_| error(format!("Invalid number '{}'", &rest[..len])))?;
+            tokens.push(Token::Num(number));
+            rest = &rest[len..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = ident_len(rest);
+            tokens.push(Token::Ident(rest[..len].to_string()));
+            rest = &rest[len..];
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else {
+            return Err(error(format!("Unexpected '{}' in jq expression", c)));
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+/// A JSON-style string literal; returns it and its length in `s`.
+fn lex_string(s: &str) -> Result<(String, usize), QueryError> {
+    let mut escaped = false;
+    for (i, c) in s.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => {
+                let text = serde_json::from_str(&s[..=i]).map_err(|e| error(format!("Invalid string: {}", e)))?;
+                return Ok((text, i + 1));
+            }
+            _ => {}
+        }
+    }
+    Err(error("Unterminated string in jq expression"))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Op(o)) if *o == op);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), QueryError> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            Err(error(format!("Expected '{}' in jq expression", op)))
+        }
+    }
+
+    fn eat_ident(&mut self, name: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Ident(i)) if i == name);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn pipe(&mut self) -> Result<Filter, QueryError> {
+        let left = self.comma()?;
+        if self.eat("|") {
+            return Ok(Filter::Pipe(Box::new(left), Box::new(self.pipe()?)));
+        }
+        Ok(left)
+    }
+
+    fn comma(&mut self) -> Result<Filter, QueryError> {
+        let mut left = self.or()?;
+        while self.eat(",") {
+            left = Filter::Comma(Box::new(left), Box::new(self.or()?));
+        }
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Filter, QueryError> {
+        let mut left = self.and()?;
+        while self.eat_ident("or") {
+            left = Filter::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Filter, QueryError> {
+        let mut left = self.comparison()?;
+        while self.eat_ident("and") {
+            left = Filter::And(Box::new(left), Box::new(self.comparison()?));
+        }
+        Ok(left)
+    }
+
+    fn comparison(&mut self) -> Result<Filter, QueryError> {
+        let left = self.postfix()?;
+        let op = match self.peek() {
+            Some(Token::Op(op @ ("==" | "!=" | "<" | "<=" | ">" | ">="))) => *op,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        Ok(Filter::Compare(Box::new(left), op, Box::new(self.postfix()?)))
+    }
+
+    /// A term followed by `.name`, `[index]`, `[]` and `?` suffixes.
+    fn postfix(&mut self) -> Result<Filter, QueryError> {
+        let mut filter = self.term()?;
+        loop {
+            match self.peek() {
+                Some(Token::Field(name)) => {
+                    filter = Filter::Field(Box::new(filter), name.clone());
+                    self.pos += 1;
+                }
+                Some(Token::Op("[")) => {
+                    self.pos += 1;
+                    filter = self.index(filter)?;
+                }
+                Some(Token::Op("?")) => self.pos += 1,
+                _ => return Ok(filter),
+            }
+        }
+    }
+
+    /// The inside of `[...]` after `base`.
+    fn index(&mut self, base: Filter) -> Result<Filter, QueryError> {
+        let filter = match self.peek().cloned() {
+            Some(Token::Op("]")) => Filter::Iterate(Box::new(base)),
+            Some(Token::Num(n)) if n.fract() == 0.0 => {
+                self.pos += 1;
+                Filter::Index(Box::new(base), n as i64)
+            }
+            Some(Token::Str(key)) => {
+                self.pos += 1;
+                Filter::Field(Box::new(base), key)
+            }
+            _ => return Err(error("Only [], [number] and [\"key\"] indexes are supported in jq expressions")),
+        };
+        self.expect("]")?;
+        Ok(filter)
+    }
+
+    fn term(&mut self) -> Result<Filter, QueryError> {
+        let token = self.peek().cloned().ok_or_else(|| error("Unexpected end of jq expression"))?;
+        self.pos += 1;
+        Ok(match token {
+            Token::Dot => Filter::Identity,
+            Token::Field(name) => Filter::Field(Box::new(Filter::Identity), name),
+            Token::Str(s) => Filter::Literal(Value::from(s)),
+            Token::Num(n) => Filter::Literal(number(n)),
+            Token::Op("(") => {
+                let inner = self.pipe()?;
+                self.expect(")")?;
+                inner
+            }
+            Token::Op("[") => {
+                if self.eat("]") {
+                    return Ok(Filter::Array(None));
+                }
+                let inner = self.pipe()?;
+                self.expect("]")?;
+                Filter::Array(Some(Box::new(inner)))
+            }
+            Token::Op("{") => self.object()?,
+            Token::Ident(name) => match name.as_str() {
+                "true" => Filter::Literal(Value::Bool(true)),
+                "false" => Filter::Literal(Value::Bool(false)),
+                "null" => Filter::Literal(Value::Null),
+                _ => self.call(&name)?,
+            },
+            other => return Err(error(format!("Unexpected {:?} in jq expression", other))),
+        })
+    }
+
+    fn call(&mut self, name: &str) -> Result<Filter, QueryError> {
+        let Some(&(_, arity)) = BUILTINS.iter().find(|(builtin, _)| *builtin == name) else {
+            return Err(error(format!("Unsupported jq function '{}'", name)));
+        };
+        let mut args = Vec::new();
+        if self.eat("(") {
+            args.push(self.pipe()?);
+            while self.eat(";") {
+                args.push(self.pipe()?);
+            }
+            self.expect(")")?;
+        }
+        if args.len() != arity {
+            return Err(error(format!("jq function '{}' takes {} argument(s)", name, arity)));
+        }
+        Ok(Filter::Call(Builtin::parse(name, &args)?, args))
+    }
+
+    /// `{key: filter, "key": filter, key}` after the opening brace.
+    fn object(&mut self) -> Result<Filter, QueryError> {
+        let mut entries = Vec::new();
+        if self.eat("}") {
+            return Ok(Filter::Object(entries));
+        }
+        loop {
+            let key = match self.peek().cloned() {
+                Some(Token::Ident(key) | Token::Str(key)) => key,
+                _ => return Err(error("Expected a key in jq object construction")),
+            };
+            self.pos += 1;
+            // `{key}` is shorthand for `{key: .key}`
+            let value = if self.eat(":") {
+                self.or()?
+            } else {
+                Filter::Field(Box::new(Filter::Identity), key.clone())
+            };
+            entries.push((key, value));
+            if self.eat("}") {
+                return Ok(Filter::Object(entries));
+            }
+            self.expect(",")?;
+        }
+    }
+}
+
+/// Integers stay integers so projections print `100`, not `100.0`.
+fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < 9e15 {
+        Value::from(n as i64)
+    } else {
+        Number::from_f64(n).map_or(Value::Null, Value::Number)
+    }
+}
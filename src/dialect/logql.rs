@@ -0,0 +1,499 @@
+// File: src/dialect/logql.rs
+
+use super::{condition, quote};
+use crate::parsers::ansi::AnsiMode;
+use crate::parsers::{self, Format, LogEntry};
+use crate::pipeline::{Pipeline, Record, Transform};
+use crate::query::{self, Expr, Operator, Query, QueryError};
+use crate::transform::extract::Extract;
+use crate::transform::insert_fields;
+use crate::transform::remap::Remap;
+use crate::transform::select::Select;
+use serde_json::Value;
+
+/// Compiles a LogQL log query (Grafana Loki) onto a [`Pipeline`]:
+///
+/// - `{app="api", env!~"dev.*"}` stream selectors, matched against fields
+/// - line filters `|= "text"`, `!= "text"`, `|~ "regex"`, `!~ "regex"` (case-sensitive)
+/// - parsers `| json`, `| logfmt`, `| regexp "(?P<name>..)"`, `| pattern "<ip> - <_>"`
+///   and `| decolorize`
+/// - label filters `| status >= 500 and level = "error"`, with `or`, `,` and parentheses
+/// - `| line_format "{{.path}} {{.status}}"`, `| label_format dst=src`, `| drop a, b`
+///   and `| keep a, b`
+///
+/// Filters up to the first formatting stage become the pipeline's query; later ones
+/// run in order after it, so they see the reformatted line and renamed fields.
+/// Metric queries (`rate(...)`, `| unwrap`) are not supported.
+pub fn parse(query: &str) -> Result<Pipeline, QueryError> {
+    let mut parser = Parser { tokens: tokenize(query)?, pos: 0 };
+    let mut pipeline = Pipeline::new();
+    let mut filters = Vec::new();
+    let mut formatted = false;
+
+    if parser.peek() == Some(&Token::Punct('{')) {
+        parser.pos += 1;
+        filters.extend(parser.selector()?);
+    }
+    while let Some(token) = parser.next() {
+        let stage = match token {
+            Token::Op(op @ ("|=" | "!=" | "|~" | "!~")) => {
+                let text = parser.string("a line filter")?;
+                let (op, pattern) = match op {
+                    "|=" => (Operator::Matches, regex::escape(&text)),
+                    "!=" => (Operator::NotMatches, regex::escape(&text)),
+                    "|~" => (Operator::Matches, text),
+                    _ => (Operator::NotMatches, text),
+                };
+                Stage::Filter(condition("text", op, format!("/{}/", pattern))?)
+            }
+            Token::Op("|") => parser.stage()?,
+            other => return Err(error(format!("Expected '|' before {}", other.describe()))),
+        };
+        match stage {
+            Stage::Filter(expr) if !formatted => filters.push(expr),
+            Stage::Filter(expr) => {
+                let query = Query::from_expr(expr);
+                pipeline = pipeline.with_post_filter(move |record: Record| {
+                    record.entry.matches(&query).unwrap_or(false).then_some(record)
+                });
+            }
+            Stage::Parser(format) if !formatted => {
+                pipeline = pipeline.with_parser(move |line| parse_as(line, format));
+            }
+            Stage::Parser(format) => pipeline = pipeline.with_post_filter(Reparse(format)),
+            Stage::Decolorize => pipeline = pipeline.with_ansi(AnsiMode::Strip),
+            Stage::Extract(extract) if !formatted => pipeline = pipeline.with_transform(extract),
+            Stage::Extract(extract) => pipeline = pipeline.with_post_filter(extract),
+            Stage::Format(stage) => {
+                formatted = true;
+                pipeline = pipeline.with_post_filter(stage);
+            }
+        }
+    }
+
+    let expr = match filters.len() {
+        0 => return Ok(pipeline),
+        1 => filters.remove(0),
+        _ => Expr::And(filters),
+    };
+    Ok(pipeline.with_query(Query::from_expr(expr)))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Op(&'static str),
+    Punct(char),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(w) => format!("'{}'", w),
+            Token::Str(s) => format!("\"{}\"", s),
+            Token::Op(op) => format!("'{}'", op),
+            Token::Punct(c) => format!("'{}'", c),
+        }
+    }
+}
+
+/// Longer operators first.
+const OPERATORS: &[&str] = &["|=", "|~", "!=", "!~", "=~", "==", ">=", "<=", "|", "=", ">", "<"];
+
+enum Stage {
+    Filter(Expr),
+    Parser(Format),
+    Decolorize,
+    Extract(Extract),
+    Format(Box<dyn Transform>),
+}
+
+fn error(message: impl Into<String>) -> QueryError {
+    QueryError::InvalidFormat(message.into())
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut rest = query.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else if matches!(c, '{' | '}' | '(' | ')' | ',') {
+            tokens.push(Token::Punct(c));
+            rest = &rest[1..];
+        } else if c == '"' || c == '`' {
+            let (text, len) = lex_string(rest)?;
+            tokens.push(Token::Str(text));
+            rest = &rest[len..];
+        } else {
+            let len = rest
+                .find(|c: char| c.is_whitespace() || "|!=~<>(){},\"`".contains(c))
+                .unwrap_or(rest.len());
+            if len == 0 {
+                return Err(error(format!("Unexpected '{}' in LogQL query", c)));
+            }
+            tokens.push(Token::Word(rest[..len].to_string()));
+            rest = &rest[len..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+/// A double-quoted string (Go escapes) or a raw backtick string; returns it and its
+/// length in `s`.
+fn lex_string(s: &str) -> Result<(String, usize), QueryError> {
+    let quote = s.as_bytes()[0] as char;
+    let mut out = String::new();
+    let mut chars = s.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            _ if c == quote => return Ok((out, i + 1)),
+            '\\' if quote == '"' => match chars.next().map(|(_, c)| c) {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some(c @ ('\\' | '"')) => out.push(c),
+                Some(c) => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                None => break,
+            },
+            _ => out.push(c),
+        }
+    }
+    Err(error("Unterminated string in LogQL query"))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn string(&mut self, context: &str) -> Result<String, QueryError> {
+        match self.next() {
+            Some(Token::Str(s)) => Ok(s),
+            _ => Err(error(format!("Expected a quoted string for {}", context))),
+        }
+    }
+
+    fn word(&mut self, context: &str) -> Result<String, QueryError> {
+        match self.next() {
+            Some(Token::Word(w)) => Ok(w),
+            _ => Err(error(format!("Expected a name for {}", context))),
+        }
+    }
+
+    /// Whether the next token ends the current stage.
+    fn at_stage_end(&self) -> bool {
+        matches!(self.peek(), None | Some(Token::Op("|" | "|=" | "!=" | "|~" | "!~")))
+    }
+
+    /// The matchers of `{...}`, after the opening brace.
+    fn selector(&mut self) -> Result<Vec<Expr>, QueryError> {
+        let mut matchers = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Punct('}')) => {
+                    self.pos += 1;
+                    return Ok(matchers);
+                }
+                Some(Token::Punct(',')) if !matchers.is_empty() => self.pos += 1,
+                _ => {
+                    let label = self.word("a stream selector")?;
+                    let op = match self.next() {
+                        Some(Token::Op(op @ ("=" | "!=" | "=~" | "!~"))) => op,
+                        _ => return Err(error(format!("Expected =, !=, =~ or !~ after '{}'", label))),
+                    };
+                    let value = self.string("a stream selector")?;
+                    matchers.push(label_condition(&label, op, &value)?);
+                }
+            }
+        }
+    }
+
+    /// The stage after a `|`.
+    fn stage(&mut self) -> Result<Stage, QueryError> {
+        let name = match self.peek() {
+            Some(Token::Word(name)) => name.clone(),
+            Some(Token::Punct('(')) => return Ok(Stage::Filter(self.label_or()?)),
+            _ => return Err(error("Expected a stage after '|'")),
+        };
+        // A name followed by a comparison is a label filter (`| level = "error"`), except
+        // that argument-less stages can be followed directly by a line filter.
+        let label_filter = match self.tokens.get(self.pos + 1) {
+            Some(Token::Op("|" | "|=" | "|~")) | None => false,
+            Some(Token::Op("!=" | "!~")) => !matches!(name.as_str(), "json" | "logfmt" | "decolorize"),
+            Some(Token::Op(_)) => true,
+            _ => false,
+        };
+        if label_filter {
+            return Ok(Stage::Filter(self.label_or()?));
+        }
+        self.pos += 1;
+        let stage = match name.as_str() {
+            "json" => Stage::Parser(Format::Json),
+            "logfmt" => Stage::Parser(Format::Logfmt),
+            "decolorize" => Stage::Decolorize,
+            "regexp" => Stage::Extract(Extract::new(None, &self.string("regexp")?)?),
+            "pattern" => Stage::Extract(Extract::new(None, &pattern_regex(&self.string("pattern")?))?),
+            "line_format" => Stage::Format(Box::new(LineFormat(parse_template(&self.string("line_format")?)?))),
+            "label_format" => Stage::Format(Box::new(self.label_format()?)),
+            "drop" => Stage::Format(Box::new(DropFields(self.names("drop")?))),
+            "keep" => Stage::Format(Box::new(Select::new(self.names("keep")?))),
+            _ => return Err(error(format!("Unsupported LogQL stage '{}'", name))),
+        };
+        if !self.at_stage_end() {
+            let next = self.peek().map(Token::describe).unwrap_or_default();
+            return Err(error(format!("Unexpected {} after '{}' (stage arguments are not supported)", next, name)));
+        }
+        Ok(stage)
+    }
+
+    fn names(&mut self, stage: &str) -> Result<Vec<String>, QueryError> {
+        let mut names = vec![self.word(stage)?];
+        while self.peek() == Some(&Token::Punct(',')) {
+            self.pos += 1;
+            names.push(self.word(stage)?);
+        }
+        Ok(names)
+    }
+
+    /// `dst=src` renames and `dst="{{.a}}-{{.b}}"` templates, comma-separated.
+    fn label_format(&mut self) -> Result<LabelFormat, QueryError> {
+        let mut renames = Remap::new();
+        let mut templates = Vec::new();
+        loop {
+            let target = self.word("label_format")?;
+            if self.next() != Some(Token::Op("=")) {
+                return Err(error(format!("Expected '=' after '{}' in label_format", target)));
+            }
+            match self.next() {
+                Some(Token::Word(source)) => renames = renames.rename(source, target),
+                Some(Token::Str(template)) => templates.push((target, parse_template(&template)?)),
+                _ => return Err(error("Expected a label or a template in label_format")),
+            }
+            if self.peek() != Some(&Token::Punct(',')) {
+                return Ok(LabelFormat { renames, templates });
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn label_or(&mut self) -> Result<Expr, QueryError> {
+        let mut clauses = vec![self.label_and()?];
+        while self.peek().is_some_and(|t| matches!(t, Token::Word(w) if w.eq_ignore_ascii_case("or"))) {
+            self.pos += 1;
+            clauses.push(self.label_and()?);
+        }
+        Ok(if clauses.len() == 1 { clauses.remove(0) } else { Expr::Or(clauses) })
+    }
+
+    fn label_and(&mut self) -> Result<Expr, QueryError> {
+        let mut clauses = vec![self.label_primary()?];
+        loop {
+            match self.peek() {
+                Some(Token::Word(w)) if w.eq_ignore_ascii_case("and") => self.pos += 1,
+                Some(Token::Punct(',')) => self.pos += 1,
+                _ => break,
+            }
+            clauses.push(self.label_primary()?);
+        }
+        Ok(if clauses.len() == 1 { clauses.remove(0) } else { Expr::And(clauses) })
+    }
+
+    fn label_primary(&mut self) -> Result<Expr, QueryError> {
+        if self.peek() == Some(&Token::Punct('(')) {
+            self.pos += 1;
+            let expr = self.label_or()?;
+            if self.next() != Some(Token::Punct(')')) {
+                return Err(error("Missing ')' in LogQL label filter"));
+            }
+            return Ok(expr);
+        }
+        let label = self.word("a label filter")?;
+        let op = match self.next() {
+            Some(Token::Op(op)) if op != "|" => op,
+            _ => return Err(error(format!("Expected a comparison after '{}'", label))),
+        };
+        let value = match self.next() {
+            Some(Token::Str(s)) => s,
+            Some(Token::Word(w)) => w,
+            _ => return Err(error(format!("Expected a value after '{} {}'", label, op))),
+        };
+        label_condition(&label, op, &value)
+    }
+}
+
+/// A label matcher or filter as a condition; regexes match the whole value, as in Loki.
+fn label_condition(label: &str, op: &str, value: &str) -> Result<Expr, QueryError> {
+    let (op, value) = match op {
+        "=" | "==" => (Operator::Eq, quote(value)),
+        "!=" => (Operator::Ne, quote(value)),
+        "=~" => (Operator::Matches, format!("/^(?:{})$/", value)),
+        "!~" => (Operator::NotMatches, format!("/^(?:{})$/", value)),
+        ">" => (Operator::Gt, quote(value)),
+        ">=" => (Operator::Ge, quote(value)),
+        "<" => (Operator::Lt, quote(value)),
+        "<=" => (Operator::Le, quote(value)),
+        _ => return Err(error(format!("Unsupported label operator '{}'", op))),
+    };
+    condition(label, op, value)
+}
+
+/// Loki's pattern syntax as a regex: `<name>` captures, `<_>` skips, the rest is literal.
+fn pattern_regex(pattern: &str) -> String {
+    let mut out = String::from("^");
+    let mut rest = pattern;
+    while let Some(open) = rest.find('<') {
+        let Some(close) = rest[open..].find('>').map(|close| open + close) else { break };
+        let name = &rest[open + 1..close];
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            out.push_str(&regex::escape(&rest[..=close]));
+        } else {
+            out.push_str(&regex::escape(&rest[..open]));
+            let greedy = if close + 1 == rest.len() { "" } else { "?" };
+            match name {
+                "_" => out.push_str(&format!(".*{}", greedy)),
+                _ => out.push_str(&format!("(?P<{}>.*{})", name, greedy)),
+            }
+        }
+        rest = &rest[close + 1..];
+    }
+    out.push_str(&regex::escape(rest));
+    out
+}
+
+fn parse_as(line: &str, format: Format) -> LogEntry {
+    parsers::parse_log_line_as(line, format).unwrap_or_else(|| LogEntry::unstructured(line))
+}
+
+/// A parser stage after a formatting stage: parses the reformatted line again.
+struct Reparse(Format);
+
+impl Transform for Reparse {
+    fn process(&mut self, mut record: Record, out: &mut Vec<Record>) {
+        let mut entry = parse_as(record.raw(), self.0);
+        if let Some(line_number) = record.entry.line_number() {
+            entry = entry.with_line_number(line_number);
+        }
+        if let Some(source) = record.entry.source() {
+            entry = entry.with_source(source);
+        }
+        record.entry = entry;
+        out.push(record);
+    }
+}
+
+/// A Go-template subset: literal text and `{{.field}}` / `{{__line__}}` placeholders.
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Field(String),
+    Line,
+}
+
+fn parse_template(template: &str) -> Result<Vec<Segment>, QueryError> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        let close = rest[open..]
+            .find("}}")
+            .map(|close| open + close)
+            .ok_or_else(|| error(format!("Unterminated '{{{{' in template '{}'", template)))?;
+        if open > 0 {
+            segments.push(Segment::Text(rest[..open].to_string()));
+        }
+        let inner = rest[open + 2..close].trim();
+        segments.push(match inner.strip_prefix('.') {
+            _ if inner == "__line__" => Segment::Line,
+            Some(field) if !field.is_empty() && !field.contains(char::is_whitespace) => {
+                Segment::Field(field.to_string())
+            }
+            _ => {
+                let message = format!("Only {{{{.field}}}} placeholders are supported, got '{{{{{}}}}}'", inner);
+                return Err(error(message));
+            }
+        });
+        rest = &rest[close + 2..];
+    }
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest.to_string()));
+    }
+    Ok(segments)
+}
+
+fn render(segments: &[Segment], record: &Record) -> String {
+    let mut out = String::new();
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => out.push_str(text),
+            Segment::Line => out.push_str(record.raw()),
+            Segment::Field(field) => match query::resolve_field(record.value(), field) {
+                Some(Value::String(s)) => out.push_str(s),
+                Some(Value::Null) | None => {}
+                Some(value) => out.push_str(&value.to_string()),
+            },
+        }
+    }
+    out
+}
+
+/// `line_format`: replaces the line with the rendered template; fields are kept.
+struct LineFormat(Vec<Segment>);
+
+impl Transform for LineFormat {
+    fn process(&mut self, mut record: Record, out: &mut Vec<Record>) {
+        let line = render(&self.0, &record);
+        record.entry.set_raw(line);
+        out.push(record);
+    }
+}
+
+/// `label_format`: renames, then templated fields.
+struct LabelFormat {
+    renames: Remap,
+    templates: Vec<(String, Vec<Segment>)>,
+}
+
+impl Transform for LabelFormat {
+    fn process(&mut self, mut record: Record, out: &mut Vec<Record>) {
+        if let Some(value) = record.entry.value_mut() {
+            self.renames.apply(value);
+        }
+        let rendered: Vec<(String, Value)> = self
+            .templates
+            .iter()
+            .map(|(target, segments)| (target.clone(), Value::from(render(segments, &record))))
+            .collect();
+        insert_fields(&mut record, rendered);
+        out.push(record);
+    }
+}
+
+/// `drop`: removes top-level fields.
+struct DropFields(Vec<String>);
+
+impl Transform for DropFields {
+    fn process(&mut self, mut record: Record, out: &mut Vec<Record>) {
+        if let Some(Value::Object(map)) = record.entry.value_mut() {
+            for name in &self.0 {
+                map.remove(name);
+            }
+        }
+        out.push(record);
+    }
+}
@@ -0,0 +1,377 @@
+// File: src/dialect/lucene.rs
+
+use super::{condition, quote};
+use crate::query::{Expr, Operator, Query, QueryError};
+
+/// Compiles a Lucene / Elasticsearch query string (Kibana's "Lucene" syntax) onto the
+/// native AST:
+///
+/// - `field:value`, `field:"a phrase"` compare exactly; `field:err*` and `field:/re/`
+///   match wildcards and regexes against the whole value
+/// - `field:[a TO b]`, `field:{a TO b}`, `field:>=a`, with `*` for an open bound
+/// - `field:*` and `_exists_:field` test presence; `field:(a OR b)` groups on one field
+/// - bare terms search the raw line, case-insensitively
+/// - `AND` / `&&`, `OR` / `||`, `NOT` / `!`, `+required`, `-prohibited` and parentheses
+///
+/// As in Lucene, adjacent clauses default to `OR`, and `AND` marks both of its sides
+/// required, so `a AND b OR c` means `a and b`.
+pub fn parse(query: &str) -> Result<Query, QueryError> {
+    let mut parser = Parser { tokens: tokenize(query)?, pos: 0 };
+    let expr = parser.clauses(None)?;
+    if parser.pos < parser.tokens.len() {
+        return Err(QueryError::InvalidFormat("Unbalanced ')' in Lucene query".to_string()));
+    }
+    Ok(Query::from_expr(expr))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    /// `+` prefix.
+    Required,
+    /// `-` prefix.
+    Prohibited,
+    /// `field:(` — bare terms up to the matching `)` search `field`.
+    FieldGroup(String),
+    Term(Option<String>, Term),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Term {
+    /// Unquoted text, backslash escapes kept so literal `*` / `?` stay distinguishable.
+    Word(String),
+    Phrase(String),
+    Regex(String),
+    Range { low: String, high: String, include_low: bool, include_high: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Occur {
+    Must,
+    Should,
+    MustNot,
+}
+
+fn error(message: impl Into<String>) -> QueryError {
+    QueryError::InvalidFormat(message.into())
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let next = chars.get(i + 1).copied();
+        let prefix = next.is_some_and(|n| !n.is_whitespace());
+        let token = match chars[i] {
+            c if c.is_whitespace() => None,
+            '(' => Some(Token::Open),
+            ')' => Some(Token::Close),
+            '+' if prefix => Some(Token::Required),
+            '-' if prefix => Some(Token::Prohibited),
+            '!' if prefix => Some(Token::Not),
+            '&' if next == Some('&') => {
+                i += 1;
+                Some(Token::And)
+            }
+            '|' if next == Some('|') => {
+                i += 1;
+                Some(Token::Or)
+            }
+            _ => {
+                let (token, end) = lex_term(&chars, i)?;
+                i = end;
+                tokens.push(token);
+                continue;
+            }
+        };
+        tokens.extend(token);
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+/// Reads `[field:]value` starting at `start`; returns the token and the index after it.
+fn lex_term(chars: &[char], start: usize) -> Result<(Token, usize), QueryError> {
+    let mut i = start;
+    let mut field = None;
+    if chars[i] != '"' {
+        while i < chars.len() && !ends_word(chars[i]) && chars[i] != ':' {
+            i += if chars[i] == '\\' { 2 } else { 1 };
+        }
+        if i < chars.len() && chars[i] == ':' && i > start {
+            field = Some(unescape(&chars[start..i].iter().collect::<String>()));
+            i += 1;
+        } else {
+            i = start;
+        }
+    }
+
+    let Some(&first) = chars.get(i).filter(|c| !c.is_whitespace()) else {
+        return Err(error(format!("Missing value after '{}:'", field.unwrap_or_default())));
+    };
+    if let (Some(field), '(') = (&field, first) {
+        return Ok((Token::FieldGroup(field.clone()), i + 1));
+    }
+    // `/re/` is a regex only when the closing slash ends the term; `/health*` is a path.
+    let regex_close = find_unescaped(chars, i + 1, '/')
+        .filter(|&close| first == '/' && chars.get(close + 1).is_none_or(|&c| ends_word(c)));
+
+    let (term, end) = match (first, regex_close) {
+        ('"', _) => {
+            let close = find_unescaped(chars, i + 1, '"').ok_or_else(|| error("Unterminated phrase in Lucene query"))?;
+            (Term::Phrase(unescape(&chars[i + 1..close].iter().collect::<String>())), close + 1)
+        }
+        ('[' | '{', _) => {
+            let close = (i + 1..chars.len())
+                .find(|&j| matches!(chars[j], ']' | '}'))
+                .ok_or_else(|| error("Unterminated range in Lucene query"))?;
+            let inner: String = chars[i + 1..close].iter().collect();
+            let bounds: Vec<&str> = inner.split_whitespace().collect();
+            let [low, "TO", high] = bounds[..] else {
+                return Err(error(format!("Range must look like '[low TO high]': {}", inner.trim())));
+            };
+            let range = Term::Range {
+                low: low.trim_matches('"').to_string(),
+                high: high.trim_matches('"').to_string(),
+                include_low: first == '[',
+                include_high: chars[close] == ']',
+            };
+            (range, close + 1)
+        }
+        (_, Some(close)) => (Term::Regex(chars[i + 1..close].iter().collect()), close + 1),
+        _ => {
+            let mut end = i;
+            while end < chars.len() && !ends_word(chars[end]) {
+                end += if chars[end] == '\\' { 2 } else { 1 };
+            }
+            let end = end.min(chars.len());
+            let word: String = chars[i..end].iter().collect();
+            if field.is_none() {
+                match word.as_str() {
+                    "AND" => return Ok((Token::And, end)),
+                    "OR" => return Ok((Token::Or, end)),
+                    "NOT" => return Ok((Token::Not, end)),
+                    _ => {}
+                }
+            }
+            (Term::Word(word), end)
+        }
+    };
+    Ok((Token::Term(field, term), end))
+}
+
+fn ends_word(c: char) -> bool {
+    c.is_whitespace() || c == '(' || c == ')'
+}
+
+fn find_unescaped(chars: &[char], from: usize, target: char) -> Option<usize> {
+    let mut i = from;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            c if c == target => return Some(i),
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        out.extend(if c == '\\' { chars.next() } else { Some(c) });
+    }
+    out
+}
+
+fn has_wildcard(word: &str) -> bool {
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '*' | '?' => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Translates a wildcard pattern; `any` is the class `?` matches and `*` repeats.
+fn wildcard_regex(word: &str, any: &str) -> String {
+    let mut out = String::new();
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.push_str(&regex::escape(&chars.next().map(String::from).unwrap_or_default())),
+            '*' => {
+                out.push_str(any);
+                out.push('*');
+            }
+            '?' => out.push_str(any),
+            _ => out.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    out
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    /// A run of clauses up to `)` or the end, combined with Lucene's boolean rules.
+    fn clauses(&mut self, field: Option<&str>) -> Result<Expr, QueryError> {
+        let mut clauses: Vec<(Occur, Expr)> = Vec::new();
+        loop {
+            let and = match self.peek() {
+                Some(Token::And) => Some(true),
+                Some(Token::Or) => Some(false),
+                _ => None,
+            };
+            if and.is_some() {
+                if clauses.is_empty() {
+                    return Err(error("Lucene query can't start with AND / OR"));
+                }
+                self.pos += 1;
+            }
+            let modifier = match self.peek() {
+                Some(Token::Required) => Some(Occur::Must),
+                Some(Token::Prohibited | Token::Not) => Some(Occur::MustNot),
+                _ => None,
+            };
+            if modifier.is_some() {
+                self.pos += 1;
+            }
+            if matches!(self.peek(), None | Some(Token::Close)) {
+                if and.is_some() || modifier.is_some() {
+                    return Err(error("Missing clause after operator in Lucene query"));
+                }
+                break;
+            }
+
+            let expr = self.clause(field)?;
+            if and == Some(true) {
+                if let Some((occur @ Occur::Should, _)) = clauses.last_mut() {
+                    *occur = Occur::Must;
+                }
+            }
+            let occur = modifier.unwrap_or(if and == Some(true) { Occur::Must } else { Occur::Should });
+            clauses.push((occur, expr));
+        }
+        Ok(combine(clauses))
+    }
+
+    fn clause(&mut self, field: Option<&str>) -> Result<Expr, QueryError> {
+        let token = self.tokens[self.pos].clone();
+        self.pos += 1;
+        match token {
+            Token::Open => self.group(field),
+            Token::FieldGroup(group_field) => self.group(Some(&group_field)),
+            Token::Term(term_field, term) => term_expr(term_field.as_deref().or(field), &term),
+            Token::And | Token::Or => Err(error("Missing clause before AND / OR in Lucene query")),
+            _ => Err(error("Repeated NOT / + / - in Lucene query")),
+        }
+    }
+
+    fn group(&mut self, field: Option<&str>) -> Result<Expr, QueryError> {
+        let expr = self.clauses(field)?;
+        if self.peek() != Some(&Token::Close) {
+            return Err(error("Missing ')' in Lucene query"));
+        }
+        self.pos += 1;
+        Ok(expr)
+    }
+}
+
+/// Required clauses must all match; optional ones only matter when nothing is
+/// required, and then at least one of them must match.
+fn combine(clauses: Vec<(Occur, Expr)>) -> Expr {
+    let has_required = clauses.iter().any(|(occur, _)| *occur == Occur::Must);
+    let mut required = Vec::new();
+    let mut optional = Vec::new();
+    for (occur, expr) in clauses {
+        match occur {
+            Occur::Must => required.push(expr),
+            Occur::MustNot => required.push(Expr::Not(Box::new(expr))),
+            Occur::Should => optional.push(expr),
+        }
+    }
+    if !has_required && !optional.is_empty() {
+        required.insert(0, if optional.len() == 1 { optional.remove(0) } else { Expr::Or(optional) });
+    }
+    match required.len() {
+        0 => Expr::All,
+        1 => required.remove(0),
+        _ => Expr::And(required),
+    }
+}
+
+fn term_expr(field: Option<&str>, term: &Term) -> Result<Expr, QueryError> {
+    let Some(field) = field else {
+        // Bare terms search the whole line
+        return match term {
+            Term::Word(word) if word == "*" => Ok(Expr::All),
+            Term::Word(word) if has_wildcard(word) => {
+                condition("text", Operator::Matches, format!("/(?i){}/", wildcard_regex(word, "\\S")))
+            }
+            Term::Word(word) => Ok(Expr::text(unescape(word), false)),
+            Term::Phrase(phrase) => Ok(Expr::text(phrase.clone(), false)),
+            Term::Regex(re) => condition("text", Operator::Matches, format!("/{}/", re)),
+            Term::Range { .. } => Err(error("A Lucene range needs a field, e.g. status:[500 TO 599]")),
+        };
+    };
+
+    match term {
+        Term::Word(word) if field == "_exists_" => condition(&unescape(word), Operator::Exists, ""),
+        Term::Word(word) if word == "*" => {
+            if field == "*" { Ok(Expr::All) } else { condition(field, Operator::Exists, "") }
+        }
+        Term::Word(word) => {
+            for (prefix, op) in [(">=", Operator::Ge), ("<=", Operator::Le), (">", Operator::Gt), ("<", Operator::Lt)] {
+                if let Some(rest) = word.strip_prefix(prefix) {
+                    return condition(field, op, quote(&unescape(rest)));
+                }
+            }
+            if has_wildcard(word) {
+                condition(field, Operator::Matches, format!("/^{}$/", wildcard_regex(word, ".")))
+            } else {
+                condition(field, Operator::Eq, quote(&unescape(word)))
+            }
+        }
+        Term::Phrase(phrase) => condition(field, Operator::Eq, format!("\"{}\"", phrase)),
+        Term::Regex(re) => condition(field, Operator::Matches, format!("/^(?:{})$/", re)),
+        Term::Range { low, high, include_low, include_high } => {
+            let (low, high) = ((low != "*").then_some(low), (high != "*").then_some(high));
+            if let (Some(low), Some(high), true, true) = (low, high, include_low, include_high) {
+                return condition(field, Operator::Between, format!("{}..{}", low, high));
+            }
+            let mut bounds = Vec::new();
+            if let Some(low) = low {
+                let op = if *include_low { Operator::Ge } else { Operator::Gt };
+                bounds.push(condition(field, op, quote(low))?);
+            }
+            if let Some(high) = high {
+                let op = if *include_high { Operator::Le } else { Operator::Lt };
+                bounds.push(condition(field, op, quote(high))?);
+            }
+            match bounds.len() {
+                0 => condition(field, Operator::Exists, ""),
+                1 => Ok(bounds.remove(0)),
+                _ => Ok(Expr::And(bounds)),
+            }
+        }
+    }
+}
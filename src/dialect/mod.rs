@@ -0,0 +1,24 @@
+// File: src/dialect/mod.rs
+
+pub mod jq;
+pub mod logql;
+pub mod lucene;
+pub mod sql;
+
+use crate::query::{Condition, Expr, Field, Operator, QueryError};
+
+/// A validated condition on a plain field name (no modifier).
+pub(crate) fn condition(field: &str, op: Operator, value: impl Into<String>) -> Result<Expr, QueryError> {
+    let field = Field { name: field.to_string(), modifier: None };
+    Ok(Expr::Condition(Condition::new(field, op, value).checked()?))
+}
+
+/// Quotes a literal for a condition's right-hand side when it would otherwise be
+/// ambiguous (whitespace, empty, or already quote-delimited).
+pub(crate) fn quote(value: &str) -> String {
+    if value.is_empty() || value.contains(char::is_whitespace) || value.starts_with(['"', '\'']) {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
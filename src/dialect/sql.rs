@@ -0,0 +1,758 @@
+// File: src/dialect/sql.rs
+
+use super::{condition, quote};
+use crate::aggregate::group::{GroupBy, GroupStats, MISSING_KEY};
+use crate::aggregate::Stats;
+use crate::pipeline::{Aggregation, Pipeline, Record};
+use crate::query::{self, Expr, Operator, Query, QueryError};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// A compiled `SELECT` statement over log entries:
+///
+/// ```text
+/// SELECT path, count(*), avg(latency_ms) AS avg_ms
+/// WHERE status >= 500 AND path NOT LIKE '/health%'
+/// GROUP BY path ORDER BY count DESC LIMIT 10
+/// ```
+///
+/// `FROM` is optional and ignored. `WHERE` supports `= <> != < <= > >=`, `[NOT] LIKE`,
+/// `[NOT] IN (...)`, `[NOT] BETWEEN a AND b`, `IS [NOT] NULL`, `AND`, `OR`, `NOT` and
+/// parentheses; values are compared with the native query semantics (durations, levels,
+/// times). The aggregates are `count(*)`, `count(field)` (entries where the field is
+/// numeric), `sum`, `avg`, `min`, `max`, `p50` / `median`, `p90` and `p99`. An unaliased
+/// aggregate is named `count` for `count(*)` and `avg_latency_ms` for `avg(latency_ms)`;
+/// `ORDER BY` accepts that name, the alias, or the expression itself. Grouped columns
+/// come back as strings, like [`GroupBy`] keys.
+#[derive(Debug, Clone)]
+pub struct SqlQuery {
+    columns: Vec<Column>,
+    filter: Query,
+    group_by: Vec<String>,
+    order_by: Vec<(OrderKey, bool)>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Column {
+    /// `*`: the whole entry.
+    All,
+    Field { path: String, name: String },
+    Aggregate { function: Function, field: Option<String>, name: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    P50,
+    P90,
+    P99,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum OrderKey {
+    /// An output column, by index.
+    Column(usize),
+    /// Any field of the entry (only for queries without aggregates).
+    Field(String),
+}
+
+impl Function {
+    fn parse(name: &str) -> Option<Function> {
+        match name.to_ascii_lowercase().as_str() {
+            "count" => Some(Function::Count),
+            "sum" => Some(Function::Sum),
+            "avg" => Some(Function::Avg),
+            "min" => Some(Function::Min),
+            "max" => Some(Function::Max),
+            "p50" | "median" => Some(Function::P50),
+            "p90" => Some(Function::P90),
+            "p99" => Some(Function::P99),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Function::Count => "count",
+            Function::Sum => "sum",
+            Function::Avg => "avg",
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::P50 => "p50",
+            Function::P90 => "p90",
+            Function::P99 => "p99",
+        }
+    }
+
+    fn value(&self, field: Option<&str>, stats: &Stats) -> Value {
+        let number = match self {
+            Function::Count if field.is_none() => return Value::from(stats.count),
+            Function::Count => return Value::from(stats.numeric_count),
+            Function::Sum => (stats.numeric_count > 0).then_some(stats.sum),
+            Function::Avg => stats.avg,
+            Function::Min => stats.min,
+            Function::Max => stats.max,
+            Function::P50 => stats.p50,
+            Function::P90 => stats.p90,
+            Function::P99 => stats.p99,
+        };
+        number.map_or(Value::Null, Value::from)
+    }
+}
+
+impl Column {
+    fn name(&self) -> &str {
+        match self {
+            Column::All => "*",
+            Column::Field { name, .. } | Column::Aggregate { name, .. } => name,
+        }
+    }
+
+    /// The column as written without an alias, e.g. `avg(latency)`.
+    fn spelled(&self) -> String {
+        match self {
+            Column::All => "*".to_string(),
+            Column::Field { path, .. } => path.clone(),
+            Column::Aggregate { function, field, .. } => {
+                format!("{}({})", function.as_str(), field.as_deref().unwrap_or("*"))
+            }
+        }
+    }
+}
+
+/// Parses a `SELECT` statement.
+pub fn parse(sql: &str) -> Result<SqlQuery, QueryError> {
+    let mut parser = Parser { tokens: tokenize(sql)?, pos: 0 };
+    let statement = parser.statement()?;
+    match parser.peek() {
+        None => Ok(statement),
+        Some(token) => Err(error(format!("Unexpected {} in SQL query", token.describe()))),
+    }
+}
+
+impl SqlQuery {
+    /// The `WHERE` clause as a native query.
+    pub fn filter(&self) -> &Query {
+        &self.filter
+    }
+
+    /// Output column names, in order.
+    pub fn columns(&self) -> Vec<&str> {
+        self.columns.iter().map(Column::name).collect()
+    }
+
+    /// Whether rows are groups (`GROUP BY` or an aggregate) rather than entries.
+    pub fn is_aggregate(&self) -> bool {
+        !self.group_by.is_empty() || self.columns.iter().any(|c| matches!(c, Column::Aggregate { .. }))
+    }
+
+    /// An empty result table to feed matching records into.
+    pub fn table(&self) -> Table {
+        let mut fields: Vec<Option<String>> = Vec::new();
+        for column in &self.columns {
+            if let Column::Aggregate { field, function, .. } = column {
+                // count(*) only needs entry counts, which every grouping has
+                let field = if *function == Function::Count && field.is_none() { None } else { field.clone() };
+                if !fields.contains(&field) {
+                    fields.push(field);
+                }
+            }
+        }
+        if self.is_aggregate() && fields.is_empty() {
+            fields.push(None);
+        }
+        let groups = fields.into_iter().map(|field| (field.clone(), GroupBy::new(self.group_by.clone(), field)));
+        Table { query: self.clone(), groups: groups.collect(), rows: Vec::new() }
+    }
+
+    /// A pipeline filtering on the `WHERE` clause, with the result [`Table`] as its
+    /// aggregation.
+    pub fn pipeline(&self) -> Pipeline {
+        Pipeline::new().with_query(self.filter.clone()).with_aggregation(self.table())
+    }
+
+    /// Runs the statement over `lines` and returns the result rows as JSON objects.
+    pub fn run<I>(&self, lines: I) -> Result<Vec<Value>, QueryError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut table = self.table();
+        let mut pipeline = Pipeline::new().with_query(self.filter.clone());
+        for record in pipeline.run(lines) {
+            table.observe(&record?);
+            if table.is_complete() {
+                break;
+            }
+        }
+        Ok(table.rows())
+    }
+
+    /// Rejects columns SQL couldn't compute: bare fields next to aggregates must be
+    /// grouped on, and `*` can't be mixed with aggregation.
+    fn validate(&self) -> Result<(), QueryError> {
+        if !self.is_aggregate() {
+            return Ok(());
+        }
+        for column in &self.columns {
+            match column {
+                Column::All => return Err(error("SELECT * can't be combined with GROUP BY or aggregates")),
+                Column::Field { path, .. } if !self.group_by.contains(path) => {
+                    return Err(error(format!("Column '{}' must appear in GROUP BY or an aggregate", path)));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The result of a [`SqlQuery`], built incrementally: groups for aggregate queries
+/// (via [`GroupBy`], one per aggregated field), otherwise the projected entries.
+#[derive(Debug, Clone)]
+pub struct Table {
+    query: SqlQuery,
+    groups: Vec<(Option<String>, GroupBy)>,
+    /// Projected rows with their sort keys.
+    rows: Vec<(Value, Vec<Value>)>,
+}
+
+impl Table {
+    /// Whether further records can't change the result (`LIMIT` reached, no `ORDER BY`).
+    fn is_complete(&self) -> bool {
+        !self.query.is_aggregate()
+            && self.query.order_by.is_empty()
+            && self.query.limit.is_some_and(|limit| self.rows.len() >= limit)
+    }
+
+    /// The result rows, sorted and limited.
+    pub fn rows(&self) -> Vec<Value> {
+        let mut rows = if self.query.is_aggregate() { self.group_rows() } else { self.rows.clone() };
+        if !self.query.order_by.is_empty() {
+            rows.sort_by(|(_, a), (_, b)| {
+                self.query
+                    .order_by
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (_, descending))| {
+                        let ordering = compare(&a[i], &b[i]);
+                        // Nulls last either way
+                        if *descending && !a[i].is_null() && !b[i].is_null() { ordering.reverse() } else { ordering }
+                    })
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+        let limit = self.query.limit.unwrap_or(usize::MAX);
+        rows.into_iter().take(limit).map(|(row, _)| row).collect()
+    }
+
+    fn group_rows(&self) -> Vec<(Value, Vec<Value>)> {
+        // Groups in the first grouping's order (largest first), stats per aggregated field
+        let mut order: Vec<Vec<String>> = Vec::new();
+        let mut stats: HashMap<Vec<String>, HashMap<Option<&str>, Stats>> = HashMap::new();
+        for (field, group_by) in &self.groups {
+            for GroupStats { key, stats: group } in group_by.stats().groups {
+                if !stats.contains_key(&key) {
+                    order.push(key.clone());
+                }
+                stats.entry(key).or_default().insert(field.as_deref(), group);
+            }
+        }
+        // An ungrouped aggregate over nothing is still one row (`count(*) = 0`)
+        if order.is_empty() && self.query.group_by.is_empty() {
+            order.push(Vec::new());
+        }
+
+        let empty = empty_stats();
+        order
+            .into_iter()
+            .map(|key| {
+                let group = stats.get(&key);
+                let mut row = Map::new();
+                for column in &self.query.columns {
+                    let value = match column {
+                        Column::Field { path, .. } => {
+                            let index = self.query.group_by.iter().position(|g| g == path).unwrap_or(0);
+                            match key.get(index).map(String::as_str) {
+                                Some(MISSING_KEY) | None => Value::Null,
+                                Some(k) => Value::from(k),
+                            }
+                        }
+                        Column::Aggregate { function, field, .. } => {
+                            let stats_field = if *function == Function::Count && field.is_none() {
+                                None
+                            } else {
+                                field.as_deref()
+                            };
+                            let stats = group.and_then(|g| g.get(&stats_field)).unwrap_or(&empty);
+                            function.value(field.as_deref(), stats)
+                        }
+                        Column::All => continue,
+                    };
+                    row.insert(column.name().to_string(), value);
+                }
+                let keys = self
+                    .query
+                    .order_by
+                    .iter()
+                    .map(|(key, _)| match key {
+                        OrderKey::Column(index) => column_value(&row, *index, &self.query),
+                        OrderKey::Field(_) => Value::Null,
+                    })
+                    .collect();
+                (Value::Object(row), keys)
+            })
+            .collect()
+    }
+}
+
+fn empty_stats() -> Stats {
+    Stats { count: 0, numeric_count: 0, sum: 0.0, avg: None, min: None, max: None, p50: None, p90: None, p99: None }
+}
+
+fn column_value(row: &Map<String, Value>, index: usize, query: &SqlQuery) -> Value {
+    row.get(query.columns[index].name()).cloned().unwrap_or(Value::Null)
+}
+
+impl Aggregation for Table {
+    fn observe(&mut self, record: &Record) {
+        let unstructured;
+        let entry = match record.entry.value() {
+            Some(value) => value,
+            None => {
+                unstructured = serde_json::json!({ "message": record.raw() });
+                &unstructured
+            }
+        };
+        if self.query.is_aggregate() {
+            for (_, group_by) in &mut self.groups {
+                group_by.observe(entry);
+            }
+            return;
+        }
+        if self.is_complete() {
+            return;
+        }
+
+        let mut row = Map::new();
+        for column in &self.query.columns {
+            match column {
+                Column::All => match entry {
+                    Value::Object(map) => row.extend(map.clone()),
+                    other => {
+                        row.insert("value".to_string(), other.clone());
+                    }
+                },
+                Column::Field { path, name } => {
+                    let value = query::resolve_field(entry, path).cloned().unwrap_or(Value::Null);
+                    row.insert(name.clone(), value);
+                }
+                Column::Aggregate { .. } => {}
+            }
+        }
+        let keys = self
+            .query
+            .order_by
+            .iter()
+            .map(|(key, _)| match key {
+                OrderKey::Field(path) => query::resolve_field(entry, path).cloned().unwrap_or(Value::Null),
+                OrderKey::Column(index) => column_value(&row, *index, &self.query),
+            })
+            .collect();
+        self.rows.push((Value::Object(row), keys));
+    }
+
+    fn result(&self) -> Value {
+        Value::Array(self.rows())
+    }
+}
+
+/// Numbers before strings before everything else; nulls last.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Number(_) => 0,
+            Value::String(_) => 1,
+            Value::Null => 3,
+            _ => 2,
+        }
+    }
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => {
+            x.as_f64().unwrap_or(0.0).partial_cmp(&y.as_f64().unwrap_or(0.0)).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(x), Value::String(y)) => match (x.parse::<f64>(), y.parse::<f64>()) {
+            // Group keys are strings; keep numeric ones in numeric order
+            (Ok(x), Ok(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+            _ => x.cmp(y),
+        },
+        _ if rank(a) != rank(b) => rank(a).cmp(&rank(b)),
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A name, keyword, number or other bare literal.
+    Word(String),
+    /// A `"quoted"` or `` `quoted` `` identifier.
+    Ident(String),
+    /// A `'quoted'` string.
+    Str(String),
+    Op(&'static str),
+    Punct(char),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::Word(w) | Token::Ident(w) => format!("'{}'", w),
+            Token::Str(s) => format!("'{}'", s.replace('\'', "''")),
+            Token::Op(op) => format!("'{}'", op),
+            Token::Punct(c) => format!("'{}'", c),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+    }
+}
+
+/// Longer operators first.
+const OPERATORS: &[&str] = &["<=", ">=", "<>", "!=", "==", "=", "<", ">"];
+
+/// Words that end a clause, so they can't be read as names.
+const KEYWORDS: &[&str] = &["select", "from", "where", "group", "order", "by", "limit", "and", "or", "not", "as"];
+
+fn error(message: impl Into<String>) -> QueryError {
+    QueryError::InvalidFormat(message.into())
+}
+
+fn tokenize(sql: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut rest = sql.trim_start();
+    while let Some(c) = rest.chars().next() {
+        if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else if matches!(c, '(' | ')' | ',' | '*' | ';') {
+            tokens.push(Token::Punct(c));
+            rest = &rest[1..];
+        } else if matches!(c, '\'' | '"' | '`') {
+            // Quotes are escaped by doubling them
+            let mut text = String::new();
+            let mut end = None;
+            let mut chars = rest.char_indices().skip(1).peekable();
+            while let Some((i, ch)) = chars.next() {
+                if ch != c {
+                    text.push(ch);
+                } else if chars.peek().is_some_and(|(_, next)| *next == c) {
+                    text.push(c);
+                    chars.next();
+                } else {
+                    end = Some(i + 1);
+                    break;
+                }
+            }
+            let end = end.ok_or_else(|| error("Unterminated quote in SQL query"))?;
+            tokens.push(if c == '\'' { Token::Str(text) } else { Token::Ident(text) });
+            rest = &rest[end..];
+        } else {
+            let len = rest
+                .find(|c: char| c.is_whitespace() || "()<>=!,*;'\"`".contains(c))
+                .unwrap_or(rest.len());
+            if len == 0 {
+                return Err(error(format!("Unexpected '{}' in SQL query", c)));
+            }
+            tokens.push(Token::Word(rest[..len].to_string()));
+            rest = &rest[len..];
+        }
+        rest = rest.trim_start();
+    }
+    // A trailing `;` is allowed
+    if tokens.last() == Some(&Token::Punct(';')) {
+        tokens.pop();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.peek().is_some_and(|t| t.is_keyword(keyword));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), QueryError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(error(format!("Expected {} in SQL query", keyword.to_ascii_uppercase())))
+        }
+    }
+
+    fn expect_punct(&mut self, c: char) -> Result<(), QueryError> {
+        if self.next() == Some(Token::Punct(c)) {
+            Ok(())
+        } else {
+            Err(error(format!("Expected '{}' in SQL query", c)))
+        }
+    }
+
+    /// A field name: a bare word that isn't a keyword, or a quoted identifier.
+    fn name(&mut self) -> Result<String, QueryError> {
+        match self.next() {
+            Some(Token::Word(w)) if !KEYWORDS.contains(&w.to_ascii_lowercase().as_str()) => Ok(w),
+            Some(Token::Ident(name)) => Ok(name),
+            Some(token) => Err(error(format!("Expected a field name, got {}", token.describe()))),
+            None => Err(error("Expected a field name at the end of the SQL query")),
+        }
+    }
+
+    fn statement(&mut self) -> Result<SqlQuery, QueryError> {
+        self.expect_keyword("select")?;
+        let mut columns = vec![self.column()?];
+        while self.peek() == Some(&Token::Punct(',')) {
+            self.pos += 1;
+            columns.push(self.column()?);
+        }
+        if self.eat_keyword("from") {
+            self.name()?;
+        }
+        let filter = if self.eat_keyword("where") { self.or()? } else { Expr::All };
+
+        let mut group_by = Vec::new();
+        if self.eat_keyword("group") {
+            self.expect_keyword("by")?;
+            group_by.push(self.name()?);
+            while self.peek() == Some(&Token::Punct(',')) {
+                self.pos += 1;
+                group_by.push(self.name()?);
+            }
+        }
+
+        let filter = Query::from_expr(filter);
+        let mut query = SqlQuery { columns, filter, group_by, order_by: Vec::new(), limit: None };
+        query.validate()?;
+
+        if self.eat_keyword("order") {
+            self.expect_keyword("by")?;
+            loop {
+                let key = self.order_key(&query)?;
+                let descending = self.eat_keyword("desc");
+                if !descending {
+                    self.eat_keyword("asc");
+                }
+                query.order_by.push((key, descending));
+                if self.peek() != Some(&Token::Punct(',')) {
+                    break;
+                }
+                self.pos += 1;
+            }
+        }
+        if self.eat_keyword("limit") {
+            query.limit = match self.next() {
+                Some(Token::Word(n)) => n.parse().ok(),
+                _ => None,
+            };
+            if query.limit.is_none() {
+                return Err(error("LIMIT needs a non-negative integer"));
+            }
+        }
+        Ok(query)
+    }
+
+    /// `*`, `field [AS name]` or `function(* | field) [AS name]`.
+    fn column(&mut self) -> Result<Column, QueryError> {
+        if self.peek() == Some(&Token::Punct('*')) {
+            self.pos += 1;
+            return Ok(Column::All);
+        }
+        let path = self.name()?;
+        let column = if self.peek() == Some(&Token::Punct('(')) {
+            let function = Function::parse(&path).ok_or_else(|| error(format!("Unknown SQL function '{}'", path)))?;
+            self.pos += 1;
+            let field = if self.peek() == Some(&Token::Punct('*')) {
+                self.pos += 1;
+                None
+            } else {
+                Some(self.name()?)
+            };
+            self.expect_punct(')')?;
+            if field.is_none() && function != Function::Count {
+                return Err(error(format!("{}(*) needs a field", function.as_str())));
+            }
+            let name = match &field {
+                Some(field) => format!("{}_{}", function.as_str(), field),
+                None => function.as_str().to_string(),
+            };
+            Column::Aggregate { function, field, name }
+        } else {
+            Column::Field { name: path.clone(), path }
+        };
+        if !self.eat_keyword("as") {
+            return Ok(column);
+        }
+        let alias = self.name()?;
+        Ok(match column {
+            Column::Field { path, .. } => Column::Field { path, name: alias },
+            Column::Aggregate { function, field, .. } => Column::Aggregate { function, field, name: alias },
+            Column::All => Column::All,
+        })
+    }
+
+    fn order_key(&mut self, query: &SqlQuery) -> Result<OrderKey, QueryError> {
+        let mut spelled = self.name()?;
+        if self.peek() == Some(&Token::Punct('(')) {
+            self.pos += 1;
+            let argument = if self.peek() == Some(&Token::Punct('*')) {
+                self.pos += 1;
+                "*".to_string()
+            } else {
+                self.name()?
+            };
+            self.expect_punct(')')?;
+            spelled = format!("{}({})", spelled.to_ascii_lowercase(), argument);
+        }
+        let column = query.columns.iter().position(|c| c.name() == spelled || c.spelled() == spelled);
+        match column {
+            Some(index) => Ok(OrderKey::Column(index)),
+            None if !query.is_aggregate() && !spelled.contains('(') => Ok(OrderKey::Field(spelled)),
+            None => Err(error(format!("ORDER BY '{}' is not a selected column", spelled))),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, QueryError> {
+        let mut clauses = vec![self.and()?];
+        while self.eat_keyword("or") {
+            clauses.push(self.and()?);
+        }
+        Ok(if clauses.len() == 1 { clauses.remove(0) } else { Expr::Or(clauses) })
+    }
+
+    fn and(&mut self) -> Result<Expr, QueryError> {
+        let mut clauses = vec![self.not()?];
+        while self.eat_keyword("and") {
+            clauses.push(self.not()?);
+        }
+        Ok(if clauses.len() == 1 { clauses.remove(0) } else { Expr::And(clauses) })
+    }
+
+    fn not(&mut self) -> Result<Expr, QueryError> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        if self.peek() == Some(&Token::Punct('(')) {
+            self.pos += 1;
+            let expr = self.or()?;
+            self.expect_punct(')')?;
+            return Ok(expr);
+        }
+        self.predicate()
+    }
+
+    fn literal(&mut self) -> Result<String, QueryError> {
+        match self.next() {
+            Some(Token::Str(s)) => Ok(quote(&s)),
+            Some(Token::Word(w)) if !KEYWORDS.contains(&w.to_ascii_lowercase().as_str()) => Ok(w),
+            Some(token) => Err(error(format!("Expected a value, got {}", token.describe()))),
+            None => Err(error("Expected a value at the end of the SQL query")),
+        }
+    }
+
+    fn predicate(&mut self) -> Result<Expr, QueryError> {
+        let field = self.name()?;
+        if let Some(Token::Op(op)) = self.peek() {
+            let op = match *op {
+                "=" | "==" => Operator::Eq,
+                "<>" | "!=" => Operator::Ne,
+                "<" => Operator::Lt,
+                "<=" => Operator::Le,
+                ">" => Operator::Gt,
+                _ => Operator::Ge,
+            };
+            self.pos += 1;
+            let value = self.literal()?;
+            return condition(&field, op, value);
+        }
+
+        if self.eat_keyword("is") {
+            let negated = self.eat_keyword("not");
+            self.expect_keyword("null")?;
+            // A missing field is NULL too
+            return Ok(if negated {
+                condition(&field, Operator::IsNotNull, "")?
+            } else {
+                Expr::Or(vec![condition(&field, Operator::NotExists, "")?, condition(&field, Operator::IsNull, "")?])
+            });
+        }
+
+        let negated = self.eat_keyword("not");
+        let expr = if self.eat_keyword("like") || self.eat_keyword("ilike") {
+            let insensitive = self.tokens[self.pos - 1].is_keyword("ilike");
+            let pattern = match self.next() {
+                Some(Token::Str(s)) => s,
+                _ => return Err(error("LIKE needs a quoted pattern")),
+            };
+            let flags = if insensitive { "(?i)" } else { "" };
+            condition(&field, Operator::Matches, format!("/{}^{}$/", flags, like_regex(&pattern)))?
+        } else if self.eat_keyword("in") {
+            self.expect_punct('(')?;
+            let mut values = vec![condition(&field, Operator::Eq, self.literal()?)?];
+            while self.peek() == Some(&Token::Punct(',')) {
+                self.pos += 1;
+                values.push(condition(&field, Operator::Eq, self.literal()?)?);
+            }
+            self.expect_punct(')')?;
+            if values.len() == 1 { values.remove(0) } else { Expr::Or(values) }
+        } else if self.eat_keyword("between") {
+            let low = self.literal()?;
+            self.expect_keyword("and")?;
+            let high = self.literal()?;
+            condition(&field, Operator::Between, format!("{}..{}", low, high))?
+        } else {
+            let next = self.peek().map(Token::describe).unwrap_or_else(|| "the end".to_string());
+            return Err(error(format!("Expected a comparison after '{}', got {}", field, next)));
+        };
+        Ok(if negated { Expr::Not(Box::new(expr)) } else { expr })
+    }
+}
+
+/// `%` is any run of characters, `_` any single one; `\` escapes either.
+fn like_regex(pattern: &str) -> String {
+    let mut out = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => out.push_str(".*"),
+            '_' => out.push('.'),
+            '\\' => out.push_str(&regex::escape(&chars.next().map(String::from).unwrap_or_default())),
+            _ => out.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    out
+}
@@ -0,0 +1,192 @@
+// src/diff.rs
+
+use crate::aggregate::group::key_value;
+use crate::patterns::PatternMiner;
+use crate::pipeline::Record;
+use crate::transform;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const DEFAULT_SIGMA: f64 = 3.0;
+const DEFAULT_MIN_RATIO: f64 = 1.5;
+const DEFAULT_MIN_COUNT: u64 = 3;
+
+/// What entries are grouped by before the two sides are compared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompareKey {
+    /// Values of these fields, joined with a space (dotted paths and JSON pointers allowed).
+    Fields(Vec<String>),
+    /// The message template the entry's message falls into (see [`PatternMiner`]).
+    Pattern,
+}
+
+/// Which of the two sources an entry belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    /// The reference, e.g. yesterday or the stable deployment.
+    Baseline,
+    /// The source under inspection, e.g. today or the canary.
+    Candidate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Only seen in the candidate.
+    Appeared,
+    /// Only seen in the baseline.
+    Disappeared,
+    Increased,
+    Decreased,
+}
+
+/// A group whose share of entries differs significantly between the two sides.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub key: String,
+    pub kind: ChangeKind,
+    pub baseline: u64,
+    pub candidate: u64,
+    /// Share of the side's entries falling in this group.
+    pub baseline_rate: f64,
+    pub candidate_rate: f64,
+    /// Two-proportion z-score; positive when the candidate's share is higher.
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffReport {
+    pub baseline_total: u64,
+    pub candidate_total: u64,
+    /// Most significant first.
+    pub changes: Vec<Change>,
+}
+
+/// Compares two sources (yesterday vs today, canary vs baseline) by grouping key and
+/// reports the groups that appeared, disappeared, or changed rate.
+///
+/// Rates are shares of each side's own total, so sources of different volume compare
+/// fairly. A group counts as changed when its shares differ by more than `sigma`
+/// (two-proportion z-test) and by at least `min_ratio`; groups with fewer than
+/// `min_count` entries on both sides are ignored as noise. In pattern mode both sides
+/// share one miner, so a template means the same thing on either side.
+#[derive(Debug, Clone)]
+pub struct Compare {
+    key: CompareKey,
+    miner: PatternMiner,
+    counts: HashMap<String, [u64; 2]>,
+    clusters: HashMap<usize, [u64; 2]>,
+    totals: [u64; 2],
+    sigma: f64,
+    min_ratio: f64,
+    min_count: u64,
+}
+
+impl Compare {
+    pub fn new(key: CompareKey) -> Self {
+        Compare {
+            key,
+            miner: PatternMiner::new(),
+            counts: HashMap::new(),
+            clusters: HashMap::new(),
+            totals: [0; 2],
+            sigma: DEFAULT_SIGMA,
+            min_ratio: DEFAULT_MIN_RATIO,
+            min_count: DEFAULT_MIN_COUNT,
+        }
+    }
+
+    pub fn set_sigma(&mut self, sigma: f64) {
+        self.sigma = sigma;
+    }
+
+    /// Minimum factor between the two shares for a rate change to be reported.
+    pub fn set_min_ratio(&mut self, min_ratio: f64) {
+        self.min_ratio = min_ratio.max(1.0);
+    }
+
+    pub fn set_min_count(&mut self, min_count: u64) {
+        self.min_count = min_count;
+    }
+
+    /// Counts one entry towards a side.
+    pub fn observe(&mut self, side: Side, record: &Record) {
+        let i = side as usize;
+        self.totals[i] += 1;
+        match &self.key {
+            CompareKey::Fields(fields) => {
+                let key = fields.iter().map(|field| key_value(record.value(), field)).collect::<Vec<_>>().join(" ");
+                self.counts.entry(key).or_default()[i] += 1;
+            }
+            CompareKey::Pattern => {
+                let cluster = self.miner.add(transform::message(record));
+                self.clusters.entry(cluster).or_default()[i] += 1;
+            }
+        }
+    }
+
+    /// The groups that differ between the two sides.
+    pub fn report(&self) -> DiffReport {
+        let counts: Vec<(String, [u64; 2])> = match self.key {
+            CompareKey::Fields(_) => self.counts.iter().map(|(key, counts)| (key.clone(), *counts)).collect(),
+            CompareKey::Pattern => {
+                let templates = self.miner.templates();
+                self.clusters.iter().map(|(&id, counts)| (templates[id].clone(), *counts)).collect()
+            }
+        };
+
+        let mut changes: Vec<Change> = counts
+            .into_iter()
+            .filter_map(|(key, [baseline, candidate])| self.change(key, baseline, candidate))
+            .collect();
+        changes.sort_by(|a, b| b.score.abs().total_cmp(&a.score.abs()).then_with(|| a.key.cmp(&b.key)));
+        DiffReport { baseline_total: self.totals[0], candidate_total: self.totals[1], changes }
+    }
+
+    fn change(&self, key: String, baseline: u64, candidate: u64) -> Option<Change> {
+        if baseline.max(candidate) < self.min_count {
+            return None;
+        }
+        let [baseline_total, candidate_total] = self.totals.map(|total| total.max(1) as f64);
+        let baseline_rate = baseline as f64 / baseline_total;
+        let candidate_rate = candidate as f64 / candidate_total;
+
+        let pooled = (baseline + candidate) as f64 / (baseline_total + candidate_total);
+        let stderr = (pooled * (1.0 - pooled) * (1.0 / baseline_total + 1.0 / candidate_total)).sqrt();
+        let score = if stderr > 0.0 { (candidate_rate - baseline_rate) / stderr } else { 0.0 };
+
+        let kind = if baseline == 0 {
+            ChangeKind::Appeared
+        } else if candidate == 0 {
+            ChangeKind::Disappeared
+        } else {
+            let ratio = candidate_rate.max(baseline_rate) / candidate_rate.min(baseline_rate);
+            if score.abs() <= self.sigma || ratio < self.min_ratio {
+                return None;
+            }
+            if score > 0.0 {
+                ChangeKind::Increased
+            } else {
+                ChangeKind::Decreased
+            }
+        };
+        Some(Change { key, kind, baseline, candidate, baseline_rate, candidate_rate, score })
+    }
+}
+
+/// Compares two record streams in one go.
+pub fn compare(
+    key: CompareKey,
+    baseline: impl IntoIterator<Item = Record>,
+    candidate: impl IntoIterator<Item = Record>,
+) -> DiffReport {
+    let mut compare = Compare::new(key);
+    for record in baseline {
+        compare.observe(Side::Baseline, &record);
+    }
+    for record in candidate {
+        compare.observe(Side::Candidate, &record);
+    }
+    compare.report()
+}
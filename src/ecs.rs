@@ -0,0 +1,187 @@
+// src/ecs.rs
+
+use crate::parsers::LogEntry;
+use crate::time as time_parser;
+use crate::transform::flatten::{flatten, DEFAULT_MAX_DEPTH};
+use chrono::SecondsFormat;
+use serde_json::{Map, Value};
+use std::net::IpAddr;
+
+/// ECS release the output follows, written to `ecs.version`.
+pub const ECS_VERSION: &str = "8.11.0";
+
+/// How a source value is coerced for its ECS field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Strings as-is, other scalars as their JSON text.
+    Keyword,
+    /// Integers, or strings holding one; anything else is left unmapped.
+    Long,
+    /// The first address of a (possibly comma-separated) list; must parse as an IP.
+    Ip,
+    /// `HTTP/1.1` becomes `1.1`.
+    HttpVersion,
+}
+
+/// `(ECS field, source keys, kind)`: the first source present fills the field. Sources
+/// are matched after flattening, so already-nested ECS input maps onto itself. Generic
+/// keys such as `name` or `log` mean different things across sources and aren't sources.
+const MAPPINGS: &[(&str, &[&str], Kind)] = &[
+    ("message", &["message", "msg", "@m", "@mt", "Message", "body"], Kind::Keyword),
+    ("log.logger", &["log.logger", "logger", "logger_name"], Kind::Keyword),
+    ("service.name", &["service.name", "service", "app", "service_name", "appname", "app_name"], Kind::Keyword),
+    ("host.name", &["host.name", "host", "hostname"], Kind::Keyword),
+    ("trace.id", &["trace.id", "trace_id", "traceId", "dd.trace_id"], Kind::Keyword),
+    ("span.id", &["span.id", "span_id", "spanId", "dd.span_id"], Kind::Keyword),
+    ("error.message", &["error.message", "error", "err"], Kind::Keyword),
+    ("http.request.method", &["http.request.method", "method", "http_method"], Kind::Keyword),
+    ("http.request.referrer", &["http.request.referrer", "http_referer", "referer", "referrer"], Kind::Keyword),
+    ("http.response.status_code", &["http.response.status_code", "status", "status_code", "http_status"], Kind::Long),
+    ("http.response.body.bytes", &["http.response.body.bytes", "body_bytes_sent", "bytes_sent", "bytes"], Kind::Long),
+    ("http.version", &["http.version", "protocol"], Kind::HttpVersion),
+    ("url.path", &["url.path", "path", "uri", "url_path"], Kind::Keyword),
+    ("url.query", &["url.query", "query", "query_string"], Kind::Keyword),
+    // After `url.path`: access-log parsers split `path` into `url.path`/`url.query`, leaving the original
+    ("url.original", &["url.original", "url", "request_uri", "path"], Kind::Keyword),
+    ("url.domain", &["url.domain", "vhost", "server_name"], Kind::Keyword),
+    ("source.ip", &["source.ip", "remote_addr", "client_ip", "client.ip", "ip"], Kind::Ip),
+    ("network.forwarded_ip", &["network.forwarded_ip", "x_forwarded_for"], Kind::Ip),
+    ("user_agent.original", &["user_agent.original", "http_user_agent", "user_agent", "ua"], Kind::Keyword),
+    ("user.name", &["user.name", "user", "username", "remote_user"], Kind::Keyword),
+    ("process.pid", &["process.pid", "pid"], Kind::Long),
+    ("process.thread.id", &["process.thread.id", "tid"], Kind::Long),
+    ("log.syslog.facility.code", &["log.syslog.facility.code", "facility"], Kind::Long),
+    ("log.syslog.severity.code", &["log.syslog.severity.code", "severity"], Kind::Long),
+];
+
+/// Keys consumed by `log.level` (which comes from [`LogEntry::level`]).
+const LEVEL_SOURCES: &[&str] = &["log.level", "level", "lvl", "loglevel", "severity_text", "severity", "@l"];
+
+/// Derived by the parsers; redundant once the ECS fields are filled.
+const DERIVED: &[&str] = &["status_class", "time_local", "@t", "level_num"];
+
+/// Prefix of the per-parameter fields split out of `url.query`.
+const URL_PARAMS: &str = "url.params.";
+
+/// Converts an entry into an Elastic Common Schema document, ready for a bulk request:
+///
+/// - `@timestamp` (RFC 3339, milliseconds) and `log.level` from the entry's own
+///   timestamp and level detection
+/// - well-known fields renamed and typed, e.g. `status` becomes the integer
+///   `http.response.status_code`, `remote_addr` becomes `source.ip`, `path` becomes `url.path`
+/// - `event.original` (the raw line), `log.file.path` (the entry's source) and `ecs.version`
+/// - everything else under `labels`, as strings with dots in keys replaced by `_`
+///
+/// Fields are nested objects (`{"http": {"response": {"status_code": 503}}}`). Null and
+/// `-` values count as missing; unstructured lines keep only the raw line as `message`.
+pub fn to_ecs(entry: &LogEntry) -> Value {
+    let mut doc = Map::new();
+    let mut fields = match entry.value().map(|value| flatten(value, DEFAULT_MAX_DEPTH)) {
+        Some(Value::Object(map)) => map,
+        _ => {
+            let mut map = Map::new();
+            map.insert("message".to_string(), Value::from(entry.raw()));
+            map
+        }
+    };
+
+    if let Some(ts) = entry.timestamp() {
+        insert_path(&mut doc, "@timestamp", Value::from(ts.to_rfc3339_opts(SecondsFormat::Millis, true)));
+        for key in time_parser::COMMON_KEYS.iter().chain(DERIVED) {
+            fields.remove(*key);
+        }
+    }
+    if let Some(level) = entry.level() {
+        insert_path(&mut doc, "log.level", Value::from(level.as_str()));
+        for key in LEVEL_SOURCES {
+            // A numeric `severity` is a syslog code, mapped below
+            if *key != "severity" || fields.get(*key).is_some_and(Value::is_string) {
+                fields.remove(*key);
+            }
+        }
+    }
+
+    for (target, sources, kind) in MAPPINGS {
+        let found = sources.iter().find_map(|source| {
+            let value = coerce(fields.get(*source)?, *kind)?;
+            Some((*source, value))
+        });
+        if let Some((source, value)) = found {
+            fields.remove(source);
+            insert_path(&mut doc, target, value);
+        }
+    }
+    for key in DERIVED {
+        fields.remove(*key);
+    }
+    fields.retain(|key, _| !key.starts_with(URL_PARAMS));
+
+    insert_path(&mut doc, "event.original", Value::from(entry.raw()));
+    if let Some(source) = entry.source() {
+        insert_path(&mut doc, "log.file.path", Value::from(source));
+    }
+    insert_path(&mut doc, "ecs.version", Value::from(ECS_VERSION));
+
+    let labels: Map<String, Value> = fields
+        .into_iter()
+        .filter(|(_, value)| !is_absent(value))
+        .map(|(key, value)| (key.replace('.', "_"), keyword(&value)))
+        .collect();
+    if !labels.is_empty() {
+        doc.insert("labels".to_string(), Value::Object(labels));
+    }
+    Value::Object(doc)
+}
+
+/// Null, or the `-` access logs write for a missing value.
+fn is_absent(value: &Value) -> bool {
+    value.is_null() || value.as_str() == Some("-")
+}
+
+fn keyword(value: &Value) -> Value {
+    match value {
+        Value::String(_) => value.clone(),
+        other => Value::from(other.to_string()),
+    }
+}
+
+fn coerce(value: &Value, kind: Kind) -> Option<Value> {
+    if is_absent(value) {
+        return None;
+    }
+    match kind {
+        Kind::Keyword => Some(keyword(value)),
+        Kind::Long => match value {
+            Value::Number(n) => n.as_i64().map(Value::from),
+            Value::String(s) => s.trim().parse::<i64>().ok().map(Value::from),
+            _ => None,
+        },
+        Kind::Ip => {
+            let first = value.as_str()?.split(',').next()?.trim();
+            first.parse::<IpAddr>().ok().map(|ip| Value::from(ip.to_string()))
+        }
+        Kind::HttpVersion => {
+            let version = value.as_str()?;
+            Some(Value::from(version.strip_prefix("HTTP/").unwrap_or(version)))
+        }
+    }
+}
+
+/// Inserts `value` at a dotted path, creating intermediate objects.
+fn insert_path(doc: &mut Map<String, Value>, path: &str, value: Value) {
+    match path.split_once('.') {
+        // `@timestamp` and the like are single keys
+        None => {
+            doc.insert(path.to_string(), value);
+        }
+        Some((head, rest)) => {
+            let child = doc.entry(head.to_string()).or_insert_with(|| Value::Object(Map::new()));
+            if !child.is_object() {
+                *child = Value::Object(Map::new());
+            }
+            if let Value::Object(child) = child {
+                insert_path(child, rest, value);
+            }
+        }
+    }
+}
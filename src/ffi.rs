@@ -0,0 +1,118 @@
+// src/ffi.rs
+
+// C ABI for embedding the engine outside WASM (Python `ctypes`, Go `cgo`, Node `ffi-napi`).
+//
+// Ownership: strings returned here are allocated by Rust and must be released with
+// `loglens_string_free`, compiled queries with `loglens_query_free`. Strings passed in
+// are borrowed for the duration of the call and must be NUL-terminated UTF-8.
+
+use crate::parsers;
+use crate::query::Query;
+use serde_json::json;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::ptr;
+
+/// Opaque handle to a compiled query.
+pub struct LoglensQuery(Query);
+
+/// Borrows a C string as `&str`, or `None` when it is null or not UTF-8.
+unsafe fn borrow_str<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+/// Hands a string to the caller. Interior NULs (impossible in JSON output) yield null.
+fn into_c_string(s: String) -> *mut c_char {
+    CString::new(s).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Parses one line and returns a JSON object: `{"format", "parsed", "timestamp"}`,
+/// where `parsed` is null for plain text. Returns null if `line` is null or not UTF-8.
+///
+/// # Safety
+///
+/// `line` must be null or point to a NUL-terminated string. The result must be freed
+/// with [`loglens_string_free`].
+#[no_mangle]
+pub unsafe extern "C" fn loglens_parse_line(line: *const c_char) -> *mut c_char {
+    let Some(line) = borrow_str(line) else {
+        return ptr::null_mut();
+    };
+    let entry = parsers::parse_log_line(line);
+    let result = json!({
+        "format": entry.format().as_str(),
+        "timestamp": entry.timestamp().map(|ts| ts.to_rfc3339()),
+        "parsed": entry.into_value(),
+    });
+    into_c_string(result.to_string())
+}
+
+/// Compiles a query. On failure returns null and, if `error_out` is non-null, stores
+/// an error message there (free it with [`loglens_string_free`]).
+///
+/// # Safety
+///
+/// `query` must be null or point to a NUL-terminated string; `error_out` must be null
+/// or valid for a write. The handle must be freed with [`loglens_query_free`].
+#[no_mangle]
+pub unsafe extern "C" fn loglens_query_compile(query: *const c_char, error_out: *mut *mut c_char) -> *mut LoglensQuery {
+    let result = match borrow_str(query) {
+        Some(query) => Query::parse(query).map_err(|e| e.to_string()),
+        None => Err("query is null or not valid UTF-8".to_string()),
+    };
+    match result {
+        Ok(query) => Box::into_raw(Box::new(LoglensQuery(query))),
+        Err(message) => {
+            if !error_out.is_null() {
+                *error_out = into_c_string(message);
+            }
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Parses `line` and evaluates the compiled query against it.
+/// Returns 1 on a match, 0 on no match and -1 on a null/invalid argument or an
+/// evaluation error.
+///
+/// # Safety
+///
+/// `query` must be null or a live handle from [`loglens_query_compile`]; `line` must be
+/// null or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn loglens_query_match(query: *const LoglensQuery, line: *const c_char) -> c_int {
+    let (Some(query), Some(line)) = (query.as_ref(), borrow_str(line)) else {
+        return -1;
+    };
+    match parsers::parse_log_line(line).matches(&query.0) {
+        Ok(true) => 1,
+        Ok(false) => 0,
+        Err(_) => -1,
+    }
+}
+
+/// Releases a compiled query. Null is ignored.
+///
+/// # Safety
+///
+/// `query` must be null or a handle from [`loglens_query_compile`] not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn loglens_query_free(query: *mut LoglensQuery) {
+    if !query.is_null() {
+        drop(Box::from_raw(query));
+    }
+}
+
+/// Releases a string returned by this module. Null is ignored.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this module and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn loglens_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
@@ -0,0 +1,43 @@
+// src/fuzzy.rs
+
+/// Smallest edit distance between `needle` and any substring of `haystack`
+/// (approximate substring matching), compared case-insensitively.
+///
+/// Returns `None` when even the best alignment needs more than `max_distance` edits.
+pub fn substring_distance(haystack: &str, needle: &str, max_distance: usize) -> Option<usize> {
+    let needle: Vec<char> = needle.chars().flat_map(char::to_lowercase).collect();
+    let haystack: Vec<char> = haystack.chars().flat_map(char::to_lowercase).collect();
+
+    // Column-wise DP over the needle; row 0 is free so a match may start anywhere.
+    let mut column: Vec<usize> = (0..=needle.len()).collect();
+    let mut best = column[needle.len()];
+
+    for &h in &haystack {
+        let mut diagonal = column[0];
+        column[0] = 0;
+        for i in 1..=needle.len() {
+            let substitution = diagonal + usize::from(needle[i - 1] != h);
+            diagonal = column[i];
+            column[i] = substitution.min(column[i] + 1).min(column[i - 1] + 1);
+        }
+        best = best.min(column[needle.len()]);
+        if best == 0 {
+            break;
+        }
+    }
+
+    (best <= max_distance).then_some(best)
+}
+
+/// Similarity in `0.0..=1.0` between `needle` and its best match inside `haystack`.
+/// `1.0` means the needle occurs verbatim (ignoring case).
+pub fn similarity(haystack: &str, needle: &str) -> f64 {
+    let len = needle.chars().count();
+    if len == 0 {
+        return 1.0;
+    }
+    match substring_distance(haystack, needle, len) {
+        Some(d) => 1.0 - d as f64 / len as f64,
+        None => 0.0,
+    }
+}
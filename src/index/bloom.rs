@@ -0,0 +1,99 @@
+// File: src/index/bloom.rs
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashSet;
+
+/// Bloom filter over the byte trigrams of lowercased text. A substring can only occur
+/// in the inserted text if every one of its trigrams does, so [`BloomFilter::may_contain`]
+/// answers "definitely not" for most absent needles, for any substring and not just
+/// whole words. Needles shorter than three bytes can't be ruled out.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BloomFilter {
+    /// Bit positions probed per trigram.
+    hashes: u32,
+    #[serde(serialize_with = "to_hex", deserialize_with = "from_hex")]
+    bits: Vec<u64>,
+}
+
+impl BloomFilter {
+    /// An empty filter sized for `items` distinct trigrams at `bits_per_item` bits each
+    /// (10 bits give about 1% false positives).
+    pub fn new(items: usize, bits_per_item: usize) -> Self {
+        let words = (items.max(1) * bits_per_item.max(1)).div_ceil(64);
+        // k = ln 2 * m / n minimizes the false positive rate
+        let hashes = ((bits_per_item as f64) * std::f64::consts::LN_2).round().clamp(1.0, 16.0) as u32;
+        BloomFilter { hashes, bits: vec![0; words] }
+    }
+
+    /// A filter holding exactly `trigrams`, as collected with [`trigrams`].
+    pub fn from_trigrams(trigrams: &HashSet<u32>, bits_per_item: usize) -> Self {
+        let mut filter = BloomFilter::new(trigrams.len(), bits_per_item);
+        for &trigram in trigrams {
+            filter.insert_trigram(trigram);
+        }
+        filter
+    }
+
+    pub fn insert_text(&mut self, text: &str) {
+        for trigram in trigrams(text) {
+            self.insert_trigram(trigram);
+        }
+    }
+
+    /// False when `needle` (compared case-insensitively) can't occur in any inserted text.
+    pub fn may_contain(&self, needle: &str) -> bool {
+        trigrams(needle).all(|trigram| {
+            positions(trigram, self.hashes, self.bits.len()).all(|(word, mask)| self.bits[word] & mask != 0)
+        })
+    }
+
+    fn insert_trigram(&mut self, trigram: u32) {
+        for (word, mask) in positions(trigram, self.hashes, self.bits.len()) {
+            self.bits[word] |= mask;
+        }
+    }
+}
+
+/// Double hashing: probe `i` is bit `h1 + i * h2` modulo the filter size, returned as
+/// a word index and mask.
+fn positions(trigram: u32, hashes: u32, words: usize) -> impl Iterator<Item = (usize, u64)> {
+    let len = words as u64 * 64;
+    let hash = mix(u64::from(trigram));
+    let (h1, h2) = (hash & 0xffff_ffff, (hash >> 32) | 1);
+    (0..u64::from(hashes)).map(move |i| {
+        let bit = h1.wrapping_add(i.wrapping_mul(h2)) % len;
+        ((bit / 64) as usize, 1 << (bit % 64))
+    })
+}
+
+/// The byte trigrams of `text` after lowercasing, each packed into a `u32`.
+pub fn trigrams(text: &str) -> impl Iterator<Item = u32> {
+    let lower = text.to_lowercase().into_bytes();
+    (0..lower.len().saturating_sub(2))
+        .map(move |i| u32::from(lower[i]) << 16 | u32::from(lower[i + 1]) << 8 | u32::from(lower[i + 2]))
+}
+
+/// splitmix64 finalizer, spreading the packed trigram over all 64 bits.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+fn to_hex<S: Serializer>(bits: &[u64], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&bits.iter().map(|word| format!("{:016x}", word)).collect::<String>())
+}
+
+fn from_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u64>, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    if hex.is_empty() || hex.len() % 16 != 0 {
+        return Err(serde::de::Error::custom("bloom filter bits must be a non-empty multiple of 16 hex digits"));
+    }
+    (0..hex.len())
+        .step_by(16)
+        .map(|i| {
+            let word = hex.get(i..i + 16).ok_or_else(|| serde::de::Error::custom("invalid bloom filter bits"))?;
+            u64::from_str_radix(word, 16).map_err(serde::de::Error::custom)
+        })
+        .collect()
+}
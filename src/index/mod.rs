@@ -0,0 +1,676 @@
+// File: src/index/mod.rs
+
+// Block index over a plain-text log file. The file is cut into blocks of
+// consecutive lines; each block records its byte range, its time range, per field
+// which values occur in it, and a bloom filter of its text. A query is checked
+// against those summaries first and only blocks that could hold a match are read
+// and evaluated.
+//
+// The summaries only ever over-approximate: a block is skipped when the query
+// provably can't match any of its lines under the default `QueryOptions`, so a
+// search returns exactly what a full scan would.
+
+pub mod bloom;
+
+use self::bloom::BloomFilter;
+use crate::level::Level;
+use crate::parsers::{parse_log_line, LogEntry};
+use crate::query::{Condition, Expr, Modifier, Operator, Query, QueryOptions, TextSearch};
+use crate::source::{self, SourceError};
+use crate::time::{self as time_parser, TimeOptions};
+use crate::transform::flatten::{flatten, DEFAULT_MAX_DEPTH};
+use crate::units;
+use crate::version::Version;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Bumped whenever the serialized layout changes; older index files are rebuilt.
+const FORMAT_VERSION: u32 = 2;
+
+/// Extension appended to a log file's name for its index (`app.log.llidx`).
+pub const INDEX_EXTENSION: &str = "llidx";
+
+/// Bytes hashed from the start of the file to tell an append from a replacement.
+const HEAD_LEN: usize = 4096;
+
+/// How the index is laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexOptions {
+    /// Lines per block. Smaller blocks skip more precisely but make the index larger.
+    pub block_lines: usize,
+    /// Distinct values kept per field. Once a field's dictionary is full, blocks with
+    /// new values only record that the field is present (and its numeric range).
+    pub max_values: usize,
+    /// Bloom filter bits per distinct trigram of a block's text, for skipping blocks on
+    /// substring and string equality searches (see [`bloom`]). 0 builds no filters.
+    pub bloom_bits: usize,
+}
+
+impl Default for IndexOptions {
+    fn default() -> Self {
+        IndexOptions { block_lines: 4096, max_values: 1024, bloom_bits: 10 }
+    }
+}
+
+impl IndexOptions {
+    pub fn with_block_lines(mut self, block_lines: usize) -> Self {
+        self.block_lines = block_lines.max(1);
+        self
+    }
+
+    pub fn with_max_values(mut self, max_values: usize) -> Self {
+        self.max_values = max_values;
+        self
+    }
+
+    pub fn with_bloom_bits(mut self, bloom_bits: usize) -> Self {
+        self.bloom_bits = bloom_bits;
+        self
+    }
+}
+
+/// Identifies the indexed file contents: growth with an unchanged head is an append.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Fingerprint {
+    len: u64,
+    modified: Option<SystemTime>,
+    head_len: usize,
+    head_hash: u64,
+}
+
+impl Fingerprint {
+    fn of(path: &Path) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        let mut head = Vec::with_capacity(HEAD_LEN);
+        File::open(path)?.take(HEAD_LEN as u64).read_to_end(&mut head)?;
+        Ok(Fingerprint {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+            head_len: head.len(),
+            head_hash: fnv1a(&head),
+        })
+    }
+
+    /// True when `current` is this file with lines appended.
+    fn is_prefix_of(&self, current: &Fingerprint, path: &Path) -> io::Result<bool> {
+        if current.len < self.len {
+            return Ok(false);
+        }
+        let mut head = Vec::with_capacity(self.head_len);
+        File::open(path)?.take(self.head_len as u64).read_to_end(&mut head)?;
+        Ok(head.len() == self.head_len && fnv1a(&head) == self.head_hash)
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// The distinct values seen for one field across the file; blocks refer to them by id.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Dictionary {
+    values: Vec<Value>,
+    /// Value (as JSON text) to id; rebuilt on first use after loading.
+    #[serde(skip)]
+    ids: HashMap<String, u32>,
+}
+
+impl Dictionary {
+    /// The id for `value`, adding it unless the dictionary already holds `max` values.
+    fn id(&mut self, value: &Value, max: usize) -> Option<u32> {
+        if self.ids.len() != self.values.len() {
+            self.ids = self.values.iter().enumerate().map(|(id, v)| (v.to_string(), id as u32)).collect();
+        }
+        let key = value.to_string();
+        if let Some(&id) = self.ids.get(&key) {
+            return Some(id);
+        }
+        if self.values.len() >= max {
+            return None;
+        }
+        let id = self.values.len() as u32;
+        self.values.push(value.clone());
+        self.ids.insert(key, id);
+        Some(id)
+    }
+}
+
+/// What one block holds for one field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct FieldSummary {
+    /// Lines in the block with the field.
+    present: usize,
+    /// Dictionary ids of every value, sorted; `None` when some value wasn't a scalar
+    /// or didn't fit in the dictionary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    values: Option<Vec<u32>>,
+    /// Smallest and largest value, kept when `values` is `None` and every value is a number.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    range: Option<(Value, Value)>,
+}
+
+/// A run of consecutive lines.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Block {
+    /// Byte offset of the first line.
+    pub offset: u64,
+    /// Length in bytes, line endings included.
+    pub len: u64,
+    /// 1-based number of the first line.
+    pub first_line: usize,
+    pub lines: usize,
+    /// Earliest and latest timestamp in the block's timestamp fields.
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    /// By lowercased, flattened field name.
+    fields: BTreeMap<String, FieldSummary>,
+    /// Trigrams of the raw lines and of every string value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bloom: Option<BloomFilter>,
+}
+
+/// An index over one file. Build it with [`Index::build`], or use [`Index::open`] to
+/// keep a sidecar index file up to date across runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Index {
+    version: u32,
+    options: IndexOptions,
+    fingerprint: Fingerprint,
+    dictionaries: BTreeMap<String, Dictionary>,
+    blocks: Vec<Block>,
+}
+
+/// The index file used for `path` by [`Index::open`].
+pub fn index_path(path: impl AsRef<Path>) -> PathBuf {
+    let mut name = path.as_ref().as_os_str().to_owned();
+    name.push(".");
+    name.push(INDEX_EXTENSION);
+    PathBuf::from(name)
+}
+
+impl Index {
+    /// Indexes `path` with the default [`IndexOptions`].
+    pub fn build(path: impl AsRef<Path>) -> io::Result<Index> {
+        Index::build_with(path, IndexOptions::default())
+    }
+
+    pub fn build_with(path: impl AsRef<Path>, options: IndexOptions) -> io::Result<Index> {
+        let path = path.as_ref();
+        let mut index = Index {
+            version: FORMAT_VERSION,
+            options,
+            fingerprint: Fingerprint::of(path)?,
+            dictionaries: BTreeMap::new(),
+            blocks: Vec::new(),
+        };
+        index.index_from(path, 0, 1)?;
+        Ok(index)
+    }
+
+    /// Loads the index stored next to `path` (see [`index_path`]), brings it up to date
+    /// with [`Index::refresh`] and saves it back if anything changed. A missing,
+    /// unreadable or outdated index file is rebuilt.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Index> {
+        let path = path.as_ref();
+        let sidecar = index_path(path);
+        let mut index = match Index::load(&sidecar) {
+            Ok(index) => index,
+            Err(_) => {
+                let index = Index::build(path)?;
+                index.save(&sidecar)?;
+                return Ok(index);
+            }
+        };
+        if index.refresh(path)? {
+            index.save(&sidecar)?;
+        }
+        Ok(index)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Index> {
+        let reader = BufReader::new(File::open(path)?);
+        let index: Index = serde_json::from_reader(reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if index.version != FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("index format {} is not supported (expected {})", index.version, FORMAT_VERSION),
+            ));
+        }
+        Ok(index)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, self).map_err(io::Error::from)?;
+        io::Write::flush(&mut writer)
+    }
+
+    /// Catches the index up with `path`. Appended lines are indexed incrementally
+    /// (re-reading only the last block, which may have been partial); a file that
+    /// shrank or whose beginning changed, e.g. after rotation, is indexed from scratch.
+    /// Returns whether the index changed.
+    pub fn refresh(&mut self, path: impl AsRef<Path>) -> io::Result<bool> {
+        let path = path.as_ref();
+        let current = Fingerprint::of(path)?;
+        if current == self.fingerprint {
+            return Ok(false);
+        }
+
+        let (offset, first_line) = if self.fingerprint.is_prefix_of(&current, path)? {
+            match self.blocks.pop() {
+                Some(last) => (last.offset, last.first_line),
+                None => (0, 1),
+            }
+        } else {
+            self.dictionaries.clear();
+            self.blocks.clear();
+            (0, 1)
+        };
+        self.fingerprint = current;
+        self.index_from(path, offset, first_line)?;
+        Ok(true)
+    }
+
+    pub fn options(&self) -> IndexOptions {
+        self.options
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    /// Lines covered by the index.
+    pub fn line_count(&self) -> usize {
+        self.blocks.iter().map(|block| block.lines).sum()
+    }
+
+    /// Reads `path` from `offset` to the end, appending blocks.
+    fn index_from(&mut self, path: &Path, offset: u64, first_line: usize) -> io::Result<()> {
+        let mut reader = BufReader::new(File::open(path)?);
+        if source::is_compressed(reader.fill_buf()?) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "compressed files can't be indexed; decompress them first",
+            ));
+        }
+        reader.seek(SeekFrom::Start(offset))?;
+
+        let bloom = self.options.bloom_bits > 0;
+        let mut block = BlockBuilder::new(offset, first_line, bloom);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            let read = reader.read_until(b'\n', &mut buf)?;
+            if read == 0 {
+                break;
+            }
+            let entry = parse_log_line(&line_text(&buf));
+            block.observe(&entry, &mut self.dictionaries, self.options.max_values);
+            block.len += read as u64;
+
+            if block.lines >= self.options.block_lines {
+                let next = BlockBuilder::new(block.offset + block.len, block.first_line + block.lines, bloom);
+                self.blocks.push(std::mem::replace(&mut block, next).finish(self.options.bloom_bits));
+            }
+        }
+        if block.lines > 0 {
+            self.blocks.push(block.finish(self.options.bloom_bits));
+        }
+        Ok(())
+    }
+
+    /// The blocks that may hold lines matching `query`, in file order.
+    pub fn candidates(&self, query: &Query) -> Vec<&Block> {
+        self.blocks.iter().filter(|block| self.may_match(&query.expr, block, query.now())).collect()
+    }
+
+    /// Lines of the candidate blocks for `query`, with their 1-based line numbers. Lines
+    /// in these blocks may still not match; see [`Index::search`] for evaluated results.
+    ///
+    /// The index must be current for `path` (see [`Index::refresh`]).
+    pub fn lines(&self, path: impl AsRef<Path>, query: &Query) -> io::Result<IndexedLines<'_>> {
+        Ok(IndexedLines {
+            reader: BufReader::new(File::open(path)?),
+            blocks: self.candidates(query).into_iter(),
+            remaining: 0,
+            line_number: 0,
+            buf: Vec::new(),
+        })
+    }
+
+    /// The entries of `path` matching `query`, reading only the candidate blocks. Entries
+    /// carry their line numbers and the file as their source.
+    pub fn search<'a>(
+        &'a self,
+        path: impl AsRef<Path>,
+        query: &'a Query,
+    ) -> io::Result<impl Iterator<Item = Result<LogEntry, SourceError>> + 'a> {
+        let source = path.as_ref().to_string_lossy().into_owned();
+        let lines = self.lines(path, query)?;
+        Ok(lines.filter_map(move |line| {
+            let (line_number, line) = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(SourceError::Io(e))),
+            };
+            let entry = parse_log_line(&line).with_line_number(line_number).with_source(source.clone());
+            match entry.matches(query) {
+                Ok(true) => Some(Ok(entry)),
+                Ok(false) => None,
+                Err(e) => Some(Err(SourceError::Query(e))),
+            }
+        }))
+    }
+
+    fn may_match(&self, expr: &Expr, block: &Block, now: DateTime<Utc>) -> bool {
+        match expr {
+            Expr::And(clauses) => clauses.iter().all(|clause| self.may_match(clause, block, now)),
+            Expr::Or(clauses) => clauses.iter().any(|clause| self.may_match(clause, block, now)),
+            Expr::Condition(condition) => self.condition_may_match(condition, block, now),
+            Expr::Text(TextSearch { term, negate: false, .. }) => {
+                block.bloom.as_ref().is_none_or(|bloom| bloom.may_contain(term))
+            }
+            Expr::All | Expr::Text(_) | Expr::Not(_) => true,
+        }
+    }
+
+    fn condition_may_match(&self, condition: &Condition, block: &Block, now: DateTime<Utc>) -> bool {
+        let name = condition.field.name.as_str();
+        let plain = condition.field.modifier.is_none() && !is_timestamp_field(name);
+        if plain && block.bloom.as_ref().is_some_and(|bloom| bloom_rules_out(condition, bloom)) {
+            return false;
+        }
+        // Modifiers reading other fields, or the clock, aren't summarized
+        let modifier = condition.field.modifier;
+        let by_value = matches!(modifier, None | Some(Modifier::Num | Modifier::Dur | Modifier::Semver));
+        if !by_value || name == "text" || name.starts_with('/') || name.contains('*') {
+            return true;
+        }
+        if is_timestamp_field(name) {
+            return time_may_match(condition, block, now);
+        }
+
+        let key = name.to_ascii_lowercase();
+        // An object or array under this name was flattened into longer keys
+        let nested = |k: &String| k.strip_prefix(key.as_str()).is_some_and(|rest| rest.starts_with(['.', '[']));
+        if block.fields.keys().any(nested) {
+            return true;
+        }
+
+        let probe = Query::from_expr(Expr::Condition(condition.clone())).with_now(now);
+        let holds = |value: Option<&Value>| {
+            let mut object = Map::new();
+            if let Some(value) = value {
+                object.insert(name.to_string(), value.clone());
+            }
+            probe.matches(&Value::Object(object), "").unwrap_or(true)
+        };
+
+        let summary = block.fields.get(&key);
+        if summary.map_or(0, |s| s.present) < block.lines && holds(None) {
+            return true;
+        }
+        let Some(summary) = summary else { return false };
+        match (&summary.values, self.dictionaries.get(&key)) {
+            (Some(ids), Some(dictionary)) => ids.iter().any(|&id| holds(dictionary.values.get(id as usize))),
+            // Numeric ordering is monotonic in the value, so the extremes decide
+            _ if modifier == Some(Modifier::Semver) => true,
+            _ => match (&summary.range, condition.op) {
+                (Some((_, max)), Operator::Gt | Operator::Ge) => holds(Some(max)),
+                (Some((min, _)), Operator::Lt | Operator::Le) => holds(Some(min)),
+                _ => true,
+            },
+        }
+    }
+}
+
+/// True when `condition` needs a substring the block's filter doesn't hold: `text contains`
+/// (every comma-separated term), `contains` on a field, or string equality (`==`, `~=`)
+/// with a literal that can only equal an identical string.
+fn bloom_rules_out(condition: &Condition, bloom: &BloomFilter) -> bool {
+    let unquote = |s: &str| s.trim().trim_matches(|c| c == '"' || c == '\'').to_string();
+    let literal = unquote(&condition.value);
+    let text = condition.field.name == "text";
+    match condition.op {
+        Operator::Contains if text => condition.value.split(',').map(unquote).any(|term| !bloom.may_contain(&term)),
+        Operator::Contains => !bloom.may_contain(&literal),
+        Operator::Eq | Operator::Is | Operator::IEq if !text && is_string_literal(&literal) => {
+            !bloom.may_contain(&literal)
+        }
+        _ => false,
+    }
+}
+
+/// A literal equality only matches as the same string: not a number, boolean, level,
+/// version, status class or unit literal, which compare by meaning rather than text.
+fn is_string_literal(literal: &str) -> bool {
+    let status_class = matches!(literal.as_bytes(), [b'1'..=b'5', b'x' | b'X', b'x' | b'X']);
+    literal.parse::<f64>().is_err()
+        && !literal.eq_ignore_ascii_case("true")
+        && !literal.eq_ignore_ascii_case("false")
+        && !status_class
+        && Level::parse(literal).is_none()
+        && !Version::looks_like_version(literal)
+        && units::parse_duration_nanos(literal).is_none()
+        && units::parse_byte_size(literal).is_none()
+}
+
+fn is_timestamp_field(name: &str) -> bool {
+    QueryOptions::default_ref().timestamp_fields.iter().any(|key| key.eq_ignore_ascii_case(name))
+}
+
+/// Entries without a timestamp never satisfy time comparisons, so a block without
+/// one is skipped; otherwise its time range must overlap the condition's.
+fn time_may_match(condition: &Condition, block: &Block, now: DateTime<Utc>) -> bool {
+    if !matches!(condition.op, Operator::Gt | Operator::Ge | Operator::Lt | Operator::Le | Operator::Between) {
+        return true;
+    }
+    let (Some(start), Some(end)) = (block.start, block.end) else { return false };
+    let times = condition.times(now, &TimeOptions { now: Some(now), ..TimeOptions::default() });
+    let times: Vec<DateTime<Utc>> = match times.iter().cloned().collect::<Result<_, _>>() {
+        Ok(times) => times,
+        Err(_) => return true,
+    };
+    match (condition.op, times.as_slice()) {
+        (Operator::Gt, [t]) => end > *t,
+        (Operator::Ge, [t]) => end >= *t,
+        (Operator::Lt, [t]) => start < *t,
+        (Operator::Le, [t]) => start <= *t,
+        (Operator::Between, [a, b]) => end >= *a.min(b) && start <= *a.max(b),
+        _ => true,
+    }
+}
+
+/// Timestamps a time condition could compare this entry by: each timestamp field,
+/// found as queries find it.
+fn entry_times(value: &Value) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+    QueryOptions::default_ref().timestamp_fields.iter().filter_map(move |key| {
+        let found = crate::query::resolve_field(value, key)?;
+        time_parser::parse_field_timestamp(key, found, TimeOptions::default_ref())
+    })
+}
+
+/// A line as [`source::Lines`] yields it: without its ending, invalid UTF-8 replaced.
+fn line_text(buf: &[u8]) -> String {
+    let line = buf.strip_suffix(b"\n").unwrap_or(buf);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    String::from_utf8_lossy(line).into_owned()
+}
+
+struct BlockBuilder {
+    offset: u64,
+    len: u64,
+    first_line: usize,
+    lines: usize,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    fields: BTreeMap<String, FieldBuilder>,
+    /// Collected when blocks get a bloom filter.
+    trigrams: Option<HashSet<u32>>,
+}
+
+#[derive(Default)]
+struct FieldBuilder {
+    present: usize,
+    ids: BTreeSet<u32>,
+    /// A value wasn't recorded in the dictionary.
+    overflow: bool,
+    range: Option<(Value, Value)>,
+    /// A value other than a number or null was seen.
+    non_numeric: bool,
+}
+
+impl BlockBuilder {
+    fn new(offset: u64, first_line: usize, bloom: bool) -> Self {
+        BlockBuilder {
+            offset,
+            len: 0,
+            first_line,
+            lines: 0,
+            start: None,
+            end: None,
+            fields: BTreeMap::new(),
+            trigrams: bloom.then(HashSet::new),
+        }
+    }
+
+    fn observe(&mut self, entry: &LogEntry, dictionaries: &mut BTreeMap<String, Dictionary>, max_values: usize) {
+        self.lines += 1;
+        if let Some(trigrams) = &mut self.trigrams {
+            trigrams.extend(bloom::trigrams(entry.raw()));
+            if let Some(value) = entry.value() {
+                collect_string_trigrams(value, trigrams);
+            }
+        }
+        let Some(value) = entry.value() else { return };
+
+        for time in entry_times(value) {
+            self.start = Some(self.start.map_or(time, |start| start.min(time)));
+            self.end = Some(self.end.map_or(time, |end| end.max(time)));
+        }
+
+        let Value::Object(flat) = flatten(value, DEFAULT_MAX_DEPTH) else { return };
+        let mut seen = HashSet::new();
+        for (key, value) in flat {
+            let key = key.to_ascii_lowercase();
+            let field = self.fields.entry(key.clone()).or_default();
+            if seen.insert(key.clone()) {
+                field.present += 1;
+            }
+            field.observe(&value, dictionaries.entry(key).or_default(), max_values);
+        }
+    }
+
+    fn finish(self, bloom_bits: usize) -> Block {
+        let fields = self
+            .fields
+            .into_iter()
+            .map(|(key, field)| {
+                let summary = FieldSummary {
+                    present: field.present,
+                    values: (!field.overflow).then(|| field.ids.into_iter().collect()),
+                    range: field.range.filter(|_| field.overflow && !field.non_numeric),
+                };
+                (key, summary)
+            })
+            .collect();
+        Block {
+            offset: self.offset,
+            len: self.len,
+            first_line: self.first_line,
+            lines: self.lines,
+            start: self.start,
+            end: self.end,
+            fields,
+            bloom: self.trigrams.map(|trigrams| BloomFilter::from_trigrams(&trigrams, bloom_bits)),
+        }
+    }
+}
+
+/// Strings are matched unescaped, so they are added besides the raw line.
+fn collect_string_trigrams(value: &Value, trigrams: &mut HashSet<u32>) {
+    match value {
+        Value::String(s) => trigrams.extend(bloom::trigrams(s)),
+        Value::Array(items) => items.iter().for_each(|item| collect_string_trigrams(item, trigrams)),
+        Value::Object(map) => map.values().for_each(|item| collect_string_trigrams(item, trigrams)),
+        _ => {}
+    }
+}
+
+impl FieldBuilder {
+    fn observe(&mut self, value: &Value, dictionary: &mut Dictionary, max_values: usize) {
+        match value {
+            Value::Number(n) => {
+                let n = n.as_f64().unwrap_or_default();
+                let (min, max) = self.range.get_or_insert_with(|| (value.clone(), value.clone()));
+                if n < min.as_f64().unwrap_or_default() {
+                    *min = value.clone();
+                }
+                if n > max.as_f64().unwrap_or_default() {
+                    *max = value.clone();
+                }
+            }
+            Value::Null => {}
+            _ => self.non_numeric = true,
+        }
+        if self.overflow {
+            return;
+        }
+        let id = match value {
+            // Kept whole by `flatten` (empty, or nested too deep)
+            Value::Array(_) | Value::Object(_) => None,
+            scalar => dictionary.id(scalar, max_values),
+        };
+        match id {
+            Some(id) => {
+                self.ids.insert(id);
+            }
+            None => self.overflow = true,
+        }
+    }
+}
+
+/// Lines of an index's candidate blocks, as `(line number, line)`.
+pub struct IndexedLines<'a> {
+    reader: BufReader<File>,
+    blocks: std::vec::IntoIter<&'a Block>,
+    /// Bytes left in the current block.
+    remaining: u64,
+    line_number: usize,
+    buf: Vec<u8>,
+}
+
+impl Iterator for IndexedLines<'_> {
+    type Item = io::Result<(usize, String)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining == 0 {
+            let block = self.blocks.next()?;
+            if let Err(e) = self.reader.seek(SeekFrom::Start(block.offset)) {
+                return Some(Err(e));
+            }
+            self.remaining = block.len;
+            self.line_number = block.first_line;
+        }
+
+        self.buf.clear();
+        let read = match self.reader.read_until(b'\n', &mut self.buf) {
+            Ok(0) => {
+                // The file shrank since it was indexed
+                self.remaining = 0;
+                return self.next();
+            }
+            Ok(read) => read as u64,
+            Err(e) => return Some(Err(e)),
+        };
+        self.remaining = self.remaining.saturating_sub(read);
+        let line_number = self.line_number;
+        self.line_number += 1;
+        Some(Ok((line_number, line_text(&self.buf))))
+    }
+}
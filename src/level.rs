@@ -0,0 +1,115 @@
+// src/level.rs
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// Canonical log severity, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl Level {
+    /// Parses a level name or alias (case-insensitive), or a numeric level.
+    pub fn parse(s: &str) -> Option<Level> {
+        let s = s.trim().trim_matches(|c| c == '"' || c == '\'');
+        match s.to_ascii_lowercase().as_str() {
+            "trace" | "trc" | "verbose" => Some(Level::Trace),
+            "debug" | "dbg" => Some(Level::Debug),
+            "info" | "inf" | "information" | "informational" | "notice" => Some(Level::Info),
+            "warn" | "wrn" | "warning" => Some(Level::Warn),
+            "error" | "err" | "eror" => Some(Level::Error),
+            "fatal" | "critical" | "crit" | "panic" | "alert" | "emerg" | "emergency" => Some(Level::Fatal),
+            other => other.parse::<u64>().ok().and_then(Level::from_number),
+        }
+    }
+
+    /// Maps a numeric level onto the canonical scale.
+    ///
+    /// `0..=7` are syslog severities (0 = emergency, 7 = debug) and
+    /// `10..=60` are the bunyan/pino steps (10 = trace, 60 = fatal).
+    pub fn from_number(n: u64) -> Option<Level> {
+        match n {
+            0..=7 => Level::from_syslog(n as u8),
+            10 => Some(Level::Trace),
+            20 => Some(Level::Debug),
+            30 => Some(Level::Info),
+            40 => Some(Level::Warn),
+            50 => Some(Level::Error),
+            60 => Some(Level::Fatal),
+            _ => None,
+        }
+    }
+
+    /// Maps a syslog severity (RFC 5424) onto the canonical scale.
+    pub fn from_syslog(severity: u8) -> Option<Level> {
+        match severity {
+            0..=2 => Some(Level::Fatal),
+            3 => Some(Level::Error),
+            4 => Some(Level::Warn),
+            5 | 6 => Some(Level::Info),
+            7 => Some(Level::Debug),
+            _ => None,
+        }
+    }
+
+    /// Like [`Level::parse`], but also accepts the single-letter abbreviations
+    /// (`W`, `E`, ...) some loggers write into their level field. Those are too
+    /// ambiguous to accept in free text.
+    pub fn parse_field(s: &str) -> Option<Level> {
+        match s.trim() {
+            "T" | "t" => Some(Level::Trace),
+            "D" | "d" => Some(Level::Debug),
+            "I" | "i" | "N" | "n" => Some(Level::Info),
+            "W" | "w" => Some(Level::Warn),
+            "E" | "e" => Some(Level::Error),
+            "F" | "f" | "C" | "c" => Some(Level::Fatal),
+            other => Level::parse(other),
+        }
+    }
+
+    /// The bunyan/pino number for the level (10 = trace ... 60 = fatal).
+    pub fn as_number(&self) -> u64 {
+        match self {
+            Level::Trace => 10,
+            Level::Debug => 20,
+            Level::Info => 30,
+            Level::Warn => 40,
+            Level::Error => 50,
+            Level::Fatal => 60,
+        }
+    }
+
+    /// Parses a JSON value holding either a level name or a numeric level.
+    pub fn from_value(value: &Value) -> Option<Level> {
+        match value {
+            Value::String(s) => Level::parse(s),
+            Value::Number(n) => n.as_u64().and_then(Level::from_number),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Trace => "trace",
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+            Level::Error => "error",
+            Level::Fatal => "fatal",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
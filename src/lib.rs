@@ -1,12 +1,57 @@
 // loglens-core/src/lib.rs
 
+pub mod aggregate;
+pub mod anomaly;
+pub mod correlate;
+pub mod dialect;
+pub mod diff;
+pub mod ecs;
+pub mod fuzzy;
+pub mod level;
+pub mod merge;
+pub mod output;
 pub mod parsers;
+pub mod patterns;
+pub mod pipeline;
 pub mod query;
+pub mod rules;
+pub mod schema;
+pub mod session;
+pub mod source;
+pub mod suggest;
+pub mod tail;
 pub mod time;
+pub mod transform;
+pub mod units;
+pub mod version;
 
 // Re-export for easy access
 pub use parsers::LogEntry;
-pub use query::evaluate;
+pub use query::{evaluate, evaluate_with_options, Query, QueryOptions};
+
+// Multi-threaded batch evaluation, behind the 'parallel' feature
+#[cfg(feature = "parallel")]
+pub mod parallel;
+
+// On-disk block index for re-querying large files, behind the 'index' feature
+#[cfg(feature = "index")]
+pub mod index;
+
+// Stream-based equivalents for async applications, behind the 'async' feature
+#[cfg(feature = "async")]
+pub mod stream;
+
+// OpenTelemetry log records (OTLP/JSON), behind the 'otlp' feature
+#[cfg(feature = "otlp")]
+pub mod otlp;
+
+// C-compatible bindings for non-WASM embedders, behind the 'ffi' feature
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+// Python bindings via PyO3, behind the 'python' feature
+#[cfg(feature = "python")]
+mod python;
 
 // Only compile the wasm module if the 'wasm' feature is enabled
 #[cfg(feature = "wasm")]
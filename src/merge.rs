@@ -0,0 +1,178 @@
+// src/merge.rs
+
+use crate::pipeline::Record;
+use crate::time::{self as time_parser, TimeOptions};
+use chrono::{DateTime, Utc};
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::time::Duration;
+
+/// Records held back at most, across all sources, before ordering gives way to memory.
+pub const DEFAULT_MAX_BUFFERED: usize = 10_000;
+
+/// A record released by [`Merge`], tagged with where it came from.
+#[derive(Debug)]
+pub struct MergedRecord {
+    /// Index of the source in the order passed to [`Merge::new`].
+    pub source: usize,
+    /// The extracted timestamp, or `None` when the record had none.
+    pub timestamp: Option<DateTime<Utc>>,
+    pub record: Record,
+}
+
+/// K-way merge of several per-source-ordered record streams into one stream
+/// ordered by extracted timestamp.
+///
+/// Each source may run up to the lateness window out of order. A record is held
+/// until every unfinished source has read past its timestamp plus the window, or
+/// until the buffer is full. Records without a timestamp keep the position of the
+/// previous record from the same source, so continuation lines stay with their entry.
+pub struct Merge<I> {
+    sources: Vec<SourceState<I>>,
+    heap: BinaryHeap<Reverse<Pending>>,
+    lateness: chrono::Duration,
+    max_buffered: usize,
+    timestamp_fields: Option<Vec<String>>,
+    sequence: u64,
+}
+
+struct SourceState<I> {
+    records: I,
+    finished: bool,
+    /// Latest timestamp read from this source, if any record has been read yet.
+    latest: Option<DateTime<Utc>>,
+}
+
+struct Pending {
+    at: DateTime<Utc>,
+    source: usize,
+    sequence: u64,
+    timestamp: Option<DateTime<Utc>>,
+    record: Record,
+}
+
+impl Pending {
+    fn key(&self) -> (DateTime<Utc>, usize, u64) {
+        (self.at, self.source, self.sequence)
+    }
+}
+
+impl PartialEq for Pending {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Pending {}
+
+impl PartialOrd for Pending {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Pending {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl<I> Merge<I>
+where
+    I: Iterator<Item = Record>,
+{
+    /// Merges `sources`, each already ordered by time, with no lateness allowed.
+    pub fn new(sources: impl IntoIterator<Item = I>) -> Self {
+        Merge {
+            sources: sources
+                .into_iter()
+                .map(|records| SourceState { records, finished: false, latest: None })
+                .collect(),
+            heap: BinaryHeap::new(),
+            lateness: chrono::Duration::zero(),
+            max_buffered: DEFAULT_MAX_BUFFERED,
+            timestamp_fields: None,
+            sequence: 0,
+        }
+    }
+
+    /// How far out of order a single source may be.
+    pub fn set_lateness(&mut self, lateness: Duration) {
+        self.lateness = chrono::Duration::from_std(lateness).unwrap_or(chrono::Duration::MAX);
+    }
+
+    /// Caps the number of held-back records; past it, the earliest is released early.
+    pub fn set_max_buffered(&mut self, max_buffered: usize) {
+        self.max_buffered = max_buffered.max(1);
+    }
+
+    /// Field names to read timestamps from, instead of the common defaults.
+    pub fn set_timestamp_fields(&mut self, fields: Vec<String>) {
+        self.timestamp_fields = Some(fields);
+    }
+
+    /// The point every unfinished source has passed; `None` means "everything is safe".
+    fn watermark(&self) -> Option<DateTime<Utc>> {
+        self.sources
+            .iter()
+            .filter(|source| !source.finished)
+            .map(|source| match source.latest {
+                Some(latest) => latest.checked_sub_signed(self.lateness).unwrap_or(DateTime::<Utc>::MIN_UTC),
+                None => DateTime::<Utc>::MIN_UTC,
+            })
+            .min()
+    }
+
+    /// Reads one record from the source that is holding the watermark back.
+    fn pull(&mut self) -> bool {
+        let Some(index) = (0..self.sources.len())
+            .filter(|&i| !self.sources[i].finished)
+            .min_by_key(|&i| self.sources[i].latest)
+        else {
+            return false;
+        };
+
+        let source = &mut self.sources[index];
+        let Some(record) = source.records.next() else {
+            source.finished = true;
+            return true;
+        };
+
+        let timestamp = match &self.timestamp_fields {
+            Some(fields) => time_parser::extract_timestamp_with_keys(record.value(), fields, &TimeOptions::default()),
+            None => time_parser::extract_and_parse_timestamp(record.value()),
+        };
+        let at = timestamp.or(source.latest).unwrap_or(DateTime::<Utc>::MIN_UTC);
+        source.latest = Some(source.latest.map_or(at, |latest| latest.max(at)));
+
+        self.sequence += 1;
+        self.heap.push(Reverse(Pending { at, source: index, sequence: self.sequence, timestamp, record }));
+        true
+    }
+}
+
+impl<I> Iterator for Merge<I>
+where
+    I: Iterator<Item = Record>,
+{
+    type Item = MergedRecord;
+
+    fn next(&mut self) -> Option<MergedRecord> {
+        loop {
+            if let Some(Reverse(earliest)) = self.heap.peek() {
+                let safe = self.watermark().is_none_or(|watermark| earliest.at <= watermark);
+                if safe || self.heap.len() >= self.max_buffered {
+                    let Reverse(pending) = self.heap.pop()?;
+                    return Some(MergedRecord {
+                        source: pending.source,
+                        timestamp: pending.timestamp,
+                        record: pending.record,
+                    });
+                }
+            }
+            if !self.pull() {
+                return None;
+            }
+        }
+    }
+}
@@ -0,0 +1,326 @@
+// src/otlp.rs
+
+// OpenTelemetry log data model, serialized in the OTLP/JSON encoding: POST an
+// [`ExportLogsServiceRequest`] to a collector's `/v1/logs` endpoint with
+// `Content-Type: application/json`. 64-bit integers are written as strings and
+// trace/span ids as lowercase hex, as the encoding requires.
+
+use crate::level::Level;
+use crate::parsers::{LogEntry, LEVEL_KEYS, MESSAGE_KEYS};
+use crate::time as time_parser;
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+
+/// Fields a trace id is taken from, in order of preference.
+const TRACE_ID_KEYS: &[&str] = &["trace_id", "traceId", "trace.id", "traceid", "dd.trace_id", "otel.trace_id"];
+/// Fields a span id is taken from, in order of preference.
+const SPAN_ID_KEYS: &[&str] = &["span_id", "spanId", "span.id", "spanid", "dd.span_id", "otel.span_id"];
+/// W3C trace context header (`00-<trace id>-<span id>-<flags>`), used when no id field is set.
+const TRACEPARENT_KEY: &str = "traceparent";
+
+/// Derived by the parsers from fields already carried as severity and timestamp.
+const DERIVED: &[&str] = &["level_num", "time_local", "@t"];
+
+/// A log record as defined by the OpenTelemetry logs data model.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogRecord {
+    /// When the event occurred; zero when the entry has no timestamp.
+    #[serde(serialize_with = "as_string", skip_serializing_if = "is_default")]
+    pub time_unix_nano: u64,
+    /// When the record was converted.
+    #[serde(serialize_with = "as_string")]
+    pub observed_time_unix_nano: u64,
+    /// 1-24 (see [`severity_number`]); 0 when the level is unknown.
+    #[serde(skip_serializing_if = "is_default")]
+    pub severity_number: i32,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub severity_text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<AnyValue>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<KeyValue>,
+    /// 32 lowercase hex digits, or empty.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub trace_id: String,
+    /// 16 lowercase hex digits, or empty.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub span_id: String,
+    /// W3C trace flags (bit 0: sampled).
+    #[serde(skip_serializing_if = "is_default")]
+    pub flags: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeyValue {
+    pub key: String,
+    pub value: AnyValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AnyValue {
+    StringValue(String),
+    BoolValue(bool),
+    IntValue(#[serde(serialize_with = "as_string")] i64),
+    DoubleValue(f64),
+    ArrayValue(ArrayValue),
+    KvlistValue(KeyValueList),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ArrayValue {
+    pub values: Vec<AnyValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeyValueList {
+    pub values: Vec<KeyValue>,
+}
+
+/// The body of an OTLP/HTTP logs export.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportLogsServiceRequest {
+    pub resource_logs: Vec<ResourceLogs>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceLogs {
+    pub resource: Resource,
+    pub scope_logs: Vec<ScopeLogs>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Resource {
+    pub attributes: Vec<KeyValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopeLogs {
+    pub scope: InstrumentationScope,
+    pub log_records: Vec<LogRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InstrumentationScope {
+    pub name: String,
+    pub version: String,
+}
+
+impl ExportLogsServiceRequest {
+    /// One resource (e.g. `service.name`, `host.name`) holding `log_records`, attributed
+    /// to this crate as the instrumentation scope.
+    pub fn new(resource: Vec<KeyValue>, log_records: Vec<LogRecord>) -> Self {
+        let scope = InstrumentationScope {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        };
+        ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                resource: Resource { attributes: resource },
+                scope_logs: vec![ScopeLogs { scope, log_records }],
+            }],
+        }
+    }
+}
+
+impl KeyValue {
+    pub fn new(key: impl Into<String>, value: impl Into<AnyValue>) -> Self {
+        KeyValue { key: key.into(), value: value.into() }
+    }
+}
+
+impl From<&str> for AnyValue {
+    fn from(s: &str) -> Self {
+        AnyValue::StringValue(s.to_string())
+    }
+}
+
+impl From<String> for AnyValue {
+    fn from(s: String) -> Self {
+        AnyValue::StringValue(s)
+    }
+}
+
+impl AnyValue {
+    /// Converts a JSON value; `None` for null, which OTLP has no representation for.
+    /// Null array elements and object members are dropped.
+    pub fn from_json(value: &Value) -> Option<AnyValue> {
+        Some(match value {
+            Value::Null => return None,
+            Value::Bool(b) => AnyValue::BoolValue(*b),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => AnyValue::IntValue(i),
+                None => AnyValue::DoubleValue(n.as_f64()?),
+            },
+            Value::String(s) => AnyValue::StringValue(s.clone()),
+            Value::Array(items) => {
+                AnyValue::ArrayValue(ArrayValue { values: items.iter().filter_map(AnyValue::from_json).collect() })
+            }
+            Value::Object(map) => AnyValue::KvlistValue(KeyValueList { values: key_values(map) }),
+        })
+    }
+}
+
+fn key_values(map: &Map<String, Value>) -> Vec<KeyValue> {
+    map.iter()
+        .filter_map(|(key, value)| Some(KeyValue { key: key.clone(), value: AnyValue::from_json(value)? }))
+        .collect()
+}
+
+/// The severity number for a level: the first of each range the data model defines
+/// (TRACE 1, DEBUG 5, INFO 9, WARN 13, ERROR 17, FATAL 21).
+pub fn severity_number(level: Level) -> i32 {
+    match level {
+        Level::Trace => 1,
+        Level::Debug => 5,
+        Level::Info => 9,
+        Level::Warn => 13,
+        Level::Error => 17,
+        Level::Fatal => 21,
+    }
+}
+
+/// Converts an entry into an OTLP log record:
+///
+/// - `timeUnixNano` from the entry's timestamp, `observedTimeUnixNano` the time of conversion
+/// - `severityNumber` from the detected level, `severityText` the level as written
+/// - `body` the `message`/`msg` field, or the raw line for unstructured entries
+/// - `traceId`/`spanId` from `trace_id`/`span_id` and their usual spellings (`traceId`,
+///   `trace.id`, `dd.trace_id`, ...) or a W3C `traceparent`; Datadog's decimal ids are
+///   converted to hex and 64-bit trace ids zero-padded. Invalid ids are left as attributes.
+/// - every other field as an attribute (objects as key-value lists), plus
+///   `log.file.path` when the entry has a source
+pub fn to_log_record(entry: &LogEntry) -> LogRecord {
+    to_log_record_at(entry, Utc::now())
+}
+
+/// Like [`to_log_record`], with an explicit observed time.
+pub fn to_log_record_at(entry: &LogEntry, observed: DateTime<Utc>) -> LogRecord {
+    let mut record = LogRecord {
+        time_unix_nano: entry.timestamp().map_or(0, unix_nanos),
+        observed_time_unix_nano: unix_nanos(observed),
+        severity_number: 0,
+        severity_text: String::new(),
+        body: None,
+        attributes: Vec::new(),
+        trace_id: String::new(),
+        span_id: String::new(),
+        flags: 0,
+    };
+
+    let Some(Value::Object(fields)) = entry.value() else {
+        record.body = Some(AnyValue::from(entry.raw()));
+        if let Some(level) = entry.level() {
+            record.severity_number = severity_number(level);
+            record.severity_text = level.as_str().to_uppercase();
+        }
+        record.attributes.extend(entry.source().map(|source| KeyValue::new("log.file.path", source)));
+        return record;
+    };
+    let mut fields = fields.clone();
+
+    if record.time_unix_nano != 0 {
+        for key in time_parser::COMMON_KEYS {
+            fields.remove(*key);
+        }
+    }
+    for key in DERIVED {
+        fields.remove(*key);
+    }
+    let level = LEVEL_KEYS.iter().find_map(|key| Some((*key, Level::from_value(fields.get(*key)?)?)));
+    if let Some((key, level)) = level {
+        record.severity_number = severity_number(level);
+        record.severity_text = match fields.remove(key) {
+            Some(Value::String(text)) => text,
+            _ => level.as_str().to_uppercase(),
+        };
+    }
+    let message = MESSAGE_KEYS.iter().find(|key| fields.get(**key).is_some_and(Value::is_string));
+    record.body = match message {
+        Some(key) => fields.remove(*key).as_ref().and_then(AnyValue::from_json),
+        None => Some(AnyValue::from(entry.raw())),
+    };
+
+    if let Some((key, id)) = find_id(&fields, TRACE_ID_KEYS, 32) {
+        fields.remove(key);
+        record.trace_id = id;
+    }
+    if let Some((key, id)) = find_id(&fields, SPAN_ID_KEYS, 16) {
+        fields.remove(key);
+        record.span_id = id;
+    }
+    let parent = fields.get(TRACEPARENT_KEY).and_then(Value::as_str).and_then(traceparent);
+    if let Some((trace_id, span_id, flags)) = parent.filter(|_| record.trace_id.is_empty()) {
+        fields.remove(TRACEPARENT_KEY);
+        record.trace_id = trace_id;
+        record.span_id = span_id;
+        record.flags = flags;
+    }
+
+    record.attributes = key_values(&fields);
+    record.attributes.extend(entry.source().map(|source| KeyValue::new("log.file.path", source)));
+    record
+}
+
+/// The first of `keys` holding a valid id of `digits` hex digits.
+fn find_id(fields: &Map<String, Value>, keys: &[&'static str], digits: usize) -> Option<(&'static str, String)> {
+    keys.iter().find_map(|key| Some((*key, normalize_id(fields.get(*key)?, digits)?)))
+}
+
+/// Lowercase hex of exactly `digits` digits. Shorter hex ids are zero-padded (64-bit
+/// B3/X-Ray style trace ids), decimal ids (Datadog) converted, dashes (UUID-formatted
+/// ids) removed. All-zero ids are invalid.
+fn normalize_id(value: &Value, digits: usize) -> Option<String> {
+    let hex = match value {
+        Value::Number(n) => format!("{:x}", n.as_u64()?),
+        Value::String(s) => {
+            let s: String = s.trim().chars().filter(|c| *c != '-').collect();
+            let is_hex = s.chars().all(|c| c.is_ascii_hexdigit());
+            // 16- and 32-digit strings are hex even when every digit is decimal
+            if s.is_empty() || !is_hex {
+                return None;
+            } else if s.len() != 16 && s.len() != 32 && s.chars().all(|c| c.is_ascii_digit()) {
+                format!("{:x}", s.parse::<u128>().ok()?)
+            } else {
+                s.to_ascii_lowercase()
+            }
+        }
+        _ => return None,
+    };
+    if hex.len() > digits || hex.chars().all(|c| c == '0') {
+        return None;
+    }
+    Some(format!("{:0>width$}", hex, width = digits))
+}
+
+/// Splits a W3C `traceparent` into trace id, span id and flags.
+fn traceparent(header: &str) -> Option<(String, String, u32)> {
+    let mut parts = header.trim().split('-');
+    let (version, trace_id, span_id, flags) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+    let is_id = |s: &str, len: usize| {
+        s.len() == len && s.chars().all(|c| c.is_ascii_hexdigit()) && s.chars().any(|c| c != '0')
+    };
+    if version.len() != 2 || version == "ff" || !is_id(trace_id, 32) || !is_id(span_id, 16) {
+        return None;
+    }
+    let flags = u32::from_str_radix(flags, 16).ok()?;
+    Some((trace_id.to_ascii_lowercase(), span_id.to_ascii_lowercase(), flags))
+}
+
+/// Nanoseconds since the epoch; times before 1970 (or past 2262) clamp to zero.
+fn unix_nanos(time: DateTime<Utc>) -> u64 {
+    time.timestamp_nanos_opt().and_then(|nanos| u64::try_from(nanos).ok()).unwrap_or(0)
+}
+
+fn as_string<T: ToString, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
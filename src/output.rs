@@ -0,0 +1,191 @@
+// src/output.rs
+
+use crate::ecs;
+use crate::parsers::LogEntry;
+use crate::pipeline::Record;
+use crate::query;
+use crate::transform::flatten::{flatten, DEFAULT_MAX_DEPTH};
+use serde_json::Value;
+use std::io::{self, Write};
+
+/// How entries are rendered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One JSON object per line.
+    Ndjson,
+    /// Comma-separated values with a header row, one column per field.
+    Csv(Vec<String>),
+    /// Space-aligned columns with a header row. Widths depend on every row, so
+    /// rows are held until [`Output::finish`].
+    Table(Vec<String>),
+    /// `key=value` pairs, nested fields flattened to dotted keys.
+    Logfmt,
+    /// One Elastic Common Schema document per line (see [`ecs::to_ecs`]).
+    Ecs,
+    /// An Elasticsearch `_bulk` request body: each ECS document preceded by a `create`
+    /// action for the named index (or data stream).
+    EsBulk(String),
+}
+
+/// The entry as a JSON value; unstructured lines become `{"message": <line>}`.
+fn as_json(entry: &LogEntry) -> Value {
+    match entry.value() {
+        Some(value) => value.clone(),
+        None => serde_json::json!({ "message": entry.raw() }),
+    }
+}
+
+/// Renders a field for a column: strings as-is, other values as JSON, missing fields
+/// as an empty cell. The `message` column of an unstructured line is the line itself.
+pub fn column_value(entry: &LogEntry, column: &str) -> String {
+    match entry.value() {
+        Some(value) => match query::resolve_field(value, column) {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        },
+        None if column == "message" => entry.raw().to_string(),
+        None => String::new(),
+    }
+}
+
+pub fn to_ndjson(entry: &LogEntry) -> String {
+    as_json(entry).to_string()
+}
+
+/// Re-serializes an entry as logfmt. Values are quoted when they contain spaces,
+/// quotes or `=`, or are empty; null values are written as a bare `key=`.
+pub fn to_logfmt(entry: &LogEntry) -> String {
+    let flat = flatten(&as_json(entry), DEFAULT_MAX_DEPTH);
+    let Value::Object(map) = flat else { return logfmt_value(&flat) };
+    map.iter()
+        .map(|(key, value)| format!("{}={}", key, logfmt_value(value)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn logfmt_value(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => {
+            let needs_quotes = s.is_empty() || s.chars().any(|c| c.is_whitespace() || c == '"' || c == '=');
+            if needs_quotes {
+                format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+            } else {
+                s.clone()
+            }
+        }
+        other => other.to_string(),
+    }
+}
+
+/// One CSV row (RFC 4180 quoting, no line terminator).
+pub fn to_csv_row(entry: &LogEntry, columns: &[String]) -> String {
+    csv_line(columns.iter().map(|column| column_value(entry, column)))
+}
+
+fn csv_line(cells: impl Iterator<Item = String>) -> String {
+    cells
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Renders rows as an aligned plain-text table with a header row.
+pub fn render_table<'a>(entries: impl IntoIterator<Item = &'a LogEntry>, columns: &[String]) -> String {
+    let rows: Vec<Vec<String>> = entries
+        .into_iter()
+        .map(|entry| columns.iter().map(|column| table_cell(&column_value(entry, column))).collect())
+        .collect();
+    table(columns, &rows)
+}
+
+/// Table cells stay on one line.
+fn table_cell(value: &str) -> String {
+    value.replace('\n', "\\n").replace('\t', " ")
+}
+
+fn table(columns: &[String], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = columns.iter().map(|c| c.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(columns).chain(rows.iter().map(Vec::as_slice)) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Writes entries to `writer` in one [`OutputFormat`], so consumers don't each
+/// serialize [`LogEntry`] themselves.
+pub struct Output<W: Write> {
+    writer: W,
+    format: OutputFormat,
+    header_written: bool,
+    rows: Vec<Vec<String>>,
+}
+
+impl<W: Write> Output<W> {
+    pub fn new(writer: W, format: OutputFormat) -> Self {
+        Output { writer, format, header_written: false, rows: Vec::new() }
+    }
+
+    pub fn write_entry(&mut self, entry: &LogEntry) -> io::Result<()> {
+        match &self.format {
+            OutputFormat::Ndjson => writeln!(self.writer, "{}", to_ndjson(entry)),
+            OutputFormat::Logfmt => writeln!(self.writer, "{}", to_logfmt(entry)),
+            OutputFormat::Ecs => writeln!(self.writer, "{}", ecs::to_ecs(entry)),
+            OutputFormat::EsBulk(index) => {
+                writeln!(self.writer, "{}", serde_json::json!({ "create": { "_index": index } }))?;
+                writeln!(self.writer, "{}", ecs::to_ecs(entry))
+            }
+            OutputFormat::Csv(columns) => {
+                if !self.header_written {
+                    self.header_written = true;
+                    writeln!(self.writer, "{}", csv_line(columns.iter().cloned()))?;
+                }
+                writeln!(self.writer, "{}", to_csv_row(entry, columns))
+            }
+            OutputFormat::Table(columns) => {
+                let row = columns.iter().map(|column| table_cell(&column_value(entry, column))).collect();
+                self.rows.push(row);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn write_record(&mut self, record: &Record) -> io::Result<()> {
+        self.write_entry(&record.entry)
+    }
+
+    /// Writes anything still held (the table, or a CSV header when no row was written),
+    /// flushes, and returns the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        match &self.format {
+            OutputFormat::Table(columns) => self.writer.write_all(table(columns, &self.rows).as_bytes())?,
+            OutputFormat::Csv(columns) if !self.header_written => {
+                writeln!(self.writer, "{}", csv_line(columns.iter().cloned()))?
+            }
+            _ => {}
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
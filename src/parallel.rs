@@ -0,0 +1,77 @@
+// src/parallel.rs
+
+use crate::aggregate::Mergeable;
+use crate::parsers;
+use crate::query::{Query, QueryError, QueryOptions};
+use rayon::prelude::*;
+use serde_json::Value;
+
+/// Lines handed to one worker at a time. Large enough to amortise scheduling,
+/// small enough to balance uneven lines.
+const CHUNK_SIZE: usize = 4096;
+
+/// Output of a parallel batch run.
+#[derive(Debug, Clone)]
+pub struct BatchResult<A> {
+    /// Indices (0-based) of the matching lines, in input order.
+    pub matches: Vec<usize>,
+    /// The combined aggregation over every matching line.
+    pub aggregate: A,
+}
+
+/// Parses and evaluates every line across all cores, returning the indices of matching lines.
+pub fn par_filter<S>(lines: &[S], query: &Query, options: &QueryOptions) -> Result<Vec<usize>, QueryError>
+where
+    S: AsRef<str> + Sync,
+{
+    par_aggregate(lines, query, options, || NoAggregate).map(|result| result.matches)
+}
+
+/// Like [`par_filter`], but also feeds matching entries into an aggregation.
+/// Each chunk builds its own state with `init`; the partial states are merged at the end.
+pub fn par_aggregate<S, A, F>(
+    lines: &[S],
+    query: &Query,
+    options: &QueryOptions,
+    init: F,
+) -> Result<BatchResult<A>, QueryError>
+where
+    S: AsRef<str> + Sync,
+    A: Mergeable,
+    F: Fn() -> A + Sync,
+{
+    let partials: Vec<Result<(Vec<usize>, A), QueryError>> = lines
+        .par_chunks(CHUNK_SIZE)
+        .enumerate()
+        .map(|(chunk_index, chunk)| {
+            let offset = chunk_index * CHUNK_SIZE;
+            let mut matches = Vec::new();
+            let mut aggregate = init();
+            for (i, line) in chunk.iter().enumerate() {
+                let line = line.as_ref();
+                let entry = parsers::parse_log_line(line);
+                if entry.matches_with_options(query, options)? {
+                    matches.push(offset + i);
+                    aggregate.observe(entry.value().unwrap_or(&Value::Null));
+                }
+            }
+            Ok((matches, aggregate))
+        })
+        .collect();
+
+    let mut result = BatchResult { matches: Vec::new(), aggregate: init() };
+    for partial in partials {
+        let (matches, aggregate) = partial?;
+        result.matches.extend(matches);
+        result.aggregate.merge(aggregate);
+    }
+    Ok(result)
+}
+
+/// Placeholder aggregation for plain filtering.
+struct NoAggregate;
+
+impl Mergeable for NoAggregate {
+    fn observe(&mut self, _entry: &Value) {}
+    fn merge(&mut self, _other: Self) {}
+}
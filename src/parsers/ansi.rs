@@ -0,0 +1,108 @@
+// File: src/parsers/ansi.rs
+
+use crate::level::Level;
+use std::borrow::Cow;
+
+const ESC: char = '\u{1b}';
+const BEL: char = '\u{7}';
+
+/// Field added by [`AnsiMode::Hint`].
+pub const COLOR_LEVEL_FIELD: &str = "color_level";
+
+/// What to do with terminal escape sequences before a line is parsed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnsiMode {
+    /// Parse lines exactly as read.
+    #[default]
+    Keep,
+    /// Remove escape sequences, so colored JSON and logfmt are still detected.
+    Strip,
+    /// Remove escape sequences, and record the level implied by the line's first
+    /// foreground color (red = error, yellow = warn, ...) as a `color_level` field.
+    Hint,
+}
+
+/// Removes ANSI escape sequences: CSI (`ESC [ ... m`, cursor movement, erase), OSC
+/// (`ESC ] ... BEL`, e.g. hyperlinks and titles) and two-byte escapes. Borrows when
+/// the line has none.
+pub fn strip_ansi(line: &str) -> Cow<'_, str> {
+    if !line.contains(ESC) {
+        return Cow::Borrowed(line);
+    }
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != ESC {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameter and intermediate bytes, then one final byte in '@'..='~'.
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: up to BEL or the string terminator `ESC \`.
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == BEL {
+                        break;
+                    }
+                    if c == ESC && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// The level suggested by the first foreground color set in the line, the way most
+/// console loggers color their level tags.
+pub fn color_level(line: &str) -> Option<Level> {
+    let mut rest = line;
+    while let Some(start) = rest.find("\u{1b}[") {
+        rest = &rest[start + 2..];
+        let end = rest.find(|c: char| ('@'..='~').contains(&c))?;
+        let (params, tail) = rest.split_at(end);
+        if tail.starts_with('m') {
+            if let Some(level) = sgr_level(params) {
+                return Some(level);
+            }
+        }
+        rest = &tail[1..];
+    }
+    None
+}
+
+fn sgr_level(params: &str) -> Option<Level> {
+    let mut codes = params.split(';').map(|p| p.parse::<u16>().unwrap_or(0));
+    while let Some(code) = codes.next() {
+        let level = match code {
+            31 | 91 => Level::Error,
+            33 | 93 => Level::Warn,
+            32 | 92 => Level::Info,
+            34 | 36 | 94 | 96 => Level::Debug,
+            90 => Level::Trace,
+            // Extended colors (`38;5;n`, `38;2;r;g;b`) carry their own arguments.
+            38 | 48 => {
+                let args = match codes.next() {
+                    Some(5) => 1,
+                    Some(2) => 3,
+                    _ => 0,
+                };
+                codes.by_ref().take(args).for_each(|_| {});
+                continue;
+            }
+            _ => continue,
+        };
+        return Some(level);
+    }
+    None
+}
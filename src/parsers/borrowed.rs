@@ -0,0 +1,141 @@
+// File: src/parsers/borrowed.rs
+
+use super::json::Key;
+use super::lazy::FieldSet;
+use super::{normalize_level, parse_log_line, sniff, Format, LogEntry, ParseOptions};
+use crate::parsers::detect::Trace;
+use crate::query::{self, Query, QueryError, QueryOptions};
+use serde::de::{Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::fmt;
+
+/// A parsed line that borrows from the input instead of owning its fields.
+///
+/// A JSON object line is split into its top-level keys and their still-encoded values
+/// ([`RawValue`] slices of the line); a value is only decoded when it is read, so
+/// filtering with [`LogEntryRef::matches`] decodes just the fields the query uses.
+/// Keys are borrowed too unless they contain escapes. Other formats are parsed into an
+/// owned [`LogEntry`] as usual.
+///
+/// Reading and matching behave exactly like the [`LogEntry`] that [`parse_log_line`]
+/// returns for the same line; [`LogEntryRef::to_entry`] produces it.
+#[derive(Debug, Clone)]
+pub struct LogEntryRef<'a> {
+    raw: &'a str,
+    fields: Fields<'a>,
+}
+
+#[derive(Debug, Clone)]
+enum Fields<'a> {
+    Json(Vec<(Cow<'a, str>, &'a RawValue)>),
+    Owned(LogEntry),
+}
+
+/// Parses a line like [`parse_log_line`], borrowing from `line` where it can.
+pub fn parse_log_line_ref(line: &str) -> LogEntryRef<'_> {
+    let trimmed = line.trim();
+    if trimmed.starts_with('{') && trimmed.ends_with('}') {
+        if let Ok(BorrowedObject(fields)) = serde_json::from_str(trimmed) {
+            return LogEntryRef { raw: line, fields: Fields::Json(fields) };
+        }
+    }
+    LogEntryRef { raw: line, fields: Fields::Owned(sniff(line, ParseOptions::default_ref(), &mut Trace::off())) }
+}
+
+impl<'a> LogEntryRef<'a> {
+    /// The line as it was read.
+    pub fn raw(&self) -> &'a str {
+        self.raw
+    }
+
+    pub fn format(&self) -> Format {
+        match &self.fields {
+            Fields::Json(_) => Format::Json,
+            Fields::Owned(entry) => entry.format(),
+        }
+    }
+
+    pub fn is_structured(&self) -> bool {
+        match &self.fields {
+            Fields::Json(_) => true,
+            Fields::Owned(entry) => entry.is_structured(),
+        }
+    }
+
+    /// A field by name, dotted path or JSON pointer, decoded on demand. The level fields
+    /// read as normalized, as on [`LogEntry`].
+    pub fn get(&self, path: &str) -> Option<Value> {
+        match &self.fields {
+            Fields::Json(_) => query::resolve_field(&self.project(Some(&[path])), path).cloned(),
+            Fields::Owned(entry) => entry.get(path).cloned(),
+        }
+    }
+
+    /// Tests the entry against a compiled query, decoding only the fields it reads.
+    pub fn matches(&self, query: &Query) -> Result<bool, QueryError> {
+        match &self.fields {
+            Fields::Json(_) => query.matches(&self.project(query.fields().as_deref()), self.raw),
+            Fields::Owned(entry) => entry.matches(query),
+        }
+    }
+
+    /// The owned entry, fully parsed.
+    pub fn to_entry(&self) -> LogEntry {
+        match &self.fields {
+            Fields::Json(_) => parse_log_line(self.raw),
+            Fields::Owned(entry) => entry.clone(),
+        }
+    }
+
+    /// An object holding the top-level fields `names` can resolve to (every field for
+    /// `None`), plus those level normalization and the timestamp fallbacks read, with
+    /// the level normalized as the parser does.
+    fn project(&self, names: Option<&[&str]>) -> Value {
+        let Fields::Json(fields) = &self.fields else { return Value::Null };
+        let wanted = FieldSet::new(names, QueryOptions::default_ref());
+        let mut value = decode(fields.iter().filter(|(key, _)| wanted.contains(key)));
+        normalize_level(&mut value);
+        value
+    }
+}
+
+/// Decodes fields into an object; repeated keys keep the last value, as `serde_json` does.
+fn decode<'b, 'a: 'b>(fields: impl Iterator<Item = &'b (Cow<'a, str>, &'a RawValue)>) -> Value {
+    let mut map = Map::new();
+    for (key, raw) in fields {
+        if let Ok(value) = serde_json::from_str(raw.get()) {
+            map.insert(key.to_string(), value);
+        }
+    }
+    Value::Object(map)
+}
+
+/// A JSON object's top-level entries, borrowed from the input.
+struct BorrowedObject<'a>(Vec<(Cow<'a, str>, &'a RawValue)>);
+
+impl<'de> Deserialize<'de> for BorrowedObject<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(ObjectVisitor)
+    }
+}
+
+struct ObjectVisitor;
+
+impl<'de> Visitor<'de> for ObjectVisitor {
+    type Value = BorrowedObject<'de>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut fields = Vec::with_capacity(map.size_hint().unwrap_or(8));
+        while let Some(Key(key)) = map.next_key()? {
+            fields.push((key, map.next_value()?));
+        }
+        Ok(BorrowedObject(fields))
+    }
+}
@@ -0,0 +1,194 @@
+// File: src/parsers/detect.rs
+
+use super::{parse_detected_as, parse_log_line, sniff, Format, LogEntry, ParseOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Lines sampled before the detector commits to a format.
+pub const DEFAULT_SAMPLE_SIZE: usize = 100;
+
+/// One parser's verdict on a line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attempt {
+    pub format: Format,
+    pub accepted: bool,
+    /// Why the parser rejected the line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Which parser handled a line and how sure the detection was, for debugging why
+/// logs end up unstructured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectionReport {
+    pub format: Format,
+    /// `0.0..=1.0`. Strict JSON is 1.0; logfmt is the share of keys that carry a value.
+    /// Plain text is 1.0 when no parser came close, and lower when one was tried and failed.
+    pub confidence: f64,
+    /// Every parser tried, in order.
+    pub attempts: Vec<Attempt>,
+    /// For plain text, why each parser rejected the line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Records parser verdicts while sniffing; free when turned off.
+pub(crate) struct Trace {
+    attempts: Option<Vec<Attempt>>,
+    confidence: f64,
+    /// A parser's pre-check passed but the parse itself failed.
+    near_miss: bool,
+}
+
+impl Trace {
+    pub(crate) fn off() -> Self {
+        Trace { attempts: None, confidence: 0.0, near_miss: false }
+    }
+
+    fn on() -> Self {
+        Trace { attempts: Some(Vec::new()), confidence: 0.0, near_miss: false }
+    }
+
+    pub(crate) fn accept(&mut self, format: Format, confidence: f64) {
+        if let Some(attempts) = &mut self.attempts {
+            attempts.push(Attempt { format, accepted: true, reason: None });
+            self.confidence = confidence;
+        }
+    }
+
+    /// The line didn't pass the parser's pre-check.
+    pub(crate) fn reject(&mut self, format: Format, reason: impl FnOnce() -> String) {
+        if let Some(attempts) = &mut self.attempts {
+            attempts.push(Attempt { format, accepted: false, reason: Some(reason()) });
+        }
+    }
+
+    /// The line looked like `format` but didn't parse.
+    pub(crate) fn fail(&mut self, format: Format, reason: impl FnOnce() -> String) {
+        self.near_miss = true;
+        self.reject(format, reason);
+    }
+}
+
+/// Parses a line like [`parse_log_line`] and reports how the format was chosen.
+pub fn parse_log_line_with_report(line: &str) -> (LogEntry, DetectionReport) {
+    let mut trace = Trace::on();
+    let entry = sniff(line, ParseOptions::default_ref(), &mut trace);
+    let attempts = trace.attempts.unwrap_or_default();
+
+    let (confidence, reason) = if entry.is_structured() {
+        (trace.confidence, None)
+    } else {
+        let reason = attempts
+            .iter()
+            .filter_map(|attempt| Some(format!("{}: {}", attempt.format.as_str(), attempt.reason.as_ref()?)))
+            .collect::<Vec<_>>()
+            .join("; ");
+        (if trace.near_miss { 0.5 } else { 1.0 }, Some(reason))
+    };
+    let report = DetectionReport { format: entry.format(), confidence, attempts, reason };
+    (entry, report)
+}
+
+/// The structured format most of `lines` parse as, if more than half of them do.
+pub fn detect_format<S: AsRef<str>>(lines: &[S]) -> Option<Format> {
+    let mut counts: HashMap<Format, usize> = HashMap::new();
+    for line in lines {
+        *counts.entry(parse_log_line(line.as_ref()).format()).or_default() += 1;
+    }
+    dominant(&counts)
+}
+
+fn dominant(counts: &HashMap<Format, usize>) -> Option<Format> {
+    let total: usize = counts.values().sum();
+    counts
+        .iter()
+        .filter(|(&format, &count)| format != Format::Plain && count * 2 > total)
+        .map(|(&format, _)| format)
+        .next()
+}
+
+/// Sniffs the format of one source from its first lines, then parses every later line
+/// with that format's parser alone.
+///
+/// Per-line sniffing is slower and can flip mid-file (a JSON service printing one
+/// `key=value` banner). The first `sample_size` lines are parsed with the usual
+/// heuristics and tallied; if more than half share a structured format, the detector
+/// locks in to it. Sources without a clear majority keep per-line sniffing. Use one
+/// detector per source.
+///
+/// Lines the locked parser rejects fall back to the heuristics, unless the fallback is
+/// turned off, in which case they become unstructured.
+#[derive(Debug, Clone)]
+pub struct FormatDetector {
+    sample_size: usize,
+    counts: HashMap<Format, usize>,
+    sampled: usize,
+    locked: Option<Format>,
+    fallback: bool,
+}
+
+impl Default for FormatDetector {
+    fn default() -> Self {
+        FormatDetector::new()
+    }
+}
+
+impl FormatDetector {
+    pub fn new() -> Self {
+        FormatDetector::with_sample_size(DEFAULT_SAMPLE_SIZE)
+    }
+
+    pub fn with_sample_size(sample_size: usize) -> Self {
+        FormatDetector {
+            sample_size: sample_size.max(1),
+            counts: HashMap::new(),
+            sampled: 0,
+            locked: None,
+            fallback: true,
+        }
+    }
+
+    /// Whether lines the locked parser rejects are re-parsed with the heuristics
+    /// (the default) or kept as unstructured.
+    pub fn with_fallback(mut self, fallback: bool) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Skips sampling and parses everything as `format`.
+    pub fn lock(&mut self, format: Format) {
+        self.locked = Some(format);
+        self.sampled = self.sample_size;
+    }
+
+    /// The format locked in, once sampling found one.
+    pub fn locked(&self) -> Option<Format> {
+        self.locked
+    }
+
+    /// Whether the sample is complete (locked in or not).
+    pub fn is_decided(&self) -> bool {
+        self.sampled >= self.sample_size
+    }
+
+    pub fn parse(&mut self, line: &str) -> LogEntry {
+        if let Some(format) = self.locked {
+            return match parse_detected_as(line, format) {
+                Some(entry) => entry,
+                None if self.fallback => parse_log_line(line),
+                None => LogEntry::unstructured(line),
+            };
+        }
+
+        let entry = parse_log_line(line);
+        if !self.is_decided() {
+            *self.counts.entry(entry.format()).or_default() += 1;
+            self.sampled += 1;
+            if self.is_decided() {
+                self.locked = dominant(&self.counts);
+            }
+        }
+        entry
+    }
+}
@@ -1,8 +1,362 @@
 // src/parsers/json.rs
 
-use serde_json::{Result, Value};
+use super::{DuplicateKeys, KeyedMap};
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use serde_json::{Map, Number, Result, Value};
+use std::borrow::Cow;
+use std::fmt;
+
+/// Nesting accepted by the lenient parser before it gives up.
+const MAX_DEPTH: usize = 128;
 
 /// Attempts to parse a single line as a JSON object.
 pub fn parse_json_line(line: &str) -> Result<Value> {
     serde_json::from_str(line)
 }
+
+/// Like [`parse_json_line`], with `policy` deciding what happens to keys an object
+/// repeats, at any depth. `serde_json` alone keeps the last one.
+pub fn parse_json_line_with(line: &str, policy: DuplicateKeys) -> Result<Value> {
+    if policy == DuplicateKeys::Last {
+        return parse_json_line(line);
+    }
+    let mut deserializer = serde_json::Deserializer::from_str(line);
+    let value = WithPolicy(policy).deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Decodes only the top-level entries of a JSON object whose key `wanted` accepts; the
+/// other values are scanned past without being built. The whole line is still checked,
+/// so an object this accepts is exactly one [`parse_json_line`] accepts. Repeated keys
+/// keep the last value.
+pub fn extract_fields(line: &str, wanted: impl Fn(&str) -> bool) -> Result<Value> {
+    let mut deserializer = serde_json::Deserializer::from_str(line);
+    let value = deserializer.deserialize_map(Extract(wanted))?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Builds an object from the entries whose key passes the filter.
+struct Extract<F>(F);
+
+impl<'de, F: Fn(&str) -> bool> Visitor<'de> for Extract<F> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> std::result::Result<Value, A::Error> {
+        let mut map = Map::new();
+        while let Some(Key(key)) = access.next_key()? {
+            if (self.0)(&key) {
+                let value = access.next_value()?;
+                map.insert(key.into_owned(), value);
+            } else {
+                access.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+/// An object key, borrowed from the input unless it had to be unescaped.
+pub(super) struct Key<'a>(pub(super) Cow<'a, str>);
+
+impl<'de> Deserialize<'de> for Key<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_str(KeyVisitor)
+    }
+}
+
+struct KeyVisitor;
+
+impl<'de> Visitor<'de> for KeyVisitor {
+    type Value = Key<'de>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an object key")
+    }
+
+    fn visit_borrowed_str<E>(self, s: &'de str) -> std::result::Result<Self::Value, E> {
+        Ok(Key(Cow::Borrowed(s)))
+    }
+
+    fn visit_str<E>(self, s: &str) -> std::result::Result<Self::Value, E> {
+        Ok(Key(Cow::Owned(s.to_string())))
+    }
+
+    fn visit_string<E>(self, s: String) -> std::result::Result<Self::Value, E> {
+        Ok(Key(Cow::Owned(s)))
+    }
+}
+
+/// Deserializes a [`Value`], building objects through a [`KeyedMap`].
+#[derive(Clone, Copy)]
+struct WithPolicy(DuplicateKeys);
+
+impl<'de> DeserializeSeed<'de> for WithPolicy {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for WithPolicy {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> std::result::Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E>(self, n: i64) -> std::result::Result<Value, E> {
+        Ok(Value::from(n))
+    }
+
+    fn visit_u64<E>(self, n: u64) -> std::result::Result<Value, E> {
+        Ok(Value::from(n))
+    }
+
+    fn visit_f64<E>(self, n: f64) -> std::result::Result<Value, E> {
+        Ok(Number::from_f64(n).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E>(self, s: &str) -> std::result::Result<Value, E> {
+        Ok(Value::from(s))
+    }
+
+    fn visit_string<E>(self, s: String) -> std::result::Result<Value, E> {
+        Ok(Value::String(s))
+    }
+
+    fn visit_unit<E>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> std::result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> std::result::Result<Value, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element_seed(self)? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> std::result::Result<Value, A::Error> {
+        let mut map = KeyedMap::new(self.0);
+        while let Some(key) = access.next_key::<String>()? {
+            let value = access.next_value_seed(self)?;
+            map.insert(key, value);
+        }
+        Ok(map.into_value())
+    }
+}
+
+/// Byte range of the first balanced `{...}` region in `line`, skipping braces inside
+/// double-quoted strings.
+pub fn find_object(line: &str) -> Option<(usize, usize)> {
+    let start = line.find('{')?;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, b) in line.bytes().enumerate().skip(start) {
+        match b {
+            _ if escaped => escaped = false,
+            b'\\' if in_string => escaped = true,
+            b'"' => in_string = !in_string,
+            b'{' if !in_string => depth += 1,
+            b'}' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((start, i + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Recovers an object from almost-JSON that `serde_json` rejects.
+///
+/// Tolerates trailing commas, single-quoted strings, unquoted keys, `NaN` / `Infinity`
+/// (kept as the strings `"NaN"`, `"Infinity"` and `"-Infinity"`, which JSON can't hold
+/// as numbers), text before the first `{` (such as a log prefix) and anything after
+/// the matching `}`. Returns `None` when no non-empty object can be recovered.
+pub fn parse_json_lenient(line: &str) -> Option<Value> {
+    let start = line.find('{')?;
+    let mut parser = Lenient { s: &line[start..], pos: 0, depth: 0 };
+    // An empty `{}` in prose ("got {} items") isn't worth recovering.
+    parser.value().filter(|value| value.as_object().is_some_and(|map| !map.is_empty()))
+}
+
+struct Lenient<'a> {
+    s: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl Lenient<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.s.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(|b| b.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        self.skip_ws();
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        if self.s[self.pos..].starts_with(word) {
+            self.pos += word.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        self.skip_ws();
+        match self.peek()? {
+            b'{' => self.nested(Self::object),
+            b'[' => self.nested(Self::array),
+            b'"' | b'\'' => self.string().map(Value::String),
+            _ if self.eat_word("true") => Some(Value::Bool(true)),
+            _ if self.eat_word("false") => Some(Value::Bool(false)),
+            _ if self.eat_word("null") => Some(Value::Null),
+            _ if self.eat_word("NaN") => Some(Value::from("NaN")),
+            _ if self.eat_word("Infinity") || self.eat_word("+Infinity") => Some(Value::from("Infinity")),
+            _ if self.eat_word("-Infinity") => Some(Value::from("-Infinity")),
+            _ => self.number(),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Option<Value>) -> Option<Value> {
+        if self.depth >= MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Option<Value> {
+        self.pos += 1;
+        let mut map = Map::new();
+        loop {
+            if self.eat(b'}') {
+                return Some(Value::Object(map));
+            }
+            let key = self.key()?;
+            if !self.eat(b':') {
+                return None;
+            }
+            let value = self.value()?;
+            map.insert(key, value);
+            if !self.eat(b',') {
+                return self.eat(b'}').then_some(Value::Object(map));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Option<Value> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            if self.eat(b']') {
+                return Some(Value::Array(items));
+            }
+            items.push(self.value()?);
+            if !self.eat(b',') {
+                return self.eat(b']').then_some(Value::Array(items));
+            }
+        }
+    }
+
+    /// A quoted key, or a bare identifier (`level`, `http.status`, `$id`, `x-request-id`).
+    fn key(&mut self) -> Option<String> {
+        self.skip_ws();
+        if matches!(self.peek()?, b'"' | b'\'') {
+            return self.string();
+        }
+        let start = self.pos;
+        let is_key_byte = |b: u8| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'$' | b'-' | b'.' | b'@');
+        while self.peek().is_some_and(is_key_byte) {
+            self.pos += 1;
+        }
+        (self.pos > start).then(|| self.s[start..self.pos].to_string())
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let quote = self.peek()?;
+        self.pos += 1;
+        let mut out = String::new();
+        let mut chars = self.s[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    let (_, escaped) = chars.next()?;
+                    match escaped {
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        'r' => out.push('\r'),
+                        'b' => out.push('\u{8}'),
+                        'f' => out.push('\u{c}'),
+                        'u' => {
+                            let hex: String = (0..4).filter_map(|_| chars.next().map(|(_, h)| h)).collect();
+                            out.push(u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)?);
+                        }
+                        other => out.push(other),
+                    }
+                }
+                c if c as u32 == quote as u32 => {
+                    self.pos += i + 1;
+                    return Some(out);
+                }
+                c => out.push(c),
+            }
+        }
+        None
+    }
+
+    fn number(&mut self) -> Option<Value> {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit() || matches!(b, b'-' | b'+' | b'.' | b'e' | b'E')) {
+            self.pos += 1;
+        }
+        let text = self.s[start..self.pos].trim_start_matches('+');
+        if let Ok(n) = text.parse::<i64>() {
+            return Some(Value::from(n));
+        }
+        if let Ok(n) = text.parse::<u64>() {
+            return Some(Value::from(n));
+        }
+        text.parse::<f64>().ok().and_then(Number::from_f64).map(Value::Number)
+    }
+}
@@ -0,0 +1,94 @@
+// File: src/parsers/lazy.rs
+
+use super::{json, normalize_level, LEVEL_KEYS};
+use crate::query::{Query, QueryError, QueryOptions};
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// The top-level keys of a JSON object that a query can read: the fields it names and
+/// their aliases, plus the level fields normalization reads (and `level_num`, which
+/// `level` queries fall back to) and the timestamp fields.
+/// Keys compare ASCII case-insensitively, as field lookups may.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldSet {
+    /// `None` when every key may be read.
+    keys: Option<Vec<String>>,
+}
+
+impl FieldSet {
+    /// The keys `query` reads under `options`; every key when it has a wildcard.
+    pub fn for_query(query: &Query, options: &QueryOptions) -> Self {
+        FieldSet::new(query.fields().as_deref(), options)
+    }
+
+    /// The keys the field references `names` read; every key for `None`.
+    pub fn new(names: Option<&[&str]>, options: &QueryOptions) -> Self {
+        let keys = names.map(|names| {
+            let mut keys: Vec<String> = Vec::new();
+            let names = names
+                .iter()
+                .copied()
+                .chain(LEVEL_KEYS.iter().copied())
+                .chain(["level_num"])
+                .chain(options.timestamp_fields.iter().map(String::as_str));
+            for name in names {
+                let aliases = options.field_aliases.get(name).into_iter().flatten().map(String::as_str);
+                for key in std::iter::once(name).chain(aliases).flat_map(top_level_keys) {
+                    if !keys.iter().any(|known| known.eq_ignore_ascii_case(&key)) {
+                        keys.push(key.into_owned());
+                    }
+                }
+            }
+            keys
+        });
+        FieldSet { keys }
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        match &self.keys {
+            Some(keys) => keys.iter().any(|known| known.eq_ignore_ascii_case(key)),
+            None => true,
+        }
+    }
+}
+
+/// The top-level keys a field reference may read: the name itself, and the first
+/// segment of a dotted path or JSON pointer.
+fn top_level_keys(name: &str) -> impl Iterator<Item = Cow<'_, str>> {
+    let first = match name.strip_prefix('/') {
+        Some(pointer) => pointer.split('/').next().map(|segment| match segment.contains('~') {
+            true => Cow::Owned(segment.replace("~1", "/").replace("~0", "~")),
+            false => Cow::Borrowed(segment),
+        }),
+        None => name.split_once('.').map(|(first, _)| Cow::Borrowed(first)),
+    };
+    std::iter::once(Cow::Borrowed(name)).chain(first)
+}
+
+/// Decodes just the fields in `fields` from a JSON object line, skipping the other values
+/// without building them, and normalizes the level as the parser does. `None` when the
+/// line isn't a JSON object, so [`parse_log_line`](super::parse_log_line) would try other
+/// formats on it.
+pub fn extract(line: &str, fields: &FieldSet) -> Option<Value> {
+    let trimmed = line.trim();
+    if !(trimmed.starts_with('{') && trimmed.ends_with('}')) {
+        return None;
+    }
+    let mut value = json::extract_fields(trimmed, |key| fields.contains(key)).ok()?;
+    normalize_level(&mut value);
+    Some(value)
+}
+
+/// Tests a JSON object line against `query` without parsing the fields it doesn't read
+/// (`fields` should come from [`FieldSet::for_query`] with the same options). Gives the
+/// same answer as matching the entry [`parse_log_line`](super::parse_log_line) returns;
+/// `None` when the line isn't a JSON object.
+pub fn matches(
+    line: &str,
+    query: &Query,
+    fields: &FieldSet,
+    options: &QueryOptions,
+) -> Option<Result<bool, QueryError>> {
+    let value = extract(line, fields)?;
+    Some(query.matches_with_options(&value, line, options))
+}
@@ -1,9 +1,20 @@
 // src/parsers/logfmt.rs
 
-use serde_json::{Map, Value};
+use super::{DuplicateKeys, KeyedMap};
+use chrono::DateTime;
+use serde_json::{Number, Value};
 
 /// Attempts to parse a single line as logfmt using the correct library API.
+///
+/// Bare values are typed: integers and floats become numbers, `true` / `false` become
+/// booleans and ISO 8601 timestamps are normalized to RFC 3339. Quoted values
+/// (`id="123"`) always stay strings.
 pub fn parse_logfmt_line(line: &str) -> Result<Value, String> {
+    parse_logfmt_line_with(line, DuplicateKeys::Last)
+}
+
+/// Like [`parse_logfmt_line`], with `policy` deciding what happens to repeated keys.
+pub fn parse_logfmt_line_with(line: &str, policy: DuplicateKeys) -> Result<Value, String> {
     // 1. The `parse` function directly returns a Vec<Pair>.
     let pairs = logfmt::parse(line);
 
@@ -12,17 +23,80 @@ pub fn parse_logfmt_line(line: &str) -> Result<Value, String> {
         return Err("Not a valid logfmt line.".to_string());
     }
 
-    let mut map = Map::new();
+    let mut map = KeyedMap::new(policy);
+    let mut quoting = quoted_values(line).into_iter();
 
     // 3. We can now iterate directly over the successful pairs.
     for pair in pairs {
-        // The `pair.val` is an Option<String>, which we convert to a serde_json::Value
+        // The `pair.val` is an Option<String>; the library drops the quotes, so whether
+        // the value was quoted comes from scanning the line alongside it.
         let value = match pair.val {
-            Some(v) => Value::String(v),
+            Some(v) => {
+                let quoted = quoting.next().unwrap_or(false);
+                if quoted { Value::String(v) } else { infer_value(v) }
+            }
             None => Value::Null,
         };
         map.insert(pair.key, value);
     }
 
-    Ok(Value::Object(map))
+    Ok(map.into_value())
+}
+
+/// For every `key=value` pair in `line`, in order, whether its value is quoted.
+fn quoted_values(line: &str) -> Vec<bool> {
+    let mut quoted = Vec::new();
+    let mut in_quotes = false;
+    let mut escaped = false;
+    let bytes = line.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            _ if escaped => escaped = false,
+            b'\\' if in_quotes => escaped = true,
+            b'"' => in_quotes = !in_quotes,
+            b'=' if !in_quotes => quoted.push(bytes.get(i + 1) == Some(&b'"')),
+            _ => {}
+        }
+    }
+    quoted
+}
+
+/// Types a bare logfmt value.
+pub(crate) fn infer_value(raw: String) -> Value {
+    match raw.as_str() {
+        "true" => return Value::Bool(true),
+        "false" => return Value::Bool(false),
+        _ => {}
+    }
+    if looks_numeric(&raw) {
+        if let Ok(n) = raw.parse::<i64>() {
+            return Value::from(n);
+        }
+        if let Ok(n) = raw.parse::<u64>() {
+            return Value::from(n);
+        }
+        if let Some(n) = raw.parse::<f64>().ok().and_then(Number::from_f64) {
+            return Value::Number(n);
+        }
+    }
+    if raw.len() >= 20 && raw.as_bytes()[4] == b'-' {
+        if let Ok(ts) = DateTime::parse_from_rfc3339(&raw) {
+            return Value::from(ts.to_rfc3339());
+        }
+    }
+    Value::String(raw)
+}
+
+/// Decimal numbers only: no `inf` / `NaN`, no leading `+`, and no zero-padded integers
+/// (`007` is more likely an identifier than a number).
+fn looks_numeric(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    let mut bytes = digits.bytes();
+    match (bytes.next(), bytes.next()) {
+        (Some(b'0'), Some(b'0'..=b'9')) => false,
+        (Some(b'0'..=b'9'), _) => {
+            digits.bytes().all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'e' | b'E' | b'-' | b'+'))
+        }
+        _ => false,
+    }
 }
@@ -1,54 +1,589 @@
 // File: src/parsers/mod.rs
 
+pub mod ansi;
+pub mod borrowed;
+pub mod detect;
 pub mod json;
+pub mod lazy;
 pub mod logfmt;
 pub mod nginx; // ADDED
+pub mod plain;
+// simd-json borrowed-value parsing, for callers that can work on the borrowed value
+#[cfg(feature = "simd")]
+pub mod simd;
+pub mod syslog;
+pub mod url;
 
-use serde_json::Value;
+use crate::level::Level;
+use crate::parsers::detect::Trace;
+use crate::query::{self, Query, QueryError, QueryOptions};
+use crate::time::{self as time_parser, TimeOptions};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::sync::OnceLock;
 
-/// A universal representation of a single log line.
-#[derive(Debug)]
-pub enum LogEntry {
-    Structured(Value), // For JSON, Nginx, or other structured formats
-    Unstructured(String), // For plain text
+/// Fields holding the human-readable message, in order of preference.
+pub(crate) const MESSAGE_KEYS: &[&str] = &["message", "msg"];
+/// Fields holding the severity, in order of preference.
+pub(crate) const LEVEL_KEYS: &[&str] = &["level", "severity", "lvl", "loglevel", "log.level", "severity_text", "sev"];
+
+/// The format a line was parsed as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Json,
+    Logfmt,
+    /// Nginx / Common Log Format access lines, and nginx error.log lines.
+    Nginx,
+    /// RFC 5424 or BSD (RFC 3164) syslog.
+    Syslog,
+    /// Unstructured text.
+    Plain,
+}
+
+impl Format {
+    /// Parses a format name (case-insensitive), e.g. from a `--format` flag.
+    pub fn parse(s: &str) -> Option<Format> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "json" | "ndjson" | "jsonl" => Some(Format::Json),
+            "logfmt" => Some(Format::Logfmt),
+            "nginx" | "clf" | "combined" => Some(Format::Nginx),
+            "syslog" => Some(Format::Syslog),
+            "plain" | "text" => Some(Format::Plain),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Logfmt => "logfmt",
+            Format::Nginx => "nginx",
+            Format::Syslog => "syslog",
+            Format::Plain => "plain",
+        }
+    }
+}
+
+/// What to do when a JSON object or logfmt line repeats a key
+/// (`error="timeout" error="retry failed"`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateKeys {
+    /// The last occurrence replaces earlier ones.
+    #[default]
+    Last,
+    /// The first occurrence is kept and later ones are dropped.
+    First,
+    /// Every occurrence is kept, in order, in an array.
+    Collect,
+}
+
+/// Settings that tune how [`parse_log_line_with`] recognizes lines.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParseOptions {
+    /// Structured formats to try, in the usual order (JSON, nginx, syslog, logfmt).
+    /// Leave one out to stop it claiming lines; defaults to all of them.
+    pub formats: Vec<Format>,
+    /// Minimum share (`0.0..=1.0`) of logfmt keys that must carry a value. `None` keeps
+    /// the built-in heuristic, which allows fewer than half (rounded down) bare keys.
+    pub logfmt_threshold: Option<f64>,
+    /// Extra fields holding the entry's time (`created`, `meta.logged_at`, `/ctx/0/ts`).
+    /// The first one that parses is copied into `timestamp` (RFC 3339) when the entry
+    /// has none, so time queries and [`LogEntry::timestamp`] see it.
+    pub timestamp_keys: Vec<String>,
+    /// What happens to keys a JSON object or logfmt line repeats.
+    pub duplicates: DuplicateKeys,
+    /// Lines longer than this (in bytes) are kept unstructured without being parsed.
+    pub max_line_length: Option<usize>,
+    /// How timestamps without a zone (nginx error.log, BSD syslog, log prefixes) and
+    /// numeric epochs are read.
+    pub time: TimeOptions,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            formats: vec![Format::Json, Format::Nginx, Format::Syslog, Format::Logfmt],
+            logfmt_threshold: None,
+            timestamp_keys: Vec::new(),
+            duplicates: DuplicateKeys::Last,
+            max_line_length: None,
+            time: TimeOptions::default(),
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Shared default options, so plain `parse_log_line` calls don't allocate per line.
+    pub(crate) fn default_ref() -> &'static ParseOptions {
+        static DEFAULT: OnceLock<ParseOptions> = OnceLock::new();
+        DEFAULT.get_or_init(ParseOptions::default)
+    }
+
+    fn tries(&self, format: Format) -> bool {
+        self.formats.contains(&format)
+    }
+}
+
+/// Builds an object under a [`DuplicateKeys`] policy.
+pub(crate) struct KeyedMap {
+    map: Map<String, Value>,
+    policy: DuplicateKeys,
+    /// Keys whose value is already a collected array.
+    collected: HashSet<String>,
+}
+
+impl KeyedMap {
+    pub(crate) fn new(policy: DuplicateKeys) -> Self {
+        KeyedMap { map: Map::new(), policy, collected: HashSet::new() }
+    }
+
+    pub(crate) fn insert(&mut self, key: String, value: Value) {
+        let Some(existing) = self.map.get_mut(&key) else {
+            self.map.insert(key, value);
+            return;
+        };
+        match self.policy {
+            DuplicateKeys::Last => *existing = value,
+            DuplicateKeys::First => {}
+            DuplicateKeys::Collect => {
+                if self.collected.contains(&key) {
+                    if let Value::Array(items) = existing {
+                        items.push(value);
+                    }
+                } else {
+                    *existing = Value::Array(vec![existing.take(), value]);
+                    self.collected.insert(key);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn into_value(self) -> Value {
+        Value::Object(self.map)
+    }
+}
+
+/// A universal representation of a single log line: the parsed value (if any
+/// parser recognized the line), the raw line, and where it came from.
+///
+/// Entries serialize as `{"value": ..., "raw": ..., "format": "json", ...}`, so parsed
+/// entries can be cached, sent between processes and compared.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    value: Option<Value>,
+    raw: String,
+    format: Format,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    line_number: Option<usize>,
+}
+
+impl LogEntry {
+    /// An entry some parser turned into `value`.
+    pub fn structured(value: Value, raw: impl Into<String>, format: Format) -> Self {
+        LogEntry { value: Some(value), raw: raw.into(), format, source: None, line_number: None }
+    }
+
+    /// A line no parser recognized.
+    pub fn unstructured(raw: impl Into<String>) -> Self {
+        LogEntry { value: None, raw: raw.into(), format: Format::Plain, source: None, line_number: None }
+    }
+
+    /// Names the file, stream or host the line was read from.
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// 1-based position of the line in its source.
+    pub fn with_line_number(mut self, line_number: usize) -> Self {
+        self.line_number = Some(line_number);
+        self
+    }
+
+    pub fn is_structured(&self) -> bool {
+        self.value.is_some()
+    }
+
+    /// The parsed value, or `None` for unstructured lines.
+    pub fn value(&self) -> Option<&Value> {
+        self.value.as_ref()
+    }
+
+    pub fn value_mut(&mut self) -> Option<&mut Value> {
+        self.value.as_mut()
+    }
+
+    /// Replaces the parsed value, e.g. after a transform built structure for a plain line.
+    pub fn set_value(&mut self, value: Value) {
+        self.value = Some(value);
+    }
+
+    pub fn into_value(self) -> Option<Value> {
+        self.value
+    }
+
+    /// The line as it was read.
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn set_raw(&mut self, raw: impl Into<String>) {
+        self.raw = raw.into();
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    pub fn line_number(&self) -> Option<usize> {
+        self.line_number
+    }
+
+    /// A field by name, dotted path (`http.status`) or JSON pointer (`/http/status`).
+    pub fn get(&self, path: &str) -> Option<&Value> {
+        query::resolve_field(self.value.as_ref()?, path)
+    }
+
+    /// The entry's timestamp from the usual fields ([`time::COMMON_KEYS`](time_parser::COMMON_KEYS)),
+    /// or for plain text a timestamp leading the line. Times without a zone are read as UTC.
+    pub fn timestamp(&self) -> Option<DateTime<Utc>> {
+        match &self.value {
+            Some(value) => time_parser::extract_and_parse_timestamp(value),
+            None => plain::leading_timestamp(self.raw.trim_start()).map(|(ts, _)| ts),
+        }
+    }
+
+    /// Like [`timestamp`](Self::timestamp), with the time read per `options`.
+    pub fn timestamp_with(&self, options: &TimeOptions) -> Option<DateTime<Utc>> {
+        match &self.value {
+            Some(value) => time_parser::extract_timestamp_with(value, options),
+            None => plain::leading_timestamp_with(self.raw.trim_start(), options).map(|(ts, _)| ts),
+        }
+    }
+
+    /// The normalized level from the usual fields, or for plain text a level token near
+    /// the start of the line.
+    pub fn level(&self) -> Option<Level> {
+        match &self.value {
+            Some(value) => value.get("level_num").and_then(Level::from_value).or_else(|| {
+                LEVEL_KEYS.iter().find_map(|key| query::resolve_field(value, key).and_then(Level::from_value))
+            }),
+            None => plain::line_hints(&self.raw).level,
+        }
+    }
+
+    /// The human-readable message: the `message` or `msg` field, else the raw line.
+    pub fn message(&self) -> &str {
+        self.value
+            .as_ref()
+            .and_then(|value| MESSAGE_KEYS.iter().find_map(|key| value.get(key)?.as_str()))
+            .unwrap_or(&self.raw)
+    }
+
+    /// Tests the entry against a compiled query, using the raw line for text matches.
+    pub fn matches(&self, query: &Query) -> Result<bool, QueryError> {
+        self.matches_with_options(query, QueryOptions::default_ref())
+    }
+
+    pub fn matches_with_options(&self, query: &Query, options: &QueryOptions) -> Result<bool, QueryError> {
+        query.matches_with_options(self.value().unwrap_or(&Value::Null), &self.raw, options)
+    }
 }
 
 /// Parses a single line of text into a LogEntry using better heuristics.
+///
+/// Use [`detect::parse_log_line_with_report`] to see which parser took the line, or why
+/// every parser rejected it.
 pub fn parse_log_line(line: &str) -> LogEntry {
+    sniff(line, ParseOptions::default_ref(), &mut Trace::off())
+}
+
+/// Like [`parse_log_line`], with the heuristics tuned by `options`.
+pub fn parse_log_line_with(line: &str, options: &ParseOptions) -> LogEntry {
+    sniff(line, options, &mut Trace::off())
+}
+
+/// The heuristics behind [`parse_log_line`], noting each parser's verdict in `trace`.
+pub(crate) fn sniff(line: &str, options: &ParseOptions, trace: &mut Trace) -> LogEntry {
+    if options.max_line_length.is_some_and(|max| line.len() > max) {
+        return LogEntry::unstructured(line);
+    }
+    let parsed = |value, format| parsed_with(value, line, format, options);
     let trimmed = line.trim();
 
     // 1. Strict JSON check.
-    if trimmed.starts_with('{') && trimmed.ends_with('}') {
-        if let Ok(json_val) = json::parse_json_line(trimmed) {
-            return LogEntry::Structured(json_val);
+    if !options.tries(Format::Json) {
+        trace.reject(Format::Json, || "disabled".to_string());
+    } else if trimmed.starts_with('{') && trimmed.ends_with('}') {
+        match json::parse_json_line_with(trimmed, options.duplicates) {
+            Ok(json_val) => {
+                trace.accept(Format::Json, 1.0);
+                return parsed(json_val, Format::Json);
+            }
+            Err(e) => trace.fail(Format::Json, || format!("invalid JSON: {}", e)),
         }
+    } else if let Some(json_val) = parse_prefixed_json(trimmed, options.duplicates, &options.time) {
+        // A JSON object behind a log prefix (`2024-01-01 12:00:00 myapp[123]: {...}`).
+        trace.accept(Format::Json, 0.8);
+        return parsed(json_val, Format::Json);
+    } else {
+        trace.reject(Format::Json, || "no JSON object found in the line".to_string());
     }
 
     // 2. Nginx / Common Log Format check.
-    // Heuristic: Starts with a number (IP) and contains standard date brackets `[`
-    if (trimmed.starts_with(|c: char| c.is_ascii_digit()) || trimmed.starts_with(":")) 
-        && trimmed.contains(" - - [") {
+    // Heuristic: a few leading idents (address, ident, user) before a `[dd/Mon/yyyy:` date.
+    if !options.tries(Format::Nginx) {
+        trace.reject(Format::Nginx, || "disabled".to_string());
+    } else if nginx::looks_like_access_line(trimmed) {
         if let Some(nginx_val) = nginx::parse_nginx_line(trimmed) {
-            return LogEntry::Structured(nginx_val);
+            trace.accept(Format::Nginx, 0.9);
+            return parsed(nginx_val, Format::Nginx);
         }
+        trace.fail(Format::Nginx, || "malformed request, status or size fields".to_string());
+    } else if nginx::looks_like_error_line(trimmed) {
+        if let Some(nginx_val) = nginx::parse_nginx_error_line_with(trimmed, &options.time) {
+            trace.accept(Format::Nginx, 0.9);
+            return parsed(nginx_val, Format::Nginx);
+        }
+        trace.fail(Format::Nginx, || "malformed error.log level or pid#tid".to_string());
+    } else {
+        trace.reject(Format::Nginx, || "no access log [dd/Mon/yyyy:...] or error.log date".to_string());
     }
 
-    // 3. Heuristic logfmt check.
-    if trimmed.contains('=') {
-        if let Ok(logfmt_val) = logfmt::parse_logfmt_line(trimmed) {
-            if let Some(map) = logfmt_val.as_object() {
-                if !map.is_empty() {
-                    let total_keys = map.len();
-                    let null_value_keys = map.values().filter(|v| v.is_null()).count();
-                    // Basic heuristic: If less than half the keys have null values, it's likely logfmt
-                    if null_value_keys < total_keys / 2 {
-                        return LogEntry::Structured(logfmt_val);
-                    }
-                }
+    // 3. Syslog check, only when the `<PRI>` prefix makes it unambiguous.
+    if !options.tries(Format::Syslog) {
+        trace.reject(Format::Syslog, || "disabled".to_string());
+    } else if trimmed.starts_with('<') && trimmed[1..].starts_with(|c: char| c.is_ascii_digit()) {
+        if let Some(syslog_val) = syslog::parse_syslog_line_with(trimmed, &options.time) {
+            trace.accept(Format::Syslog, 0.9);
+            return parsed(syslog_val, Format::Syslog);
+        }
+        trace.fail(Format::Syslog, || "malformed header after the <PRI> prefix".to_string());
+    } else {
+        trace.reject(Format::Syslog, || "no <PRI> prefix".to_string());
+    }
+
+    // 4. Heuristic logfmt check.
+    if !options.tries(Format::Logfmt) {
+        trace.reject(Format::Logfmt, || "disabled".to_string());
+    } else if trimmed.contains('=') {
+        match check_logfmt(trimmed, options) {
+            Ok(logfmt_val) => {
+                trace.accept(Format::Logfmt, logfmt_confidence(&logfmt_val));
+                return parsed(logfmt_val, Format::Logfmt);
             }
+            Err((_, 0)) => trace.reject(Format::Logfmt, || "no key=value pairs".to_string()),
+            Err((bare, total)) => trace.fail(Format::Logfmt, || {
+                format!("{} of {} keys are bare words without a value", bare, total)
+            }),
         }
+    } else {
+        trace.reject(Format::Logfmt, || "no key=value pairs".to_string());
     }
 
-    // 4. If all else fails, treat it as unstructured text.
-    LogEntry::Unstructured(line.to_string())
-}
\ No newline at end of file
+    // 5. If all else fails, treat it as unstructured text.
+    LogEntry::unstructured(line)
+}
+
+/// Parses a line that may hold several JSON entries: objects back to back
+/// (`{...}{...}` or `{...} {...}`) or an array of objects (`[{...}, {...}]`). Each
+/// object becomes its own entry, with its own text as the raw line.
+///
+/// Anything else yields the single entry [`parse_log_line`] would.
+pub fn parse_log_line_multi(line: &str) -> Vec<LogEntry> {
+    let trimmed = line.trim();
+    if trimmed.starts_with('[') {
+        if let Ok(Value::Array(items)) = json::parse_json_line(trimmed) {
+            if !items.is_empty() && items.iter().all(Value::is_object) {
+                return items
+                    .into_iter()
+                    .map(|item| {
+                        let raw = item.to_string();
+                        parsed(item, raw, Format::Json)
+                    })
+                    .collect();
+            }
+        }
+    } else if trimmed.starts_with('{') {
+        let mut stream = serde_json::Deserializer::from_str(trimmed).into_iter::<Value>();
+        let mut entries = Vec::new();
+        let mut start = 0;
+        while let Some(Ok(value)) = stream.next() {
+            let end = stream.byte_offset();
+            if !value.is_object() {
+                break;
+            }
+            entries.push(parsed(value, trimmed[start..end].trim(), Format::Json));
+            start = end;
+        }
+        // Only when the whole line was consumed; otherwise fall back to the usual heuristics.
+        if entries.len() > 1 && trimmed[start..].trim().is_empty() {
+            return entries;
+        }
+    }
+    vec![parse_log_line(line)]
+}
+
+/// Like [`parse_log_line`], but lines left unstructured get a second chance as
+/// almost-JSON (see [`json::parse_json_lenient`]). Opt in with
+/// `Pipeline::with_parser(parse_log_line_lenient)`.
+pub fn parse_log_line_lenient(line: &str) -> LogEntry {
+    let entry = parse_log_line(line);
+    if entry.is_structured() {
+        return entry;
+    }
+    match json::parse_json_lenient(line) {
+        Some(json_val) => parsed(json_val, line, Format::Json),
+        None => entry,
+    }
+}
+
+/// A structured entry, after the steps every parser shares.
+fn parsed(value: Value, raw: impl Into<String>, format: Format) -> LogEntry {
+    parsed_with(value, raw, format, ParseOptions::default_ref())
+}
+
+fn parsed_with(mut value: Value, raw: impl Into<String>, format: Format, options: &ParseOptions) -> LogEntry {
+    normalize_level(&mut value);
+    if value.is_object() && value.get("timestamp").is_none() {
+        if let Some(ts) = time_parser::extract_timestamp_with_keys(&value, &options.timestamp_keys, &options.time) {
+            value["timestamp"] = Value::from(ts.to_rfc3339());
+        }
+    }
+    LogEntry::structured(value, raw, format)
+}
+
+/// Maps whatever level field an entry has (`WARNING`, `warn`, `W`, `30`, `sev=err`, ...)
+/// onto [`Level`], stored as the canonical `level` text plus `level_num`
+/// (10 = trace ... 60 = fatal), so `level >= warn` works the same across sources.
+///
+/// The first of the usual level fields that holds a recognisable level wins. `level`
+/// is written when the entry has none or spells that level differently (`WARNING`,
+/// `30`); a `level` that isn't a recognisable level, the other level fields and an
+/// existing `level_num` are kept as they are.
+pub fn normalize_level(value: &mut Value) {
+    let recognise = |field: &Value| match field {
+        Value::String(s) => Level::parse_field(s),
+        other => Level::from_value(other),
+    };
+    let level = LEVEL_KEYS.iter().find_map(|key| recognise(query::resolve_field(value, key)?));
+    if let (Some(level), Some(map)) = (level, value.as_object_mut()) {
+        let canonical = Value::from(level.as_str());
+        let respelled = |written: &Value| *written != canonical && recognise(written) == Some(level);
+        if map.get("level").is_none_or(respelled) {
+            map.insert("level".to_string(), canonical);
+        }
+        map.entry("level_num").or_insert_with(|| Value::from(level.as_number()));
+    }
+}
+
+/// Parses the first balanced `{...}` of a line that has text around it, merging what the
+/// prefix reveals (timestamp, level, bracketed tags, a syslog-style `app[pid]:` tag) into
+/// the object without overwriting its own fields. Text after the object is ignored.
+///
+/// Prefixes containing `=` or `"` are left alone, so logfmt lines with a JSON-valued
+/// field aren't mistaken for JSON.
+fn parse_prefixed_json(trimmed: &str, duplicates: DuplicateKeys, time: &TimeOptions) -> Option<Value> {
+    let (start, end) = json::find_object(trimmed)?;
+    let prefix = &trimmed[..start];
+    if prefix.contains(['=', '"']) {
+        return None;
+    }
+    let mut value = json::parse_json_line_with(&trimmed[start..end], duplicates).ok()?;
+    let map = value.as_object_mut()?;
+
+    let hints = plain::line_hints_with(prefix, time);
+    let has_timestamp = time_parser::COMMON_KEYS.iter().any(|key| map.contains_key(*key));
+    for (key, field) in hints.fields() {
+        let taken = match key.as_str() {
+            "timestamp" => has_timestamp,
+            "level" => LEVEL_KEYS.iter().any(|key| map.contains_key(*key)),
+            _ => map.contains_key(&key),
+        };
+        if !taken {
+            map.insert(key, field);
+        }
+    }
+    if let Some(tag) = prefix.split_whitespace().last().and_then(|token| token.strip_suffix(':')) {
+        let (app, pid) = match tag.split_once('[') {
+            Some((app, pid)) => (app, Some(pid.trim_end_matches(']'))),
+            None => (tag, None),
+        };
+        if !app.is_empty() && !map.contains_key("app") {
+            map.insert("app".to_string(), Value::from(app));
+        }
+        if let Some(pid) = pid.and_then(|pid| pid.parse::<u64>().ok()) {
+            map.entry("pid").or_insert(Value::from(pid));
+        }
+    }
+    Some(value)
+}
+
+/// Parses logfmt, rejecting lines that are mostly bare words rather than `key=value` pairs;
+/// on rejection, says how many of how many keys were bare.
+fn check_logfmt(trimmed: &str, options: &ParseOptions) -> Result<Value, (usize, usize)> {
+    let logfmt_val = logfmt::parse_logfmt_line_with(trimmed, options.duplicates).map_err(|_| (0, 0))?;
+    let map = logfmt_val.as_object().ok_or((0, 0))?;
+    let total_keys = map.len();
+    let null_value_keys = map.values().filter(|v| v.is_null()).count();
+    let rejected = match options.logfmt_threshold {
+        Some(threshold) => logfmt_confidence(&logfmt_val) < threshold,
+        // Basic heuristic: If less than half the keys have null values, it's likely logfmt
+        None => null_value_keys >= total_keys / 2,
+    };
+    if map.is_empty() || rejected {
+        return Err((null_value_keys, total_keys));
+    }
+    Ok(logfmt_val)
+}
+
+/// Share of keys that carry a value.
+fn logfmt_confidence(value: &Value) -> f64 {
+    let Some(map) = value.as_object().filter(|map| !map.is_empty()) else { return 0.0 };
+    map.values().filter(|v| !v.is_null()).count() as f64 / map.len() as f64
+}
+
+/// Parses `line` with the parser for `format` alone, skipping the heuristics of
+/// [`parse_log_line`] for deterministic results. `None` when that parser rejects the
+/// line; [`Format::Plain`] always succeeds.
+pub fn parse_log_line_as(line: &str, format: Format) -> Option<LogEntry> {
+    parse_as(line, format, false)
+}
+
+/// Like [`parse_log_line_as`] for a format that was detected rather than named: logfmt
+/// lines still have to pass the bare-word check of [`parse_log_line`].
+pub(crate) fn parse_detected_as(line: &str, format: Format) -> Option<LogEntry> {
+    parse_as(line, format, true)
+}
+
+fn parse_as(line: &str, format: Format, detected: bool) -> Option<LogEntry> {
+    let trimmed = line.trim();
+    let value = match format {
+        Format::Json => match json::parse_json_line(trimmed) {
+            Ok(value) if value.is_object() => value,
+            _ => parse_prefixed_json(trimmed, DuplicateKeys::Last, &TimeOptions::default())?,
+        },
+        Format::Logfmt if detected => check_logfmt(trimmed, ParseOptions::default_ref()).ok()?,
+        // The caller vouches for the format, so bare words don't get the line rejected.
+        Format::Logfmt => match logfmt::parse_logfmt_line(trimmed) {
+            Ok(Value::Object(map)) if !map.is_empty() => Value::Object(map),
+            _ => return None,
+        },
+        Format::Nginx => nginx::parse_nginx_line(trimmed).or_else(|| nginx::parse_nginx_error_line(trimmed))?,
+        Format::Syslog => syslog::parse_syslog_line(trimmed)?,
+        Format::Plain => return Some(LogEntry::unstructured(line)),
+    };
+    Some(parsed(value, line, format))
+}
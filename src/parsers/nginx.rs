@@ -1,45 +1,54 @@
 // File: src/parsers/nginx.rs
 
+use super::url::url_fields;
+use crate::time::TimeOptions;
 use serde_json::{Map, Value};
-use chrono::DateTime;
+use chrono::{DateTime, NaiveDateTime};
 
 /// Optimised linear scanner.
 /// It manually finds delimiters (' ', '[', '"') to slice the string.
 /// This avoids the overhead of the Regex engine entirely.
 pub fn parse_nginx_line(line: &str) -> Option<Value> {
-    let mut remainder = line;
-
-    // 1. Remote Addr (Stop at first space)
-    let (remote_addr, rest) = split_once_char(remainder, ' ')?;
-    remainder = rest.trim_start_matches('-'); // Skip the dash
-    remainder = remainder.trim_start();       // Skip spaces
+    // 1. Everything before the `[time]`: `remote_addr ident remote_user`, where the address
+    // may be a hostname or IPv6 (bare or bracketed) and may follow extra leading idents
+    // (`$host $remote_addr - -` in vhost formats, or a `10.0.0.1, 10.0.0.2` proxy chain).
+    let time_start = find_time_bracket(line)?;
+    let mut idents: Vec<&str> = line[..time_start].split_whitespace().collect();
+    if idents.len() < 3 {
+        return None;
+    }
 
-    // 2. Remote User (Stop at next space)
-    // Usually "-" but could be a username
-    let (_remote_user, rest) = split_once_char(remainder, ' ')?;
-    remainder = rest.trim_start();
+    // 2. Remote User and ident (usually "-" both)
+    let _remote_user = idents.pop()?;
+    let _ident = idents.pop()?;
+    let (remote_addr, vhost, chain) = if idents.iter().any(|token| token.ends_with(',')) {
+        let chain = idents.join(" ");
+        (idents[0].trim_end_matches(','), None, Some(chain))
+    } else {
+        let addr = idents.pop()?;
+        (addr, (!idents.is_empty()).then(|| idents.join(" ")), None)
+    };
+    let remote_addr = remote_addr.trim_start_matches('[').trim_end_matches(']');
+    let mut remainder = &line[time_start..];
 
     // 3. Time (Between [ and ])
     if !remainder.starts_with('[') { return None; }
     let end_bracket = remainder.find(']')?;
     let raw_time = &remainder[1..end_bracket];
-    remainder = &remainder[end_bracket+1..].trim_start();
+    remainder = remainder[end_bracket+1..].trim_start();
 
     // 4. Request "METHOD PATH PROTO" (Between " and ")
     if !remainder.starts_with('"') { return None; }
-    // Find the closing quote. We can't just look for " because the URL might contain escaped quotes
-    // But for standard Nginx logs, looking for the next " is usually safe. 
-    // For robustness, we look for the quote followed by a space.
-    let end_quote = remainder[1..].find('"')? + 1; 
-    let request_line = &remainder[1..end_quote];
-    remainder = &remainder[end_quote+1..].trim_start();
-
-    // Parse Request Line parts
-    let mut req_parts = request_line.split_whitespace();
-    let method = req_parts.next().unwrap_or("-");
-    let path = req_parts.next().unwrap_or("-");
-    // Protocol is the rest, or "-"
-    
+    // The request line is client-controlled, so skip quotes escaped as `\"`.
+    let end_quote = find_closing_quote(&remainder[1..])? + 1;
+    let request_line = remainder[1..end_quote].replace("\\\"", "\"");
+    remainder = remainder[end_quote+1..].trim_start();
+
+    // Parse Request Line parts. Protocol is optional (HTTP/0.9 style `GET /`); anything
+    // else (`-` for an empty request, TLS bytes sent to a plain port, ...) is kept whole
+    // and flagged rather than rejecting the line.
+    let request = parse_request_line(&request_line);
+
     // 5. Status (Int)
     let (status_str, rest) = split_once_char(remainder, ' ')?;
     remainder = rest;
@@ -57,8 +66,11 @@ pub fn parse_nginx_line(line: &str) -> Option<Value> {
     remainder = rest;
 
     // 9. X-Forwarded-For (Optional, quoted)
-    let x_forwarded = if !remainder.is_empty() {
-        extract_quoted(remainder).map(|(val, _)| val)
+    let x_forwarded = if remainder.starts_with('"') {
+        extract_quoted(remainder).map(|(val, rest)| {
+            remainder = rest;
+            val
+        })
     } else {
         None
     };
@@ -68,6 +80,9 @@ pub fn parse_nginx_line(line: &str) -> Option<Value> {
     let mut map = Map::with_capacity(10);
     
     map.insert("remote_addr".to_string(), Value::String(remote_addr.to_string()));
+    if let Some(vhost) = vhost {
+        map.insert("vhost".to_string(), Value::String(vhost));
+    }
     map.insert("time_local".to_string(), Value::String(raw_time.to_string()));
 
     // Date Parsing (The heaviest part, but necessary for stats)
@@ -77,11 +92,24 @@ pub fn parse_nginx_line(line: &str) -> Option<Value> {
         map.insert("timestamp".to_string(), Value::String(raw_time.to_string()));
     }
 
-    map.insert("method".to_string(), Value::String(method.to_string()));
-    map.insert("path".to_string(), Value::String(path.to_string()));
+    match request {
+        Some((method, path, protocol)) => {
+            map.insert("method".to_string(), Value::String(method.to_string()));
+            map.insert("path".to_string(), Value::String(path.to_string()));
+            map.extend(url_fields(path));
+            if let Some(protocol) = protocol {
+                map.insert("protocol".to_string(), Value::String(protocol.to_string()));
+            }
+        }
+        None => {
+            map.insert("request".to_string(), Value::String(request_line.clone()));
+            map.insert("malformed_request".to_string(), Value::Bool(true));
+        }
+    }
 
     if let Ok(n) = status_str.parse::<u64>() {
         map.insert("status".to_string(), Value::Number(n.into()));
+        map.insert("status_class".to_string(), Value::String(format!("{}xx", n / 100)));
         // Helper level
         let level = if n >= 500 { "ERROR" } else if n >= 400 { "WARN" } else { "INFO" };
         map.insert("level".to_string(), Value::String(level.to_string()));
@@ -94,13 +122,151 @@ pub fn parse_nginx_line(line: &str) -> Option<Value> {
     map.insert("http_referer".to_string(), Value::String(referer.to_string()));
     map.insert("http_user_agent".to_string(), Value::String(ua.to_string()));
 
-    if let Some(xf) = x_forwarded {
-        map.insert("x_forwarded_for".to_string(), Value::String(xf.to_string()));
+    if let Some(xf) = x_forwarded.map(str::to_string).or(chain) {
+        map.insert("x_forwarded_for".to_string(), Value::String(xf));
     }
 
+    // 10. Timing and other fields popular log_format variants append.
+    parse_extended_fields(remainder, &mut map);
+
     Some(Value::Object(map))
 }
 
+/// Names log_format variants give the trailing fields, mapped to the nginx variable names.
+const EXTENDED_ALIASES: &[(&str, &str)] = &[
+    ("rt", "request_time"),
+    ("urt", "upstream_response_time"),
+    ("uct", "upstream_connect_time"),
+    ("uht", "upstream_header_time"),
+    ("gz", "gzip_ratio"),
+    ("reqid", "request_id"),
+    ("req_id", "request_id"),
+];
+
+/// Parses what follows the user agent / X-Forwarded-For: `key=value` pairs
+/// (`rt=0.123 urt="0.050, 0.020" request_id=abc`), or bare values in the usual
+/// `$request_time $upstream_response_time` order followed by an optional request id.
+///
+/// Timings and `gzip_ratio` are numbers. A comma list of upstream times (one per upstream
+/// tried) becomes their sum, with the individual times in `<field>_list`. `-` means unset.
+fn parse_extended_fields(remainder: &str, map: &mut Map<String, Value>) {
+    let mut positional = ["request_time", "upstream_response_time"].into_iter();
+    for token in extended_tokens(remainder) {
+        let (key, value) = match token.split_once('=') {
+            Some((key, value)) => {
+                let key = EXTENDED_ALIASES.iter().find(|(alias, _)| *alias == key).map_or(key, |(_, name)| name);
+                (key, value.trim_matches('"'))
+            }
+            None if token.trim_matches('"') == "-" => {
+                positional.next();
+                continue;
+            }
+            None if token.starts_with(|c: char| c.is_ascii_digit()) && !is_request_id(token) => {
+                match positional.next() {
+                    Some(key) => (key, token),
+                    None => continue,
+                }
+            }
+            None if is_request_id(token.trim_matches('"')) => ("request_id", token.trim_matches('"')),
+            None => continue,
+        };
+        if value == "-" || value.is_empty() {
+            continue;
+        }
+        let value = extended_value(key, value, map);
+        map.insert(key.to_string(), value);
+    }
+}
+
+fn extended_value(key: &str, value: &str, map: &mut Map<String, Value>) -> Value {
+    if key == "request_id" {
+        return Value::String(value.to_string());
+    }
+    // `0.050, 0.020` or `0.050 : 0.020` (internal redirects); `-` for upstreams not reached.
+    let times: Vec<f64> = value
+        .split([',', ':'])
+        .filter_map(|t| t.trim().parse::<f64>().ok())
+        .collect();
+    match times.as_slice() {
+        [] => Value::String(value.to_string()),
+        [single] if !value.contains([',', ':']) => number(*single),
+        many => {
+            map.insert(format!("{}_list", key), Value::Array(many.iter().map(|t| number(*t)).collect()));
+            number(many.iter().sum())
+        }
+    }
+}
+
+fn number(n: f64) -> Value {
+    serde_json::Number::from_f64(n).map_or(Value::Null, Value::Number)
+}
+
+/// A `$request_id` (32 hex characters) or a UUID.
+fn is_request_id(token: &str) -> bool {
+    (token.len() == 32 && token.bytes().all(|b| b.is_ascii_hexdigit()))
+        || (token.len() == 36 && token.bytes().all(|b| b.is_ascii_hexdigit() || b == b'-'))
+}
+
+/// Splits on spaces, keeping quoted values and `0.050, 0.020` lists together.
+fn extended_tokens(s: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut in_quotes = false;
+    let bytes = s.as_bytes();
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'"' => in_quotes = !in_quotes,
+            b' ' if !in_quotes => {
+                let continues_list =
+                    (i > 0 && matches!(bytes[i - 1], b',' | b':' | b' ')) || bytes.get(i + 1) == Some(&b':');
+                if let (Some(from), false) = (start, continues_list) {
+                    tokens.push(&s[from..i]);
+                    start = None;
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(from) = start {
+        tokens.push(&s[from..]);
+    }
+    tokens
+}
+
+/// Whether `line` looks like an access log entry: a few leading idents, then a
+/// Common Log Format `[10/Oct/2023:13:55:36 +0000]`.
+pub fn looks_like_access_line(line: &str) -> bool {
+    find_time_bracket(line).is_some_and(|start| line[..start].split_whitespace().nth(2).is_some())
+}
+
+/// Index of the `[` opening the first `[dd/Mon/yyyy:` after a space.
+fn find_time_bracket(line: &str) -> Option<usize> {
+    line.match_indices(" [").map(|(i, _)| i + 1).find(|&start| {
+        let b = &line.as_bytes()[start + 1..];
+        b.len() >= 12
+            && b[..2].iter().all(u8::is_ascii_digit)
+            && b[2] == b'/'
+            && b[3..6].iter().all(u8::is_ascii_alphabetic)
+            && b[6] == b'/'
+            && b[7..11].iter().all(u8::is_ascii_digit)
+            && b[11] == b':'
+    })
+}
+
+/// Splits `METHOD PATH [PROTOCOL]`; `None` for anything that isn't an HTTP request.
+fn parse_request_line(request_line: &str) -> Option<(&str, &str, Option<&str>)> {
+    let mut parts = request_line.split(' ');
+    let method = parts.next().filter(|m| !m.is_empty() && m.bytes().all(|b| b.is_ascii_uppercase()))?;
+    let path = parts.next().filter(|p| !p.is_empty())?;
+    let protocol = parts.next();
+    if parts.next().is_some() || protocol.is_some_and(|p| !p.starts_with("HTTP/")) {
+        return None;
+    }
+    Some((method, path, protocol))
+}
+
 // --- Helpers ---
 
 #[inline(always)]
@@ -113,10 +279,112 @@ fn split_once_char(s: &str, delimiter: char) -> Option<(&str, &str)> {
 fn extract_quoted(s: &str) -> Option<(&str, &str)> {
     let start = s.find('"')?;
     let remainder_after_start = &s[start + 1..];
-    let end = remainder_after_start.find('"')?;
+    let end = find_closing_quote(remainder_after_start)?;
     
     let content = &remainder_after_start[..end];
-    let rest = &remainder_after_start[end + 1..].trim_start();
+    let rest = remainder_after_start[end + 1..].trim_start();
     
     Some((content, rest))
-}
\ No newline at end of file
+}
+/// Context attributes nginx appends to error.log messages, after `, `.
+const ERROR_CONTEXT_KEYS: &[&str] =
+    &["client", "server", "request", "subrequest", "upstream", "host", "referrer", "login", "port"];
+
+/// Whether `line` starts like an nginx error.log entry: `2024/01/01 12:00:00 [`.
+pub fn looks_like_error_line(line: &str) -> bool {
+    let b = line.as_bytes();
+    b.len() > 21
+        && b[4] == b'/'
+        && b[7] == b'/'
+        && b[10] == b' '
+        && b[13] == b':'
+        && b[19] == b' '
+        && b[20] == b'['
+        && b[..4].iter().all(u8::is_ascii_digit)
+}
+
+/// Parses an nginx error.log line:
+/// `2024/01/01 12:00:00 [error] 123#0: *456 message, client: 10.0.0.1, server: example.com, request: "GET / HTTP/1.1"`.
+///
+/// Produces `timestamp` (the server's local time, read as UTC), `level`, `pid`, `tid`,
+/// `connection` (the `*456`), `message`, and one field per trailing context attribute
+/// (`client`, `server`, `request`, `upstream`, `host`, ...).
+pub fn parse_nginx_error_line(line: &str) -> Option<Value> {
+    parse_nginx_error_line_with(line, &TimeOptions::default())
+}
+
+/// Like [`parse_nginx_error_line`], with the local time anchored to `options.naive_zone`.
+pub fn parse_nginx_error_line_with(line: &str, options: &TimeOptions) -> Option<Value> {
+    if !looks_like_error_line(line) {
+        return None;
+    }
+    let raw_time = &line[..19];
+    let remainder = &line[21..];
+
+    let (level, rest) = split_once_char(remainder, ']')?;
+    let (ids, rest) = rest.trim_start().split_once(": ").unwrap_or((rest.trim_start(), ""));
+    let (pid, tid) = split_once_char(ids, '#')?;
+
+    let mut map = Map::with_capacity(10);
+    let dt = NaiveDateTime::parse_from_str(raw_time, "%Y/%m/%d %H:%M:%S").ok()?;
+    map.insert("timestamp".to_string(), Value::String(options.from_naive(dt)?.to_rfc3339()));
+    map.insert("level".to_string(), Value::String(level.to_string()));
+    map.insert("pid".to_string(), Value::Number(pid.parse::<u64>().ok()?.into()));
+    map.insert("tid".to_string(), Value::Number(tid.parse::<u64>().ok()?.into()));
+
+    let mut message = rest;
+    if let Some((conn, after)) = message.strip_prefix('*').and_then(|m| split_once_char(m, ' ')) {
+        if let Ok(n) = conn.parse::<u64>() {
+            map.insert("connection".to_string(), Value::Number(n.into()));
+            message = after;
+        }
+    }
+
+    // The message itself may contain commas; the context starts at the first known key.
+    let context_start = ERROR_CONTEXT_KEYS
+        .iter()
+        .filter_map(|key| message.find(&format!(", {}: ", key)))
+        .min();
+    if let Some(start) = context_start {
+        parse_error_context(&message[start + 2..], &mut map);
+        message = &message[..start];
+    }
+    map.insert("message".to_string(), Value::String(message.to_string()));
+
+    Some(Value::Object(map))
+}
+
+/// Parses `key: value, key: "quoted, value", ...`.
+fn parse_error_context(mut s: &str, map: &mut Map<String, Value>) {
+    while let Some((key, rest)) = s.split_once(": ") {
+        let (value, rest) = match rest.strip_prefix('"') {
+            Some(quoted) => match find_closing_quote(quoted) {
+                Some(end) => (quoted[..end].replace("\\\"", "\""), &quoted[end + 1..]),
+                None => (quoted.to_string(), ""),
+            },
+            None => match rest.find(", ") {
+                Some(end) => (rest[..end].to_string(), &rest[end..]),
+                None => (rest.to_string(), ""),
+            },
+        };
+        map.insert(key.to_string(), Value::String(value));
+        match rest.strip_prefix(", ") {
+            Some(next) => s = next,
+            None => break,
+        }
+    }
+}
+
+/// Index of the first `"` not escaped with a backslash.
+fn find_closing_quote(s: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, b) in s.bytes().enumerate() {
+        match b {
+            _ if escaped => escaped = false,
+            b'\\' => escaped = true,
+            b'"' => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,149 @@
+// File: src/parsers/plain.rs
+
+use crate::level::Level;
+use crate::time::TimeOptions;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use regex::Regex;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+/// Leading tokens examined for a level or tags once the timestamp is skipped.
+const MAX_PREFIX_TOKENS: usize = 4;
+
+/// Best-effort metadata found at the start of a plain-text line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineHints {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub level: Option<Level>,
+    /// Bracketed tags such as `[main]` or `[db-pool]`, in order.
+    pub tags: Vec<String>,
+}
+
+impl LineHints {
+    pub fn is_empty(&self) -> bool {
+        self.timestamp.is_none() && self.level.is_none() && self.tags.is_empty()
+    }
+
+    /// The hints as `timestamp` (RFC 3339), `level` and `tags` fields, so time-range
+    /// and level queries work on them.
+    pub fn fields(&self) -> Vec<(String, Value)> {
+        let mut fields = Vec::new();
+        if let Some(ts) = self.timestamp {
+            fields.push(("timestamp".to_string(), Value::from(ts.to_rfc3339())));
+        }
+        if let Some(level) = self.level {
+            fields.push(("level".to_string(), Value::from(level.as_str())));
+        }
+        if !self.tags.is_empty() {
+            fields.push(("tags".to_string(), Value::from(self.tags.clone())));
+        }
+        fields
+    }
+}
+
+fn iso_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"^\[?(\d{4}-\d{2}-\d{2})[T ](\d{2}:\d{2}:\d{2})(?:[.,](\d{1,9}))? ?(Z|[+-]\d{2}:?\d{2})?\]?")
+            .unwrap()
+    })
+}
+
+fn clf_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\[?(\d{2}/[A-Z][a-z]{2}/\d{4}:\d{2}:\d{2}:\d{2} [+-]\d{4})\]?").unwrap())
+}
+
+fn syslog_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^([A-Z][a-z]{2} [ \d]\d \d{2}:\d{2}:\d{2})").unwrap())
+}
+
+/// Parses a timestamp at the very start of `line`, returning it and the rest of the line.
+///
+/// Recognizes ISO 8601 (with `T` or a space, optional fraction and offset; UTC when no
+/// offset is given), the Common Log Format (`10/Oct/2023:13:55:36 +0000`) and syslog's
+/// `Oct 10 13:55:36`, which has no year and is placed per [`TimeOptions::from_yearless`].
+pub fn leading_timestamp(line: &str) -> Option<(DateTime<Utc>, &str)> {
+    leading_timestamp_with(line, &TimeOptions::default())
+}
+
+/// Like [`leading_timestamp`], with times without an offset anchored per `options`.
+pub fn leading_timestamp_with<'a>(line: &'a str, options: &TimeOptions) -> Option<(DateTime<Utc>, &'a str)> {
+    if let Some(caps) = iso_regex().captures(line) {
+        let fraction = caps.get(3).map_or(String::new(), |m| format!(".{}", m.as_str()));
+        let offset = match caps.get(4).map(|m| m.as_str()) {
+            None => {
+                let text = format!("{}T{}{}", &caps[1], &caps[2], fraction);
+                let ts = NaiveDateTime::parse_from_str(&text, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+                return Some((options.from_naive(ts)?, &line[caps[0].len()..]));
+            }
+            Some("Z") => "Z".to_string(),
+            Some(offset) if offset.len() == 5 => format!("{}:{}", &offset[..3], &offset[3..]),
+            Some(offset) => offset.to_string(),
+        };
+        let text = format!("{}T{}{}{}", &caps[1], &caps[2], fraction, offset);
+        let ts = DateTime::parse_from_rfc3339(&text).ok()?;
+        return Some((ts.with_timezone(&Utc), &line[caps[0].len()..]));
+    }
+    if let Some(caps) = clf_regex().captures(line) {
+        let ts = DateTime::parse_from_str(&caps[1], "%d/%b/%Y:%H:%M:%S %z").ok()?;
+        return Some((ts.with_timezone(&Utc), &line[caps[0].len()..]));
+    }
+    if let Some(caps) = syslog_regex().captures(line) {
+        return Some((options.from_yearless(&caps[1])?, &line[caps[0].len()..]));
+    }
+    None
+}
+
+/// A level name (`ERROR`, `warn`, `[INFO]`, `Error:`); numeric levels are too
+/// ambiguous in free text to count.
+fn level_token(token: &str) -> Option<Level> {
+    let token = token.trim_end_matches(':');
+    if token.is_empty() || !token.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    Level::parse(token)
+}
+
+/// Extracts a leading timestamp, a level token and bracketed tags from a plain-text line.
+///
+/// Only the start of the line is examined (the timestamp, then up to four tokens), so
+/// words like "error" inside the message body are not mistaken for the level.
+pub fn line_hints(line: &str) -> LineHints {
+    line_hints_with(line, &TimeOptions::default())
+}
+
+/// Like [`line_hints`], with the timestamp read per `options`.
+pub fn line_hints_with(line: &str, options: &TimeOptions) -> LineHints {
+    let mut hints = LineHints::default();
+    let mut rest = line.trim_start();
+    if let Some((ts, after)) = leading_timestamp_with(rest, options) {
+        hints.timestamp = Some(ts);
+        rest = after;
+    }
+
+    for _ in 0..MAX_PREFIX_TOKENS {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '|' || c == '-');
+        if let Some(inner) = rest.strip_prefix('[') {
+            let Some(end) = inner.find(']') else { break };
+            let tag = inner[..end].trim();
+            rest = &inner[end + 1..];
+            match level_token(tag) {
+                Some(level) if hints.level.is_none() => hints.level = Some(level),
+                _ if !tag.is_empty() => hints.tags.push(tag.to_string()),
+                _ => {}
+            }
+            continue;
+        }
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        match level_token(&rest[..end]) {
+            Some(level) if hints.level.is_none() => {
+                hints.level = Some(level);
+                rest = &rest[end..];
+            }
+            _ => break,
+        }
+    }
+    hints
+}
@@ -0,0 +1,50 @@
+// File: src/parsers/simd.rs
+//
+// simd-json only pays off when its borrowed value is used as is: copying each line into
+// a fresh buffer and converting the result into an owned `serde_json::Value` is slower
+// than `serde_json` itself (see benches/json_parsing.rs), so the default parsers keep
+// using `serde_json`. `SimdParser` reuses its buffers across lines and hands out the
+// borrowed value; `to_value` is there for the odd line that has to become owned.
+
+use serde_json::{Map, Number, Value};
+use simd_json::{BorrowedValue, Buffers, StaticNode};
+
+/// Parses JSON lines with `simd-json`, reusing its scratch buffers from line to line.
+#[derive(Default)]
+pub struct SimdParser {
+    scratch: Vec<u8>,
+    buffers: Buffers,
+}
+
+impl SimdParser {
+    pub fn new() -> Self {
+        SimdParser::default()
+    }
+
+    /// Parses `line`, or `None` when `simd-json` rejects it. Strings in the value borrow
+    /// from the parser's buffer, so it lives until the next line is parsed. Objects keep
+    /// the last of repeated keys, like `serde_json`.
+    pub fn parse(&mut self, line: &str) -> Option<BorrowedValue<'_>> {
+        self.scratch.clear();
+        self.scratch.extend_from_slice(line.as_bytes());
+        simd_json::to_borrowed_value_with_buffers(&mut self.scratch, &mut self.buffers).ok()
+    }
+}
+
+/// Converts a borrowed value into an owned [`Value`].
+pub fn to_value(value: BorrowedValue) -> Value {
+    match value {
+        BorrowedValue::Static(StaticNode::Null) => Value::Null,
+        BorrowedValue::Static(StaticNode::Bool(b)) => Value::Bool(b),
+        BorrowedValue::Static(StaticNode::I64(n)) => Value::from(n),
+        BorrowedValue::Static(StaticNode::U64(n)) => Value::from(n),
+        BorrowedValue::Static(StaticNode::F64(n)) => Number::from_f64(n).map_or(Value::Null, Value::Number),
+        BorrowedValue::String(s) => Value::String(s.into_owned()),
+        BorrowedValue::Array(items) => Value::Array((*items).into_iter().map(to_value).collect()),
+        BorrowedValue::Object(entries) => {
+            let map: Map<String, Value> =
+                (*entries).into_iter().map(|(key, value)| (key.into_owned(), to_value(value))).collect();
+            Value::Object(map)
+        }
+    }
+}
@@ -0,0 +1,159 @@
+// File: src/parsers/syslog.rs
+
+use super::plain::leading_timestamp_with;
+use crate::level::Level;
+use crate::time::TimeOptions;
+use chrono::DateTime;
+use serde_json::{Map, Value};
+
+/// Parses a syslog line, either RFC 5424 (`<34>1 2024-05-01T12:00:00Z host app 42 ID47 - msg`)
+/// or BSD / RFC 3164 (`<34>May  1 12:00:00 host sshd[42]: msg`, with or without the
+/// `<PRI>` prefix, as written to `/var/log/syslog`).
+///
+/// Produces `timestamp` (RFC 3339), `host`, `app`, `pid`, `message` and, when a `<PRI>`
+/// is present, `facility`, `severity` and the matching `level`. RFC 5424 lines also
+/// get `msgid` and `structured_data` (`{"id": {"param": "value"}}`).
+pub fn parse_syslog_line(line: &str) -> Option<Value> {
+    parse_syslog_line_with(line, &TimeOptions::default())
+}
+
+/// Like [`parse_syslog_line`], with BSD timestamps, which carry no zone, anchored to
+/// `options.naive_zone`.
+pub fn parse_syslog_line_with(line: &str, options: &TimeOptions) -> Option<Value> {
+    let mut map = Map::new();
+    let mut rest = line.trim();
+
+    if let Some(after) = rest.strip_prefix('<') {
+        let end = after.find('>')?;
+        let pri: u8 = after[..end].parse().ok().filter(|&pri| pri <= 191)?;
+        map.insert("facility".to_string(), Value::from(pri / 8));
+        map.insert("severity".to_string(), Value::from(pri % 8));
+        if let Some(level) = Level::from_syslog(pri % 8) {
+            map.insert("level".to_string(), Value::from(level.as_str()));
+        }
+        rest = &after[end + 1..];
+    }
+
+    match rest.strip_prefix("1 ") {
+        Some(rfc5424) => parse_rfc5424(rfc5424, &mut map)?,
+        None => parse_rfc3164(rest, &mut map, options)?,
+    }
+    Some(Value::Object(map))
+}
+
+/// `-` is the RFC 5424 nil value.
+fn insert_token(map: &mut Map<String, Value>, key: &str, token: &str) {
+    if token != "-" && !token.is_empty() {
+        let value = match key {
+            "pid" => token.parse::<u64>().map(Value::from).unwrap_or_else(|_| Value::from(token)),
+            _ => Value::from(token),
+        };
+        map.insert(key.to_string(), value);
+    }
+}
+
+fn parse_rfc5424(line: &str, map: &mut Map<String, Value>) -> Option<()> {
+    let mut parts = line.splitn(6, ' ');
+    let timestamp = parts.next()?;
+    if timestamp != "-" {
+        let ts = DateTime::parse_from_rfc3339(timestamp).ok()?;
+        map.insert("timestamp".to_string(), Value::from(ts.to_rfc3339()));
+    }
+    insert_token(map, "host", parts.next()?);
+    insert_token(map, "app", parts.next()?);
+    insert_token(map, "pid", parts.next()?);
+    insert_token(map, "msgid", parts.next()?);
+
+    let rest = parts.next().unwrap_or("");
+    let message = match rest.strip_prefix('-') {
+        Some(message) => message,
+        None => {
+            let (data, message) = parse_structured_data(rest)?;
+            map.insert("structured_data".to_string(), data);
+            message
+        }
+    };
+    let message = message.strip_prefix(' ').unwrap_or(message);
+    let message = message.strip_prefix('\u{feff}').unwrap_or(message);
+    if !message.is_empty() {
+        map.insert("message".to_string(), Value::from(message));
+    }
+    Some(())
+}
+
+/// Parses `[id param="value" ...][id2 ...]`, returning the elements and what follows them.
+fn parse_structured_data(mut rest: &str) -> Option<(Value, &str)> {
+    let mut data = Map::new();
+    while let Some(element) = rest.strip_prefix('[') {
+        let id_end = element.find([' ', ']'])?;
+        let id = &element[..id_end];
+        let mut params = Map::new();
+        let mut cursor = &element[id_end..];
+        loop {
+            cursor = cursor.trim_start();
+            if let Some(after) = cursor.strip_prefix(']') {
+                cursor = after;
+                break;
+            }
+            let eq = cursor.find("=\"")?;
+            let name = &cursor[..eq];
+            let (value, after) = quoted_value(&cursor[eq + 2..])?;
+            params.insert(name.to_string(), Value::from(value));
+            cursor = after;
+        }
+        data.insert(id.to_string(), Value::Object(params));
+        rest = cursor;
+    }
+    if data.is_empty() {
+        return None;
+    }
+    Some((Value::Object(data), rest))
+}
+
+/// Reads a PARAM-VALUE up to its closing quote, unescaping `\"`, `\\` and `\]`.
+fn quoted_value(s: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some((_, escaped @ ('"' | '\\' | ']'))) => value.push(escaped),
+                Some((_, other)) => {
+                    value.push('\\');
+                    value.push(other);
+                }
+                None => return None,
+            },
+            '"' => return Some((value, &s[i + 1..])),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+fn parse_rfc3164(line: &str, map: &mut Map<String, Value>, options: &TimeOptions) -> Option<()> {
+    let (ts, rest) = leading_timestamp_with(line, options)?;
+    map.insert("timestamp".to_string(), Value::from(ts.to_rfc3339()));
+
+    let rest = rest.trim_start();
+    let (host, rest) = rest.split_once(' ')?;
+    map.insert("host".to_string(), Value::from(host));
+
+    // The TAG: `app[pid]:` or `app:`; anything else is already the message.
+    let mut message = rest.trim_start();
+    let (tag, after) = message.split_once(' ').unwrap_or((message, ""));
+    if let Some(tag) = tag.strip_suffix(':') {
+        match tag.split_once('[') {
+            Some((app, pid)) => {
+                insert_token(map, "app", app);
+                insert_token(map, "pid", pid.trim_end_matches(']'));
+            }
+            None => insert_token(map, "app", tag),
+        }
+        message = after;
+    }
+    if !message.is_empty() {
+        map.insert("message".to_string(), Value::from(message));
+    }
+    Some(())
+}
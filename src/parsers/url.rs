@@ -0,0 +1,64 @@
+// File: src/parsers/url.rs
+
+use super::logfmt::infer_value;
+use serde_json::{Map, Value};
+
+/// Splits a request target (`/search?q=rust%20logs&page=2`) into `url.path`,
+/// `url.query` and one `url.params.<name>` per query parameter, for access-log parsers.
+///
+/// The path and parameters are percent-decoded (`+` is a space in parameters).
+/// Numeric parameter values become numbers, so `url.params.page > 100` compares
+/// numerically; a parameter given more than once becomes an array.
+pub fn url_fields(target: &str) -> Map<String, Value> {
+    let mut map = Map::new();
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (target, None),
+    };
+    map.insert("url.path".to_string(), Value::String(percent_decode(path, false)));
+
+    let Some(query) = query.filter(|q| !q.is_empty()) else { return map };
+    map.insert("url.query".to_string(), Value::String(query.to_string()));
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let key = format!("url.params.{}", percent_decode(name, true));
+        let value = infer_value(percent_decode(value, true));
+        match map.get_mut(&key) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+            None => {
+                map.insert(key, value);
+            }
+        }
+    }
+    map
+}
+
+/// Decodes `%XX` escapes, leaving malformed ones as written. Invalid UTF-8 is replaced.
+pub fn percent_decode(s: &str, plus_as_space: bool) -> String {
+    if !s.contains(['%', '+']) {
+        return s.to_string();
+    }
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b'+' if plus_as_space => out.push(b' '),
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
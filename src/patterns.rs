@@ -0,0 +1,162 @@
+// src/patterns.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Placeholder for a variable token in a template.
+pub const WILDCARD: &str = "<*>";
+
+const DEFAULT_DEPTH: usize = 4;
+const DEFAULT_SIMILARITY: f64 = 0.4;
+const DEFAULT_MAX_CHILDREN: usize = 100;
+
+/// One message template and how many messages it covers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pattern {
+    /// The template, with variable tokens replaced by `<*>`.
+    pub template: String,
+    pub count: u64,
+    /// The first message that created the pattern.
+    pub example: String,
+}
+
+/// Clusters free-text messages into templates with the Drain algorithm
+/// (He et al., "Drain: An Online Log Parsing Approach with Fixed Depth Tree").
+///
+/// Messages are routed through a fixed-depth tree keyed by token count and leading
+/// tokens, then joined to the most similar cluster in the leaf, or start a new one.
+/// Tokens that differ within a cluster become `<*>`:
+/// `"Connection to 10.0.0.1 failed after 30 ms"` and `"Connection to db-2 failed after 12 ms"`
+/// both end up in `"Connection to <*> failed after <*> ms"`.
+#[derive(Debug, Clone)]
+pub struct PatternMiner {
+    depth: usize,
+    similarity: f64,
+    max_children: usize,
+    root: HashMap<usize, Node>,
+    clusters: Vec<Cluster>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    children: HashMap<String, Node>,
+    clusters: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Cluster {
+    tokens: Vec<String>,
+    count: u64,
+    example: String,
+}
+
+impl Default for PatternMiner {
+    fn default() -> Self {
+        PatternMiner::new()
+    }
+}
+
+impl PatternMiner {
+    pub fn new() -> Self {
+        PatternMiner {
+            depth: DEFAULT_DEPTH,
+            similarity: DEFAULT_SIMILARITY,
+            max_children: DEFAULT_MAX_CHILDREN,
+            root: HashMap::new(),
+            clusters: Vec::new(),
+        }
+    }
+
+    /// Minimum share of matching tokens (`0.0..=1.0`) for a message to join a cluster.
+    /// Lower values give fewer, more general patterns.
+    pub fn set_similarity_threshold(&mut self, similarity: f64) {
+        self.similarity = similarity.clamp(0.0, 1.0);
+    }
+
+    /// Depth of the routing tree, counting the root, token-count and leaf layers, so
+    /// `depth - 3` leading tokens are used for routing (minimum 3).
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth.max(3);
+    }
+
+    /// Adds a message and returns the index of the cluster it joined.
+    pub fn add(&mut self, message: &str) -> usize {
+        let tokens: Vec<String> = message.split_whitespace().map(mask).collect();
+
+        let max_children = self.max_children;
+        let mut node = self.root.entry(tokens.len()).or_default();
+        for token in tokens.iter().take(self.depth - 3) {
+            let key = if node.children.contains_key(token) || node.children.len() + 1 < max_children {
+                token.as_str()
+            } else {
+                WILDCARD
+            };
+            node = node.children.entry(key.to_string()).or_default();
+        }
+
+        let best = node
+            .clusters
+            .iter()
+            .map(|&id| (id, similarity(&self.clusters[id].tokens, &tokens)))
+            .filter(|&(_, score)| score >= self.similarity)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id);
+
+        match best {
+            Some(id) => {
+                let cluster = &mut self.clusters[id];
+                for (template, token) in cluster.tokens.iter_mut().zip(&tokens) {
+                    if template != token {
+                        *template = WILDCARD.to_string();
+                    }
+                }
+                cluster.count += 1;
+                id
+            }
+            None => {
+                let id = self.clusters.len();
+                self.clusters.push(Cluster { tokens, count: 1, example: message.to_string() });
+                node.clusters.push(id);
+                id
+            }
+        }
+    }
+
+    /// Current template of every cluster, indexed by the ids [`add`](Self::add) returns.
+    pub(crate) fn templates(&self) -> Vec<String> {
+        self.clusters.iter().map(|cluster| cluster.tokens.join(" ")).collect()
+    }
+
+    /// Every pattern found so far, most frequent first.
+    pub fn patterns(&self) -> Vec<Pattern> {
+        let mut patterns: Vec<Pattern> = self
+            .clusters
+            .iter()
+            .map(|cluster| Pattern {
+                template: cluster.tokens.join(" "),
+                count: cluster.count,
+                example: cluster.example.clone(),
+            })
+            .collect();
+        patterns.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.template.cmp(&b.template)));
+        patterns
+    }
+}
+
+/// Tokens containing a digit are almost always variables (ids, numbers, addresses).
+fn mask(token: &str) -> String {
+    if token.chars().any(|c| c.is_ascii_digit()) {
+        WILDCARD.to_string()
+    } else {
+        token.to_string()
+    }
+}
+
+/// Share of positions where the template and the message agree, ignoring wildcards.
+fn similarity(template: &[String], tokens: &[String]) -> f64 {
+    if template.is_empty() {
+        return 1.0;
+    }
+    let same = template.iter().zip(tokens).filter(|(t, m)| t == m && t.as_str() != WILDCARD).count();
+    same as f64 / template.len() as f64
+}
@@ -0,0 +1,514 @@
+// src/pipeline.rs
+
+use crate::aggregate::distinct::DistinctCount;
+use crate::aggregate::group::GroupBy;
+use crate::aggregate::histogram::Histogram;
+use crate::aggregate::top_k::TopK;
+use crate::aggregate::Aggregator;
+use crate::parsers::ansi::{self, AnsiMode};
+use crate::parsers::detect::FormatDetector;
+use crate::parsers::lazy::{self, FieldSet};
+use crate::parsers::{self, LogEntry};
+use crate::patterns::PatternMiner;
+use crate::query::{Query, QueryError, QueryOptions};
+use crate::schema::SchemaInference;
+use crate::transform;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+
+/// A parsed line travelling through a [`Pipeline`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// 1-based position of the line in the input.
+    pub line_number: usize,
+    pub entry: LogEntry,
+    /// Whether the record matched the query or is context around a match.
+    pub mark: Mark,
+}
+
+/// Distinguishes hits from the context lines emitted around them (`grep -C`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mark {
+    #[default]
+    Match,
+    Context,
+}
+
+impl Mark {
+    /// The separator grep prints after the line number: `:` for hits, `-` for context.
+    pub fn separator(&self) -> char {
+        match self {
+            Mark::Match => ':',
+            Mark::Context => '-',
+        }
+    }
+}
+
+impl Record {
+    /// The structured value, or `Value::Null` for unstructured lines.
+    pub fn value(&self) -> &Value {
+        static NULL: Value = Value::Null;
+        self.entry.value().unwrap_or(&NULL)
+    }
+
+    /// The line as it was read.
+    pub fn raw(&self) -> &str {
+        self.entry.raw()
+    }
+}
+
+/// A stage that rewrites, drops, or buffers records.
+pub trait Transform: Send {
+    /// Processes one record, pushing zero or more records to `out`.
+    fn process(&mut self, record: Record, out: &mut Vec<Record>);
+
+    /// Called once the input is exhausted, to release anything still buffered.
+    fn finish(&mut self, _out: &mut Vec<Record>) {}
+}
+
+/// Boxed transforms, for stages chosen at runtime.
+impl Transform for Box<dyn Transform> {
+    fn process(&mut self, record: Record, out: &mut Vec<Record>) {
+        (**self).process(record, out);
+    }
+
+    fn finish(&mut self, out: &mut Vec<Record>) {
+        (**self).finish(out);
+    }
+}
+
+/// Any `FnMut(Record) -> Option<Record>` is a transform; returning `None` drops the record.
+impl<F> Transform for F
+where
+    F: FnMut(Record) -> Option<Record> + Send,
+{
+    fn process(&mut self, record: Record, out: &mut Vec<Record>) {
+        if let Some(record) = self(record) {
+            out.push(record);
+        }
+    }
+}
+
+/// An aggregation fed with every record the pipeline emits.
+pub trait Aggregation: Send {
+    fn observe(&mut self, record: &Record);
+
+    /// A JSON snapshot of the current result.
+    fn result(&self) -> Value;
+}
+
+impl Aggregation for Aggregator {
+    fn observe(&mut self, record: &Record) {
+        Aggregator::observe(self, record.value());
+    }
+
+    fn result(&self) -> Value {
+        serde_json::to_value(self.stats()).unwrap_or_default()
+    }
+}
+
+impl Aggregation for GroupBy {
+    fn observe(&mut self, record: &Record) {
+        GroupBy::observe(self, record.value());
+    }
+
+    fn result(&self) -> Value {
+        serde_json::to_value(self.stats()).unwrap_or_default()
+    }
+}
+
+impl Aggregation for Histogram {
+    fn observe(&mut self, record: &Record) {
+        Histogram::observe(self, record.value());
+    }
+
+    fn result(&self) -> Value {
+        serde_json::to_value(Histogram::result(self)).unwrap_or_default()
+    }
+}
+
+impl Aggregation for TopK {
+    fn observe(&mut self, record: &Record) {
+        TopK::observe(self, record.value());
+    }
+
+    fn result(&self) -> Value {
+        serde_json::to_value(TopK::result(self)).unwrap_or_default()
+    }
+}
+
+impl Aggregation for DistinctCount {
+    fn observe(&mut self, record: &Record) {
+        DistinctCount::observe(self, record.value());
+    }
+
+    fn result(&self) -> Value {
+        serde_json::to_value(DistinctCount::result(self)).unwrap_or_default()
+    }
+}
+
+impl Aggregation for PatternMiner {
+    fn observe(&mut self, record: &Record) {
+        self.add(transform::message(record));
+    }
+
+    fn result(&self) -> Value {
+        serde_json::to_value(self.patterns()).unwrap_or_default()
+    }
+}
+
+impl Aggregation for SchemaInference {
+    fn observe(&mut self, record: &Record) {
+        SchemaInference::observe(self, record.value());
+    }
+
+    fn result(&self) -> Value {
+        serde_json::to_value(SchemaInference::result(self)).unwrap_or_default()
+    }
+}
+
+/// Running totals for a pipeline.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PipelineStats {
+    pub lines: usize,
+    pub matched: usize,
+    pub emitted: usize,
+}
+
+/// Composes a parser, a compiled query, transforms and aggregations into a
+/// single line-in, records-out processor.
+///
+/// Each line is parsed, passed through the pre-filter transforms, tested
+/// against the query, passed through the post-filter stages, and finally
+/// observed by every aggregation.
+pub struct Pipeline {
+    parser: Box<dyn Fn(&str) -> LogEntry + Send>,
+    custom_parser: bool,
+    detector: Option<FormatDetector>,
+    ansi: AnsiMode,
+    source: Option<String>,
+    query: Option<Query>,
+    options: QueryOptions,
+    transforms: Vec<Box<dyn Transform>>,
+    post_filter: Vec<Box<dyn Transform>>,
+    aggregations: Vec<Box<dyn Aggregation>>,
+    context: Option<ContextWindow>,
+    lazy_json: bool,
+    /// What the query reads, worked out on the first line lazy filtering applies to.
+    lazy_fields: Option<FieldSet>,
+    stats: PipelineStats,
+}
+
+/// Lines kept around matches: a ring buffer of recent misses, and a countdown after a hit.
+struct ContextWindow {
+    before: usize,
+    after: usize,
+    recent: VecDeque<Record>,
+    after_remaining: usize,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Pipeline::new()
+    }
+}
+
+impl Pipeline {
+    /// A pipeline that auto-detects each line's format and matches everything.
+    pub fn new() -> Self {
+        Pipeline {
+            parser: Box::new(parsers::parse_log_line),
+            custom_parser: false,
+            detector: None,
+            ansi: AnsiMode::Keep,
+            source: None,
+            query: None,
+            options: QueryOptions::default(),
+            transforms: Vec::new(),
+            post_filter: Vec::new(),
+            aggregations: Vec::new(),
+            context: None,
+            lazy_json: false,
+            lazy_fields: None,
+            stats: PipelineStats::default(),
+        }
+    }
+
+    /// Replaces the default auto-detecting parser.
+    pub fn with_parser(mut self, parser: impl Fn(&str) -> LogEntry + Send + 'static) -> Self {
+        self.parser = Box::new(parser);
+        self.custom_parser = true;
+        self
+    }
+
+    /// Sniffs the input's format from its first lines and then parses with that format
+    /// alone (see [`FormatDetector`]). Takes precedence over [`with_parser`](Self::with_parser).
+    pub fn with_detector(mut self, detector: FormatDetector) -> Self {
+        self.detector = Some(detector);
+        self
+    }
+
+    /// Handles terminal escape sequences before parsing. With [`AnsiMode::Strip`] or
+    /// [`AnsiMode::Hint`], `raw` holds the stripped line.
+    pub fn with_ansi(mut self, mode: AnsiMode) -> Self {
+        self.ansi = mode;
+        self
+    }
+
+    /// Stamps every entry with the name of its source (a file path, a host, ...).
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    pub fn with_query(mut self, query: Query) -> Self {
+        self.query = Some(query);
+        self
+    }
+
+    pub fn with_options(mut self, options: QueryOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Adds a transform that runs before the query, so it can add fields the query uses.
+    pub fn with_transform(mut self, transform: impl Transform + 'static) -> Self {
+        self.transforms.push(Box::new(transform));
+        self
+    }
+
+    /// Adds a stage that runs on matching records only (dedup, sampling, projection, ...).
+    pub fn with_post_filter(mut self, stage: impl Transform + 'static) -> Self {
+        self.post_filter.push(Box::new(stage));
+        self
+    }
+
+    pub fn with_aggregation(mut self, aggregation: impl Aggregation + 'static) -> Self {
+        self.aggregations.push(Box::new(aggregation));
+        self
+    }
+
+    /// Also emits up to `before` lines preceding and `after` lines following each match,
+    /// marked [`Mark::Context`]. Overlapping windows are merged; a jump in `line_number`
+    /// marks the boundary between two groups. Context lines skip the aggregations.
+    pub fn with_context(mut self, before: usize, after: usize) -> Self {
+        self.context = Some(ContextWindow {
+            before,
+            after,
+            recent: VecDeque::with_capacity(before),
+            after_remaining: 0,
+        });
+        self
+    }
+
+    /// Tests JSON object lines against the query before parsing them, decoding only the
+    /// fields it reads (see [`lazy::matches`]). Misses are dropped without building an
+    /// entry; hits are parsed in full as usual. Only used with the default parser, and
+    /// not when pre-filter transforms, [`AnsiMode::Hint`] or context lines need every
+    /// line parsed.
+    pub fn with_lazy_json(mut self) -> Self {
+        self.lazy_json = true;
+        self
+    }
+
+    pub fn query(&self) -> Option<&Query> {
+        self.query.as_ref()
+    }
+
+    pub fn aggregations(&self) -> &[Box<dyn Aggregation>] {
+        &self.aggregations
+    }
+
+    pub fn stats(&self) -> PipelineStats {
+        self.stats
+    }
+
+    /// Feeds one line, appending every record it releases to `out`.
+    pub fn process_line(&mut self, line: &str, out: &mut Vec<Record>) -> Result<(), QueryError> {
+        self.stats.lines += 1;
+        let hint = match self.ansi {
+            AnsiMode::Hint => ansi::color_level(line),
+            _ => None,
+        };
+        let line = match self.ansi {
+            AnsiMode::Keep => line.into(),
+            AnsiMode::Strip | AnsiMode::Hint => ansi::strip_ansi(line),
+        };
+        if let (true, Some(query)) = (self.lazy_applies(), &self.query) {
+            let fields = self.lazy_fields.get_or_insert_with(|| FieldSet::for_query(query, &self.options));
+            if let Some(is_match) = lazy::matches(&line, query, fields, &self.options) {
+                if !is_match? {
+                    return Ok(());
+                }
+            }
+        }
+        let entry = match &mut self.detector {
+            Some(detector) => detector.parse(&line),
+            None => (self.parser)(&line),
+        };
+        let mut entry = entry.with_line_number(self.stats.lines);
+        if let Some(source) = &self.source {
+            entry = entry.with_source(source.as_str());
+        }
+        let mut record = Record { line_number: self.stats.lines, entry, mark: Mark::Match };
+        if let Some(level) = hint {
+            let field = (ansi::COLOR_LEVEL_FIELD.to_string(), Value::from(level.as_str()));
+            transform::insert_fields(&mut record, [field]);
+        }
+
+        let candidates = run_stages(&mut self.transforms, vec![record]);
+        let matched = self.filter(candidates)?;
+        let released = run_stages(&mut self.post_filter, matched);
+        self.emit(released, out);
+        Ok(())
+    }
+
+    /// Whether a line's match can be decided before it is parsed.
+    fn lazy_applies(&self) -> bool {
+        self.lazy_json
+            && !self.custom_parser
+            && self.detector.is_none()
+            && self.ansi != AnsiMode::Hint
+            && self.transforms.is_empty()
+            && self.context.is_none()
+    }
+
+    /// Flushes buffered records once the input is exhausted.
+    pub fn finish(&mut self, out: &mut Vec<Record>) -> Result<(), QueryError> {
+        // Records flushed by a pre-filter transform still have to pass the query.
+        let flushed = finish_stages(&mut self.transforms);
+        let matched = self.filter(flushed)?;
+
+        let mut released = run_stages(&mut self.post_filter, matched);
+        released.extend(finish_stages(&mut self.post_filter));
+        self.emit(released, out);
+        Ok(())
+    }
+
+    fn filter(&mut self, records: Vec<Record>) -> Result<Vec<Record>, QueryError> {
+        let mut matched = Vec::with_capacity(records.len());
+        for record in records {
+            let is_match = match &self.query {
+                Some(query) => record.entry.matches_with_options(query, &self.options)?,
+                None => true,
+            };
+            if is_match {
+                self.stats.matched += 1;
+            }
+            match &mut self.context {
+                Some(context) => context.push(record, is_match, &mut matched),
+                None if is_match => matched.push(record),
+                None => {}
+            }
+        }
+        Ok(matched)
+    }
+
+    fn emit(&mut self, records: Vec<Record>, out: &mut Vec<Record>) {
+        for record in records.iter().filter(|record| record.mark == Mark::Match) {
+            for aggregation in &mut self.aggregations {
+                aggregation.observe(record);
+            }
+        }
+        self.stats.emitted += records.len();
+        out.extend(records);
+    }
+
+    /// Runs every line through the pipeline, yielding records as they are released.
+    /// Buffered records are flushed when the input ends.
+    pub fn run<I>(&mut self, lines: I) -> Run<'_, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        Run { pipeline: self, lines: lines.into_iter(), ready: VecDeque::new(), finished: false }
+    }
+}
+
+impl ContextWindow {
+    fn push(&mut self, mut record: Record, is_match: bool, out: &mut Vec<Record>) {
+        if is_match {
+            out.extend(self.recent.drain(..).map(|mut r| {
+                r.mark = Mark::Context;
+                r
+            }));
+            out.push(record);
+            self.after_remaining = self.after;
+        } else if self.after_remaining > 0 {
+            self.after_remaining -= 1;
+            record.mark = Mark::Context;
+            out.push(record);
+        } else if self.before > 0 {
+            if self.recent.len() == self.before {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(record);
+        }
+    }
+}
+
+/// Flushes each stage in order, running what it releases through the stages after it.
+fn finish_stages(stages: &mut [Box<dyn Transform>]) -> Vec<Record> {
+    let mut released = Vec::new();
+    for i in 0..stages.len() {
+        let mut flushed = Vec::new();
+        stages[i].finish(&mut flushed);
+        released.extend(run_stages(&mut stages[i + 1..], flushed));
+    }
+    released
+}
+
+fn run_stages(stages: &mut [Box<dyn Transform>], mut records: Vec<Record>) -> Vec<Record> {
+    for stage in stages {
+        let mut next = Vec::with_capacity(records.len());
+        for record in records {
+            stage.process(record, &mut next);
+        }
+        records = next;
+    }
+    records
+}
+
+/// Iterator returned by [`Pipeline::run`].
+pub struct Run<'a, I> {
+    pipeline: &'a mut Pipeline,
+    lines: I,
+    ready: VecDeque<Record>,
+    finished: bool,
+}
+
+impl<I> Iterator for Run<'_, I>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    type Item = Result<Record, QueryError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut out = Vec::new();
+        loop {
+            if let Some(record) = self.ready.pop_front() {
+                return Some(Ok(record));
+            }
+            if self.finished {
+                return None;
+            }
+            match self.lines.next() {
+                Some(line) => {
+                    if let Err(e) = self.pipeline.process_line(line.as_ref(), &mut out) {
+                        return Some(Err(e));
+                    }
+                }
+                None => {
+                    self.finished = true;
+                    if let Err(e) = self.pipeline.finish(&mut out) {
+                        return Some(Err(e));
+                    }
+                }
+            }
+            self.ready.extend(out.drain(..));
+        }
+    }
+}
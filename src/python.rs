@@ -0,0 +1,159 @@
+// src/python.rs
+
+// pyo3 0.22's #[pyfunction]/#[pymethods] expansion wraps `PyResult` returns in an `.into()`
+// that clippy flags on every such function.
+#![allow(clippy::useless_conversion)]
+
+use crate::parsers;
+use crate::pipeline::{Pipeline, Record};
+use crate::query::{Query, QueryError};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use serde_json::Value;
+
+fn py_err(e: QueryError) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+/// Converts JSON into native Python objects (`dict`, `list`, `int`, `float`, `str`, `None`).
+fn to_py(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    Ok(match value {
+        Value::Null => py.None(),
+        Value::Bool(b) => b.to_object(py),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => i.to_object(py),
+            (None, Some(u)) => u.to_object(py),
+            (None, None) => n.as_f64().unwrap_or(f64::NAN).to_object(py),
+        },
+        Value::String(s) => s.to_object(py),
+        Value::Array(items) => {
+            let list = PyList::empty_bound(py);
+            for item in items {
+                list.append(to_py(py, item)?)?;
+            }
+            list.into_py(py)
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new_bound(py);
+            for (key, item) in map {
+                dict.set_item(key, to_py(py, item)?)?;
+            }
+            dict.into_py(py)
+        }
+    })
+}
+
+fn records_to_py(py: Python<'_>, records: &[Record]) -> PyResult<PyObject> {
+    let list = PyList::empty_bound(py);
+    for record in records {
+        let value = serde_json::to_value(record).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        list.append(to_py(py, &value)?)?;
+    }
+    Ok(list.into_py(py))
+}
+
+/// `parse_line(line) -> dict` with `format`, `parsed` (None for plain text) and
+/// `timestamp` (RFC 3339 or None).
+#[pyfunction]
+fn parse_line(py: Python<'_>, line: &str) -> PyResult<PyObject> {
+    let entry = parsers::parse_log_line(line);
+    let dict = PyDict::new_bound(py);
+    dict.set_item("format", entry.format().as_str())?;
+    dict.set_item("timestamp", entry.timestamp().map(|ts| ts.to_rfc3339()))?;
+    dict.set_item("parsed", to_py(py, entry.value().unwrap_or(&Value::Null))?)?;
+    Ok(dict.into_py(py))
+}
+
+/// A compiled query: `Query("status >= 500").matches(line)`.
+#[pyclass(name = "Query")]
+struct PyQuery {
+    query: Query,
+}
+
+#[pymethods]
+impl PyQuery {
+    /// Raises `ValueError` on invalid syntax.
+    #[new]
+    fn new(query: &str) -> PyResult<Self> {
+        Query::parse(query).map(|query| PyQuery { query }).map_err(py_err)
+    }
+
+    /// Parses `line` and reports whether it matches.
+    fn matches(&self, line: &str) -> PyResult<bool> {
+        parsers::parse_log_line(line).matches(&self.query).map_err(py_err)
+    }
+
+    /// Indices (0-based) of the matching lines.
+    fn filter(&self, lines: Vec<String>) -> PyResult<Vec<usize>> {
+        let mut matches = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            if parsers::parse_log_line(line).matches(&self.query).map_err(py_err)? {
+                matches.push(index);
+            }
+        }
+        Ok(matches)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Query({:?})", self.query.expr)
+    }
+}
+
+/// A filtering [`Pipeline`]: lines in, matching records (as dicts) out, with optional
+/// grep-style context lines.
+#[pyclass(name = "Pipeline")]
+struct PyPipeline {
+    pipeline: Pipeline,
+}
+
+#[pymethods]
+impl PyPipeline {
+    #[new]
+    #[pyo3(signature = (query=None, before=0, after=0))]
+    fn new(query: Option<&str>, before: usize, after: usize) -> PyResult<Self> {
+        let mut pipeline = Pipeline::new();
+        if let Some(query) = query {
+            pipeline = pipeline.with_query(Query::parse(query).map_err(py_err)?);
+        }
+        if before > 0 || after > 0 {
+            pipeline = pipeline.with_context(before, after);
+        }
+        Ok(PyPipeline { pipeline })
+    }
+
+    /// Feeds one line; returns the records it released (possibly none).
+    fn process(&mut self, py: Python<'_>, line: &str) -> PyResult<PyObject> {
+        let mut out = Vec::new();
+        self.pipeline.process_line(line, &mut out).map_err(py_err)?;
+        records_to_py(py, &out)
+    }
+
+    /// Flushes anything still buffered at the end of the input.
+    fn finish(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        let mut out = Vec::new();
+        self.pipeline.finish(&mut out).map_err(py_err)?;
+        records_to_py(py, &out)
+    }
+
+    /// Runs every line through the pipeline and returns all released records.
+    fn run(&mut self, py: Python<'_>, lines: Vec<String>) -> PyResult<PyObject> {
+        let records = self.pipeline.run(lines).collect::<Result<Vec<_>, _>>().map_err(py_err)?;
+        records_to_py(py, &records)
+    }
+
+    /// `{"lines", "matched", "emitted"}` so far.
+    #[getter]
+    fn stats(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let stats = serde_json::to_value(self.pipeline.stats()).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        to_py(py, &stats)
+    }
+}
+
+#[pymodule]
+fn loglens_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse_line, m)?)?;
+    m.add_class::<PyQuery>()?;
+    m.add_class::<PyPipeline>()?;
+    Ok(())
+}
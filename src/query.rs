@@ -1,37 +1,724 @@
 // File: src/engine.rs
 
-use crate::time as time_parser;
+use crate::fuzzy;
+use crate::level::Level;
+use crate::time::{self as time_parser, TimeOptions};
+use crate::units;
+use crate::version::Version;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::cell::{OnceCell, RefCell};
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt;
+use std::str::FromStr;
 use regex::Regex;
 use std::sync::OnceLock;
 
-const OPERATORS: &[&str] = &[
-    // Longer operators first to avoid substring matching issues
-    "!contains+", "!contains-",
-    "!between", // Range exclusion
-    "!~=", "!contains", "!exists", "isnot", ">=", "<=", "==", "!=",
-    "contains+", "contains-",
-    "between", // Range inclusion
-    "contains", "exists",
-    // Shorter operators last
-    "is", "~=", ">", "<",
+const LEVEL_KEYS: &[&str] = &["level", "severity"];
+const DURATION_HINTS: &[&str] = &["duration", "latency", "elapsed", "took", "_time"];
+/// Name suffixes that state a duration field's unit, as nanoseconds per unit.
+const DURATION_SUFFIXES: &[(&str, f64)] = &[
+    ("_ns", 1.0), ("_nanos", 1.0),
+    ("_us", 1e3), ("_micros", 1e3),
+    ("_ms", 1e6), ("_millis", 1e6),
+    ("_s", 1e9), ("_sec", 1e9), ("_secs", 1e9), ("_seconds", 1e9),
 ];
-const TIMESTAMP_KEYS: &[&str] = &["timestamp", "ts", "@timestamp"];
 
-fn get_value_by_field<'a>(val: &'a Value, field_key: &str) -> Option<&'a Value> {
+/// Every comparison operator understood by the query language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    Eq,
+    Ne,
+    Is,
+    IsNot,
+    Gt,
+    Lt,
+    Ge,
+    Le,
+    /// Case-insensitive equality (`~=`).
+    IEq,
+    NotIEq,
+    Contains,
+    NotContains,
+    /// Raw line contains a number greater than or equal to the value (`contains+`).
+    ContainsGe,
+    NotContainsGe,
+    /// Raw line contains a number less than or equal to the value (`contains-`).
+    ContainsLe,
+    NotContainsLe,
+    Between,
+    NotBetween,
+    /// Approximate match, tolerant of typos (`message fuzzy "connecton refused"`).
+    Fuzzy,
+    NotFuzzy,
+    /// Regular expression match (`path matches "^/api/v[0-9]+/"`).
+    Matches,
+    NotMatches,
+    Exists,
+    NotExists,
+    /// Field is present and JSON `null` (`is null`, `== null`).
+    IsNull,
+    /// Field is present and not JSON `null` (`is not null`, `!= null`).
+    IsNotNull,
+}
+
+impl Operator {
+    /// Matching order used by the parser.
+    /// Longer operators come first to avoid substring matching issues.
+    const PARSE_ORDER: &'static [Operator] = &[
+        Operator::NotContainsGe, Operator::NotContainsLe,
+        Operator::NotBetween, // Range exclusion
+        Operator::NotIEq, Operator::NotContains, Operator::NotExists,
+        Operator::NotFuzzy, // After the older word operators so their values may say "fuzzy"
+        Operator::NotMatches,
+        Operator::IsNot,
+        Operator::Ge, Operator::Le, Operator::Eq, Operator::Ne,
+        Operator::ContainsGe, Operator::ContainsLe,
+        Operator::Between, // Range inclusion
+        Operator::Contains, Operator::Exists,
+        Operator::Fuzzy, Operator::Matches,
+        // Shorter operators last
+        Operator::Is, Operator::IEq, Operator::Gt, Operator::Lt,
+    ];
+
+    /// The operator as written in a query string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Operator::Eq => "==",
+            Operator::Ne => "!=",
+            Operator::Is => "is",
+            Operator::IsNot => "isnot",
+            Operator::Gt => ">",
+            Operator::Lt => "<",
+            Operator::Ge => ">=",
+            Operator::Le => "<=",
+            Operator::IEq => "~=",
+            Operator::NotIEq => "!~=",
+            Operator::Contains => "contains",
+            Operator::NotContains => "!contains",
+            Operator::ContainsGe => "contains+",
+            Operator::NotContainsGe => "!contains+",
+            Operator::ContainsLe => "contains-",
+            Operator::NotContainsLe => "!contains-",
+            Operator::Between => "between",
+            Operator::NotBetween => "!between",
+            Operator::Fuzzy => "fuzzy",
+            Operator::NotFuzzy => "!fuzzy",
+            Operator::Matches => "matches",
+            Operator::NotMatches => "!matches",
+            Operator::Exists => "exists",
+            Operator::NotExists => "!exists",
+            Operator::IsNull => "is null",
+            Operator::IsNotNull => "is not null",
+        }
+    }
+
+    /// Operators spelled as a word (`is`, `fuzzy`, `!matches`, ...) only count as a whole
+    /// whitespace-delimited token, so `matches_played > 3` or `is_fuzzy > 1` compare
+    /// those fields.
+    fn is_word(self) -> bool {
+        self.as_str().trim_start_matches('!').starts_with(|c: char| c.is_ascii_alphabetic())
+    }
+
+    /// Where the operator first occurs in `text`.
+    fn position_in(self, text: &str) -> Option<usize> {
+        let token = self.as_str();
+        if !self.is_word() {
+            return text.find(token);
+        }
+        text.match_indices(token).map(|(at, _)| at).find(|&at| {
+            let before = text[..at].chars().next_back();
+            let after = text[at + token.len()..].chars().next();
+            before.is_none_or(char::is_whitespace) && after.is_none_or(char::is_whitespace)
+        })
+    }
+
+    /// Finds the first operator contained in `text`, in parse order, and where it starts.
+    fn find_in(text: &str) -> Option<(Operator, usize)> {
+        Self::PARSE_ORDER.iter().find_map(|op| Some((*op, op.position_in(text)?)))
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Optional wrapper applied to a field name, e.g. `num(latency)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Modifier {
+    /// Coerce string values to numbers before comparing.
+    Num,
+    /// Treat the field as a duration so literals like `1.5s` are converted to its unit.
+    Dur,
+    /// Compare both sides as semantic versions (`semver(version) >= 2.3`).
+    Semver,
+    /// Give any field timestamp semantics (`time(created_at) > 1h ago`).
+    Time,
+    /// Count the keys matching a wildcard pattern (`count(*) > 10`, `count(http.*) >= 3`).
+    Count,
+    /// Time from the first timestamp field to the second (`age(started_at, finished_at) > 5s`).
+    Age,
+    /// Time from a timestamp field to the query's now (`since(timestamp) < 10m`).
+    Since,
+}
+
+/// The left-hand side of a condition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Field {
+    /// A top-level key, or a JSON pointer when it starts with `/`.
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modifier: Option<Modifier>,
+}
+
+impl Field {
+    fn parse(raw: &str) -> Self {
+        for (prefix, modifier) in [
+            ("num(", Modifier::Num),
+            ("dur(", Modifier::Dur),
+            ("semver(", Modifier::Semver),
+            ("time(", Modifier::Time),
+            ("count(", Modifier::Count),
+            ("age(", Modifier::Age),
+            ("since(", Modifier::Since),
+        ] {
+            if raw.starts_with(prefix) && raw.ends_with(')') {
+                let name = raw[prefix.len()..raw.len()-1].trim().to_string();
+                return Field { name, modifier: Some(modifier) };
+            }
+        }
+        Field { name: raw.to_string(), modifier: None }
+    }
+
+    /// Nanoseconds per unit when the field holds a duration.
+    ///
+    /// A unit suffix in the name wins (`_ms`, `_us`, `_s`, ...). Otherwise nginx-style
+    /// `*_time` fields are seconds, and other latency-like names or `dur()` default
+    /// to milliseconds. `age()` and `since()` measure in seconds.
+    fn duration_unit(&self) -> Option<f64> {
+        if matches!(self.modifier, Some(Modifier::Age | Modifier::Since)) {
+            return Some(1e9);
+        }
+        let name = self.name.to_ascii_lowercase();
+        let latency_like = self.modifier == Some(Modifier::Dur)
+            || DURATION_HINTS.iter().any(|hint| name.contains(hint));
+        if !latency_like {
+            return None;
+        }
+
+        if let Some((_, unit)) = DURATION_SUFFIXES.iter().find(|(suffix, _)| name.ends_with(suffix)) {
+            return Some(*unit);
+        }
+        if name.ends_with("_time") { Some(1e9) } else { Some(1e6) }
+    }
+}
+
+/// A single `field op value` comparison.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    pub field: Field,
+    pub op: Operator,
+    /// The right-hand side as written (quotes included). Empty for `exists`.
+    #[serde(default)]
+    pub value: String,
+    /// Compiled pattern for regex conditions; rebuilt on first use after deserializing.
+    #[serde(skip)]
+    regex: RegexCache,
+    /// Time literals resolved against the query's `now`; filled on first use.
+    #[serde(skip)]
+    times: TimeCache,
+    /// Lowercased `text contains` terms; filled on first use.
+    #[serde(skip)]
+    terms: TermCache,
+}
+
+impl Condition {
+    pub fn new(field: Field, op: Operator, value: impl Into<String>) -> Self {
+        Condition {
+            field,
+            op,
+            value: value.into(),
+            regex: RegexCache::default(),
+            times: TimeCache::default(),
+            terms: TermCache::default(),
+        }
+    }
+
+    /// Compiles a regex right-hand side now, so syntax errors surface at parse time and
+    /// the pattern is cached.
+    pub(crate) fn checked(self) -> Result<Self, QueryError> {
+        if self.is_regex() {
+            self.regex()?;
+        }
+        Ok(self)
+    }
+
+    /// True when the right-hand side is a regular expression: `matches`, or `~=` on `text`.
+    fn is_regex(&self) -> bool {
+        matches!(self.op, Operator::Matches | Operator::NotMatches)
+            || (self.field.name == "text" && matches!(self.op, Operator::IEq | Operator::NotIEq))
+    }
+
+    fn regex(&self) -> Result<&Regex, QueryError> {
+        if let Some(re) = self.regex.0.get() {
+            return Ok(re);
+        }
+        let re = Regex::new(unquote(&self.value))
+            .map_err(|e| QueryError::InvalidFormat(format!("Invalid regex '{}': {}", self.value, e)))?;
+        Ok(self.regex.0.get_or_init(|| re))
+    }
+
+    /// The right-hand side read as times for this field: both ends of a `between`
+    /// range, otherwise the single literal. Relative literals (`15m ago`) resolve
+    /// against `now` once, so every entry is compared with the same instant; the
+    /// result is cached for the first `options` it is read with.
+    pub(crate) fn times(&self, now: DateTime<Utc>, options: &TimeOptions) -> Cow<'_, [TimeLiteral]> {
+        let (cached_for, times) = self.times.0.get_or_init(|| (options.clone(), self.read_times(now, options)));
+        if cached_for == options {
+            Cow::Borrowed(times)
+        } else {
+            Cow::Owned(self.read_times(now, options))
+        }
+    }
+
+    /// True for an ordering against a relative or calendar literal (`> 1d ago`,
+    /// `between yesterday..today`), which only makes sense as a time comparison.
+    fn compares_relative_time(&self) -> bool {
+        let literals = match self.op {
+            Operator::Gt | Operator::Lt | Operator::Ge | Operator::Le => vec![unquote(&self.value)],
+            Operator::Between | Operator::NotBetween => {
+                split_range(&self.value).map(|(start, end)| vec![start, end]).unwrap_or_default()
+            }
+            _ => return false,
+        };
+        literals.into_iter().any(time_parser::is_relative_time)
+    }
+
+    fn read_times(&self, now: DateTime<Utc>, options: &TimeOptions) -> Vec<TimeLiteral> {
+        let literals = match self.op {
+            Operator::Between | Operator::NotBetween => {
+                split_range(&self.value).map(|(start, end)| vec![start, end]).unwrap_or_default()
+            }
+            _ => vec![self.value.trim().trim_matches(|c| c == '"' || c == '\'')],
+        };
+        literals
+            .into_iter()
+            .map(|literal| time_parser::parse_time_string_for(&self.field.name, literal, now, options))
+            .collect()
+    }
+}
+
+/// Lazily compiled regex. Ignored by equality and serialization, since it is derived
+/// entirely from the condition's value.
+#[derive(Debug, Clone, Default)]
+struct RegexCache(OnceLock<Regex>);
+
+impl PartialEq for RegexCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Lazily resolved time literals, ignored like [`RegexCache`].
+#[derive(Debug, Clone, Default)]
+struct TimeCache(OnceLock<(TimeOptions, Vec<TimeLiteral>)>);
+
+/// A time literal, or why it couldn't be read.
+type TimeLiteral = Result<DateTime<Utc>, String>;
+
+impl PartialEq for TimeCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Lowercased search terms, derived from the query text on first use so matching doesn't
+/// lowercase them for every line. Ignored by equality and serialization.
+#[derive(Debug, Clone, Default)]
+struct TermCache(OnceLock<Vec<String>>);
+
+impl TermCache {
+    fn get_or_init(&self, terms: impl FnOnce() -> Vec<String>) -> &[String] {
+        self.0.get_or_init(terms)
+    }
+}
+
+impl PartialEq for TermCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Strips one pair of matching surrounding quotes, leaving the inside untouched.
+pub(crate) fn unquote(s: &str) -> &str {
+    let s = s.trim();
+    for quote in ['"', '\'', '/'] {
+        if s.len() >= 2 && s.starts_with(quote) && s.ends_with(quote) {
+            return &s[1..s.len() - 1];
+        }
+    }
+    s
+}
+
+/// The query abstract syntax tree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expr {
+    /// Matches every entry (empty query).
+    All,
+    /// Case-insensitive substring search over the raw line.
+    Text(TextSearch),
+    /// Matches if any clause matches.
+    Or(Vec<Expr>),
+    /// Matches if every clause matches.
+    And(Vec<Expr>),
+    /// Negates a grouped expression (`not (...)`, `!(...)`).
+    Not(Box<Expr>),
+    Condition(Condition),
+}
+
+impl Expr {
+    /// A case-insensitive search for `term` in the raw line, or its absence with `negate`.
+    pub fn text(term: impl Into<String>, negate: bool) -> Self {
+        Expr::Text(TextSearch::new(term, negate))
+    }
+}
+
+/// A bare search term, matched case-insensitively anywhere in the raw line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextSearch {
+    pub term: String,
+    /// Matches lines that don't contain `term` instead.
+    pub negate: bool,
+    /// `term` lowercased; filled on first use.
+    #[serde(skip)]
+    folded: TermCache,
+}
+
+impl TextSearch {
+    pub fn new(term: impl Into<String>, negate: bool) -> Self {
+        TextSearch { term: term.into(), negate, folded: TermCache::default() }
+    }
+
+    fn matches(&self, raw_line: &str) -> bool {
+        let needle = &self.folded.get_or_init(|| vec![self.term.to_lowercase()])[0];
+        contains_folded(raw_line, needle) != self.negate
+    }
+}
+
+/// A parsed query that can be evaluated repeatedly without reparsing.
+///
+/// The AST is serde-serializable so saved searches can be stored as JSON
+/// and reconstructed later.
+///
+/// Relative times (`15m ago`, `today`) are measured from the query's `now`, captured
+/// when it is parsed (or deserialized), so the window doesn't drift while a large file
+/// is scanned. Pin it with [`Query::parse_at`] or [`Query::with_now`] for reproducible runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Query {
+    pub expr: Expr,
+    #[serde(skip)]
+    now: Now,
+}
+
+impl Query {
+    /// Parses a query string into its AST.
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        Query::parse_at(query, Utc::now())
+    }
+
+    /// Parses a query whose relative times are measured from `now`.
+    pub fn parse_at(query: &str, now: DateTime<Utc>) -> Result<Self, QueryError> {
+        Ok(Query { expr: parse_expr(query)?, now: Now(now) })
+    }
+
+    /// Wraps an AST built elsewhere, e.g. translated from another query language.
+    pub fn from_expr(expr: Expr) -> Self {
+        Query { expr, now: Now::default() }
+    }
+
+    /// Re-anchors relative times to `now`.
+    pub fn with_now(mut self, now: DateTime<Utc>) -> Self {
+        fn reset(expr: &mut Expr) {
+            match expr {
+                Expr::Or(clauses) | Expr::And(clauses) => clauses.iter_mut().for_each(reset),
+                Expr::Not(inner) => reset(inner),
+                Expr::Condition(condition) => condition.times = TimeCache::default(),
+                Expr::All | Expr::Text(_) => {}
+            }
+        }
+        reset(&mut self.expr);
+        self.now = Now(now);
+        self
+    }
+
+    /// The instant relative times are measured from.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.now.0
+    }
+
+    /// Evaluates the query against a structured entry and its raw line.
+    pub fn matches(&self, value: &Value, raw_line: &str) -> Result<bool, QueryError> {
+        self.matches_with_options(value, raw_line, QueryOptions::default_ref())
+    }
+
+    /// Evaluates the query using custom [`QueryOptions`].
+    pub fn matches_with_options(
+        &self,
+        value: &Value,
+        raw_line: &str,
+        options: &QueryOptions,
+    ) -> Result<bool, QueryError> {
+        evaluate_expr(&self.expr, value, raw_line, &Scope::new(options, self.now.0))
+    }
+
+    /// The fields the query reads (both of `age(a, b)`), without the raw-line `text`
+    /// pseudo-field. `None` when a wildcard (`http.* exists`, `count(*)`) lets it read
+    /// any field.
+    pub fn fields(&self) -> Option<Vec<&str>> {
+        fn walk<'a>(expr: &'a Expr, fields: &mut Vec<&'a str>) -> Option<()> {
+            match expr {
+                Expr::All | Expr::Text(_) => {}
+                Expr::Or(clauses) | Expr::And(clauses) => clauses.iter().try_for_each(|clause| walk(clause, fields))?,
+                Expr::Not(inner) => walk(inner, fields)?,
+                Expr::Condition(condition) => {
+                    let field = &condition.field;
+                    if field.modifier == Some(Modifier::Count) || field.name.contains('*') {
+                        return None;
+                    }
+                    let names = match field.modifier {
+                        Some(Modifier::Age) => field.name.split(',').map(str::trim).collect(),
+                        _ => vec![field.name.as_str()],
+                    };
+                    for name in names {
+                        if name != "text" && !fields.contains(&name) {
+                            fields.push(name);
+                        }
+                    }
+                }
+            }
+            Some(())
+        }
+
+        let mut fields = Vec::new();
+        walk(&self.expr, &mut fields)?;
+        Some(fields)
+    }
+}
+
+/// State for evaluating one entry.
+struct Scope<'a> {
+    options: &'a QueryOptions,
+    now: DateTime<Utc>,
+    /// Timestamps already parsed from this entry, by field, so conditions sharing a
+    /// field don't parse the same string again.
+    field_times: RefCell<HashMap<String, Option<DateTime<Utc>>>>,
+    /// The entry's timestamp from the configured timestamp fields.
+    fallback_time: OnceCell<Option<DateTime<Utc>>>,
+    /// `options.time`, with the query's `now` for timestamps without a year.
+    time: OnceCell<TimeOptions>,
+}
+
+impl<'a> Scope<'a> {
+    fn new(options: &'a QueryOptions, now: DateTime<Utc>) -> Self {
+        Scope {
+            options,
+            now,
+            field_times: RefCell::default(),
+            fallback_time: OnceCell::new(),
+            time: OnceCell::new(),
+        }
+    }
+
+    fn time(&self) -> &TimeOptions {
+        self.time.get_or_init(|| {
+            let now = self.options.time.now.or(Some(self.now));
+            TimeOptions { now, ..self.options.time.clone() }
+        })
+    }
+
+    fn field_time(&self, value: &Value, field: &str) -> Option<DateTime<Utc>> {
+        if let Some(&time) = self.field_times.borrow().get(field) {
+            return time;
+        }
+        let time = self
+            .options
+            .resolve_field(value, field)
+            .and_then(|v| time_parser::parse_field_timestamp(field, v, self.time()));
+        self.field_times.borrow_mut().insert(field.to_string(), time);
+        time
+    }
+
+    fn fallback_time(&self, value: &Value) -> Option<DateTime<Utc>> {
+        *self.fallback_time.get_or_init(|| {
+            time_parser::extract_timestamp_with_keys(value, &self.options.timestamp_fields, self.time())
+        })
+    }
+}
+
+impl Query {
+    /// Where each satisfied condition matched, so a UI can highlight exactly what made
+    /// the entry match. Empty when the query doesn't match.
+    ///
+    /// Negated conditions (`!=`, `!contains`, `not (...)`) match by absence and
+    /// contribute nothing.
+    pub fn highlights(&self, value: &Value, raw_line: &str) -> Result<Vec<Highlight>, QueryError> {
+        self.highlights_with_options(value, raw_line, QueryOptions::default_ref())
+    }
+
+    /// Like [`Query::highlights`], using custom [`QueryOptions`].
+    pub fn highlights_with_options(
+        &self,
+        value: &Value,
+        raw_line: &str,
+        options: &QueryOptions,
+    ) -> Result<Vec<Highlight>, QueryError> {
+        let scope = Scope::new(options, self.now.0);
+        let mut highlights = Vec::new();
+        if evaluate_expr(&self.expr, value, raw_line, &scope)? {
+            collect_highlights(&self.expr, value, raw_line, &scope, &mut highlights)?;
+        }
+        Ok(highlights)
+    }
+}
+
+/// Where one condition of a matching query was satisfied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Highlight {
+    /// The condition as written (`status >= 500`), or the bare search term.
+    pub condition: String,
+    /// Fields that satisfied it; empty for searches over the raw line.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+    /// Byte ranges (`start..end`) in the raw line. Empty when the matched value can't be
+    /// located, e.g. a string whose JSON escaping differs from its value.
+    pub ranges: Vec<(usize, usize)>,
+}
+
+/// A query's reference time. Ignored by equality, since two otherwise identical
+/// queries parsed a moment apart should still compare equal.
+#[derive(Debug, Clone, Copy)]
+struct Now(DateTime<Utc>);
+
+impl Default for Now {
+    fn default() -> Self {
+        Now(Utc::now())
+    }
+}
+
+impl PartialEq for Now {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// Settings that tune how a query is evaluated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryOptions {
+    /// Fields that get timestamp semantics (`>`, `<`, `between` on times).
+    /// Defaults to [`time::COMMON_KEYS`](crate::time::COMMON_KEYS), the names timestamps
+    /// are extracted from; push to extend the list or assign a new one to override it.
+    pub timestamp_fields: Vec<String>,
+    /// Minimum similarity (`0.0..=1.0`) for the `fuzzy` operator to match.
+    pub fuzzy_threshold: f64,
+    /// Resolve field names ignoring ASCII case, so `level` also finds `Level` or `LEVEL`.
+    pub case_insensitive_fields: bool,
+    /// Fallback names tried in order when a field is missing,
+    /// e.g. `level -> [severity, log.level]`.
+    pub field_aliases: HashMap<String, Vec<String>>,
+    /// How timestamps in entries and time literals in the query are read: the zone of
+    /// times without an offset (also the days of `today` or `start of week`) and the
+    /// unit of numeric epochs.
+    pub time: TimeOptions,
+}
+
+impl Default for QueryOptions {
+    fn default() -> Self {
+        QueryOptions {
+            timestamp_fields: time_parser::COMMON_KEYS.iter().map(|k| k.to_string()).collect(),
+            fuzzy_threshold: 0.8,
+            case_insensitive_fields: true,
+            field_aliases: HashMap::new(),
+            time: TimeOptions::default(),
+        }
+    }
+}
+
+impl QueryOptions {
+    /// Shared default options, so plain `evaluate` calls don't allocate per line.
+    pub(crate) fn default_ref() -> &'static QueryOptions {
+        static DEFAULT: OnceLock<QueryOptions> = OnceLock::new();
+        DEFAULT.get_or_init(QueryOptions::default)
+    }
+
+    fn is_timestamp_field(&self, field: &str) -> bool {
+        self.timestamp_fields.iter().any(|k| {
+            k == field || (self.case_insensitive_fields && k.eq_ignore_ascii_case(field))
+        })
+    }
+
+    /// Looks a field up by name, then by each of its aliases.
+    fn resolve_field<'a>(&self, val: &'a Value, field_key: &str) -> Option<&'a Value> {
+        get_value_by_field(val, field_key, self.case_insensitive_fields).or_else(|| {
+            self.field_aliases
+                .get(field_key)?
+                .iter()
+                .find_map(|alias| get_value_by_field(val, alias, self.case_insensitive_fields))
+        })
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s)
+    }
+}
+
+/// Resolves a field the way queries do by default (keys, dotted paths, JSON pointers).
+pub(crate) fn resolve_field<'a>(val: &'a Value, field_key: &str) -> Option<&'a Value> {
+    QueryOptions::default_ref().resolve_field(val, field_key)
+}
+
+fn get_value_by_field<'a>(val: &'a Value, field_key: &str, case_insensitive: bool) -> Option<&'a Value> {
     if field_key.starts_with('/') {
-        val.pointer(field_key)
-    } else {
-        val.get(field_key)
+        return val.pointer(field_key);
+    }
+    if let Some(found) = get_key(val, field_key, case_insensitive) {
+        return Some(found);
+    }
+    // Dotted names also walk nested objects: `log.level` -> {"log": {"level": ..}}
+    if field_key.contains('.') {
+        return field_key
+            .split('.')
+            .try_fold(val, |current, segment| get_key(current, segment, case_insensitive));
     }
+    None
+}
+
+fn get_key<'a>(val: &'a Value, key: &str, case_insensitive: bool) -> Option<&'a Value> {
+    let map = val.as_object()?;
+    map.get(key).or_else(|| {
+        if !case_insensitive {
+            return None;
+        }
+        map.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v)
+    })
 }
 
 /// Extracts all numbers (integers, floats, negatives) from a text string.
 /// Optimized to compile the Regex only once.
 fn extract_numbers(text: &str) -> Vec<f64> {
     static NUMBER_REGEX: OnceLock<Regex> = OnceLock::new();
-    
+
     let re = NUMBER_REGEX.get_or_init(|| {
         Regex::new(r"-?\d+(\.\d+)?").expect("Invalid number regex")
     });
@@ -56,37 +743,19 @@ impl fmt::Display for QueryError {
 
 impl std::error::Error for QueryError {}
 
-fn evaluate_and_clause(value: &Value, raw_line: &str, clause: &str) -> Result<bool, QueryError> {
-    let conditions = clause.split("&&").map(|s| s.trim());
-    for condition in conditions {
-        if condition.is_empty() {
-            continue;
-        }
-        let result = evaluate_single_condition(value, raw_line, condition)?;
-        if !result {
-            return Ok(false);
-        }
-    }
-    Ok(true)
-}
+// --- Parsing ---
 
-pub fn evaluate(value: &Value, raw_line: &str, query: &str) -> Result<bool, QueryError> {
+fn parse_expr(query: &str) -> Result<Expr, QueryError> {
     if query.trim().is_empty() {
-        return Ok(true);
+        return Ok(Expr::All);
     }
-    
-    let is_structured_query = OPERATORS.iter().any(|op| query.contains(op));
+
+    let is_structured_query = Operator::find_in(query).is_some();
 
     if !is_structured_query {
-        let mut effective_query = query;
         let negate = query.starts_with('!');
-        if negate {
-            effective_query = &query[1..];
-        }
-        let matches = raw_line
-            .to_lowercase()
-            .contains(&effective_query.to_lowercase());
-        return Ok(if negate { !matches } else { matches });
+        let term = if negate { &query[1..] } else { query };
+        return Ok(Expr::text(term, negate));
     }
 
     let normalized_query = query
@@ -95,166 +764,552 @@ pub fn evaluate(value: &Value, raw_line: &str, query: &str) -> Result<bool, Quer
         .replace(" AND ", "&&")
         .replace(" and ", "&&");
 
-    let or_clauses = normalized_query.split("||").map(|s| s.trim());
+    parse_or(&normalized_query)
+}
 
-    for or_clause in or_clauses {
+fn parse_or(query: &str) -> Result<Expr, QueryError> {
+    let mut or_clauses = Vec::new();
+    for or_clause in split_top_level(query, "||") {
         if or_clause.is_empty() {
             continue;
         }
-        if evaluate_and_clause(value, raw_line, or_clause)? {
-            return Ok(true);
+        let mut and_clauses = Vec::new();
+        for condition in split_top_level(or_clause, "&&") {
+            if condition.is_empty() {
+                continue;
+            }
+            and_clauses.push(parse_atom(condition)?);
+        }
+        or_clauses.push(Expr::And(and_clauses));
+    }
+
+    Ok(Expr::Or(or_clauses))
+}
+
+/// Parses a single condition, a parenthesised group, or a negated group.
+fn parse_atom(atom: &str) -> Result<Expr, QueryError> {
+    let negated = ["not ", "NOT ", "!"].iter().find_map(|prefix| {
+        let rest = atom.strip_prefix(prefix)?.trim_start();
+        rest.starts_with('(').then_some(rest)
+    });
+    if let Some(group) = negated {
+        if let Some(inner) = strip_outer_parens(group) {
+            return Ok(Expr::Not(Box::new(parse_or(inner)?)));
+        }
+    }
+
+    if let Some(inner) = strip_outer_parens(atom) {
+        return parse_or(inner);
+    }
+
+    Ok(Expr::Condition(parse_condition(atom)?))
+}
+
+/// Returns the inside of `( ... )` when the opening paren's match is the final character.
+fn strip_outer_parens(s: &str) -> Option<&str> {
+    if !s.starts_with('(') || !s.ends_with(')') {
+        return None;
+    }
+    let mut depth = 0usize;
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') if opens_quote(s, c) => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => {
+                depth -= 1;
+                if depth == 0 {
+                    return (i == s.len() - 1).then(|| &s[1..i]);
+                }
+            }
+            _ => {}
         }
     }
-    
-    Ok(false)
+    None
 }
 
-// --- Helper for BETWEEN operator logic ---
-fn evaluate_between(
-    log_value: &Value, 
-    range_str: &str, 
-    is_timestamp: bool
+/// Quote characters only group text when they come in pairs, so an apostrophe in an
+/// unquoted value (`msg contains don't`) doesn't swallow the rest of the query.
+fn opens_quote(s: &str, quote: char) -> bool {
+    s.matches(quote).count().is_multiple_of(2)
+}
+
+/// Splits on `separator` outside quotes and parentheses, trimming each part.
+/// Parentheses are ignored when unbalanced so stray ones in values don't swallow clauses.
+fn split_top_level<'a>(s: &'a str, separator: &str) -> Vec<&'a str> {
+    let balanced = s.matches('(').count() == s.matches(')').count();
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut start = 0;
+    let mut chars = s.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') if opens_quote(s, c) => quote = Some(c),
+            (None, '(') if balanced => depth += 1,
+            (None, ')') if balanced => depth = depth.saturating_sub(1),
+            _ if depth == 0 && s[i..].starts_with(separator) => {
+                parts.push(s[start..i].trim());
+                start = i + separator.len();
+                // Skip the rest of the separator
+                for _ in 1..separator.len() {
+                    chars.next();
+                }
+            }
+            _ => {}
+        }
+    }
+    parts.push(s[start..].trim());
+    parts
+}
+
+/// Length of a leading `name(...)` call, or 0 when the condition doesn't start with one.
+fn wrapper_len(condition: &str) -> usize {
+    let Some(open) = condition.find('(') else { return 0 };
+    if open == 0 || !condition[..open].bytes().all(|b| b.is_ascii_alphabetic()) {
+        return 0;
+    }
+    condition[open..].find(')').map_or(0, |close| open + close + 1)
+}
+
+fn parse_condition(condition: &str) -> Result<Condition, QueryError> {
+    // Operators are searched after a `modifier(...)` wrapper, so names inside it
+    // (`age(started_at, finished_at)`) aren't read as operators.
+    let wrapped = wrapper_len(condition);
+    let (op, at) = Operator::find_in(&condition[wrapped..])
+        .ok_or_else(|| QueryError::InvalidFormat(condition.to_string()))?;
+    let at = wrapped + at;
+    let parts = [condition[..at].trim(), condition[at + op.as_str().len()..].trim()];
+
+    if op == Operator::Exists || op == Operator::NotExists {
+        return Ok(Condition::new(Field::parse(parts[0]), op, String::new()));
+    }
+
+    // An unquoted `null` literal turns equality into a null check.
+    let literal = parts[1].to_ascii_lowercase();
+    let op = match (op, literal.as_str()) {
+        (Operator::Eq | Operator::Is, "null") => Operator::IsNull,
+        (Operator::Is, "not null") | (Operator::Ne | Operator::IsNot, "null") => Operator::IsNotNull,
+        _ => op,
+    };
+    if op == Operator::IsNull || op == Operator::IsNotNull {
+        return Ok(Condition::new(Field::parse(parts[0]), op, String::new()));
+    }
+
+    let condition = Condition::new(Field::parse(parts[0]), op, parts[1]);
+    if condition.field.modifier == Some(Modifier::Age) && !condition.field.name.contains(',') {
+        return Err(QueryError::InvalidFormat(format!("age() needs two fields: {}", parts[0])));
+    }
+    condition.checked()
+}
+
+// --- Evaluation ---
+
+fn evaluate_expr(
+    expr: &Expr,
+    value: &Value,
+    raw_line: &str,
+    scope: &Scope,
 ) -> Result<bool, QueryError> {
+    match expr {
+        Expr::All => Ok(true),
+        Expr::Text(search) => Ok(search.matches(raw_line)),
+        Expr::Or(clauses) => {
+            for clause in clauses {
+                if evaluate_expr(clause, value, raw_line, scope)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        Expr::And(clauses) => {
+            for clause in clauses {
+                if !evaluate_expr(clause, value, raw_line, scope)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        Expr::Not(inner) => Ok(!evaluate_expr(inner, value, raw_line, scope)?),
+        Expr::Condition(condition) => evaluate_single_condition(value, raw_line, condition, scope),
+    }
+}
+
+fn collect_highlights(
+    expr: &Expr,
+    value: &Value,
+    raw_line: &str,
+    scope: &Scope,
+    out: &mut Vec<Highlight>,
+) -> Result<(), QueryError> {
+    match expr {
+        Expr::Text(TextSearch { term, negate: false, .. }) => {
+            let ranges = find_ignore_case(raw_line, term);
+            out.push(Highlight { condition: term.clone(), fields: Vec::new(), ranges });
+        }
+        Expr::All | Expr::Text(_) | Expr::Not(_) => {}
+        Expr::Or(clauses) | Expr::And(clauses) => {
+            for clause in clauses {
+                if evaluate_expr(clause, value, raw_line, scope)? {
+                    collect_highlights(clause, value, raw_line, scope, out)?;
+                }
+            }
+        }
+        Expr::Condition(condition) => {
+            if !is_negated(condition.op) && evaluate_single_condition(value, raw_line, condition, scope)? {
+                out.push(condition_highlight(condition, value, raw_line, scope)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn is_negated(op: Operator) -> bool {
+    matches!(
+        op,
+        Operator::Ne | Operator::IsNot | Operator::NotIEq | Operator::NotContains | Operator::NotContainsGe
+            | Operator::NotContainsLe | Operator::NotBetween | Operator::NotFuzzy | Operator::NotMatches
+            | Operator::NotExists
+    )
+}
+
+fn condition_highlight(
+    condition: &Condition,
+    value: &Value,
+    raw_line: &str,
+    scope: &Scope,
+) -> Result<Highlight, QueryError> {
+    let field = condition.field.name.as_str();
+    let written = format!("{} {} {}", field, condition.op, condition.value);
+    let mut highlight = Highlight { condition: written.trim_end().to_string(), fields: Vec::new(), ranges: Vec::new() };
+
+    if field == "text" {
+        if condition.is_regex() {
+            highlight.ranges = condition.regex()?.find_iter(raw_line).map(|m| (m.start(), m.end())).collect();
+        } else if condition.op == Operator::Contains {
+            for term in condition.value.split(',').map(unquote).filter(|term| !term.is_empty()) {
+                highlight.ranges.extend(find_ignore_case(raw_line, term));
+            }
+            highlight.ranges.sort_unstable();
+        }
+        return Ok(highlight);
+    }
+    if condition.field.modifier == Some(Modifier::Count) || field.contains('*') {
+        return Ok(highlight);
+    }
+
+    let fields: Vec<&str> = match condition.field.modifier {
+        Some(Modifier::Age) => field.split(',').map(str::trim).collect(),
+        _ => vec![field],
+    };
+    for name in fields {
+        if let Some(found) = scope.options.resolve_field(value, name) {
+            highlight.ranges.extend(locate_value(raw_line, name, found));
+            highlight.fields.push(name.to_string());
+        }
+    }
+    Ok(highlight)
+}
+
+/// Byte range of a field's value in the raw line: the first occurrence after the
+/// field's key, else anywhere.
+fn locate_value(raw_line: &str, field: &str, found: &Value) -> Option<(usize, usize)> {
+    let text = match found {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        _ => return None,
+    };
+    if text.is_empty() {
+        return None;
+    }
+    let key = field.rsplit(['.', '/']).next().unwrap_or(field);
+    let after_key = find_ignore_case(raw_line, key).first().map_or(0, |&(_, end)| end);
+    raw_line[after_key..]
+        .find(&text)
+        .map(|start| after_key + start)
+        .or_else(|| raw_line.find(&text))
+        .map(|start| (start, start + text.len()))
+}
+
+/// Whether `haystack` contains the already lowercased `needle` ignoring case; the same
+/// answer as `haystack.to_lowercase().contains(needle)`, but without allocating for ASCII
+/// needles.
+fn contains_folded(haystack: &str, needle: &str) -> bool {
+    if !needle.is_ascii() {
+        return haystack.to_lowercase().contains(needle);
+    }
+    if contains_ignore_ascii_case(haystack.as_bytes(), needle.as_bytes()) {
+        return true;
+    }
+    // A few non-ASCII characters lowercase to ASCII (KELVIN SIGN to `k`, `İ` to `i̇`), so
+    // only lines holding one of them need the full Unicode lowercasing.
+    !haystack.is_ascii()
+        && haystack.chars().any(|c| !c.is_ascii() && c.to_lowercase().any(|lower| lower.is_ascii()))
+        && haystack.to_lowercase().contains(needle)
+}
+
+/// Whether `needle` occurs in `haystack` ignoring ASCII case.
+fn contains_ignore_ascii_case(haystack: &[u8], needle: &[u8]) -> bool {
+    let Some(&first) = needle.first() else { return true };
+    let (lower, upper) = (first.to_ascii_lowercase(), first.to_ascii_uppercase());
+    haystack
+        .windows(needle.len())
+        .any(|window| (window[0] == lower || window[0] == upper) && window.eq_ignore_ascii_case(needle))
+}
+
+/// Every non-overlapping case-insensitive occurrence of `needle`, as byte ranges of
+/// `haystack`. Folds case like [`contains_folded`], so whatever matches is highlighted.
+fn find_ignore_case(haystack: &str, needle: &str) -> Vec<(usize, usize)> {
+    if !haystack.is_ascii() || !needle.is_ascii() {
+        return find_folded(haystack, &needle.to_lowercase());
+    }
+    let (hay, pat) = (haystack.as_bytes(), needle.as_bytes());
+    let mut ranges = Vec::new();
+    if pat.is_empty() {
+        return ranges;
+    }
+    let mut i = 0;
+    while i + pat.len() <= hay.len() {
+        if hay[i..i + pat.len()].eq_ignore_ascii_case(pat) {
+            ranges.push((i, i + pat.len()));
+            i += pat.len();
+        } else {
+            i += 1;
+        }
+    }
+    ranges
+}
+
+/// Occurrences of a lowercased `needle` in the lowercased `haystack`, mapped back to the
+/// characters of `haystack` they were lowercased from, since lowercasing can change a
+/// character's length (`İ` becomes the three bytes of `i̇`).
+fn find_folded(haystack: &str, needle: &str) -> Vec<(usize, usize)> {
+    if needle.is_empty() {
+        return Vec::new();
+    }
+    let mut folded = String::with_capacity(haystack.len());
+    // For each byte of `folded`, the range of the character it came from.
+    let mut origin = Vec::with_capacity(haystack.len());
+    for (start, c) in haystack.char_indices() {
+        for lower in c.to_lowercase() {
+            folded.push(lower);
+        }
+        origin.resize(folded.len(), (start, start + c.len_utf8()));
+    }
+    folded.match_indices(needle).map(|(at, found)| (origin[at].0, origin[at + found.len() - 1].1)).collect()
+}
+
+pub fn evaluate(value: &Value, raw_line: &str, query: &str) -> Result<bool, QueryError> {
+    Query::parse(query)?.matches(value, raw_line)
+}
+
+/// Like [`evaluate`], but with custom [`QueryOptions`].
+pub fn evaluate_with_options(
+    value: &Value,
+    raw_line: &str,
+    query: &str,
+    options: &QueryOptions,
+) -> Result<bool, QueryError> {
+    Query::parse(query)?.matches_with_options(value, raw_line, options)
+}
+
+// --- Helpers for BETWEEN operator logic ---
+fn split_range(range_str: &str) -> Result<(&str, &str), QueryError> {
     let parts: Vec<&str> = range_str.split("..").collect();
-    
+
     if parts.len() != 2 {
         return Err(QueryError::InvalidFormat(format!(
-            "BETWEEN operator requires a range 'start..end'. Got: '{}'", 
+            "BETWEEN operator requires a range 'start..end'. Got: '{}'",
             range_str
         )));
     }
 
     let start_str = parts[0].trim().trim_matches(|c| c == '"' || c == '\'');
     let end_str = parts[1].trim().trim_matches(|c| c == '"' || c == '\'');
+    Ok((start_str, end_str))
+}
 
-    if is_timestamp {
-        let log_time = match time_parser::extract_and_parse_timestamp(log_value) {
-            Some(t) => t,
-            None => return Ok(false), 
-        };
-        
-        let t1 = time_parser::parse_time_string(start_str)
-            .map_err(|_| QueryError::InvalidFormat(format!("Invalid start time: {}", start_str)))?;
-        
-        let t2 = time_parser::parse_time_string(end_str)
-            .map_err(|_| QueryError::InvalidFormat(format!("Invalid end time: {}", end_str)))?;
+fn evaluate_time_between(
+    log_time: Option<DateTime<Utc>>,
+    range_str: &str,
+    times: &[Result<DateTime<Utc>, String>],
+) -> Result<bool, QueryError> {
+    let (start_str, end_str) = split_range(range_str)?;
 
-        // AUTO-SWAP LOGIC: Ensure we always compare Low..High
-        let (start, end) = if t1 < t2 { (t1, t2) } else { (t2, t1) };
+    let log_time = match log_time {
+        Some(t) => t,
+        None => return Ok(false),
+    };
 
-        Ok(log_time >= start && log_time <= end)
-    } else {
-        // Numeric comparison
-        if let Some(log_num) = log_value.as_f64() {
-            let n1 = start_str.parse::<f64>()
-                .map_err(|_| QueryError::InvalidFormat(format!("Invalid start number: {}", start_str)))?;
-            let n2 = end_str.parse::<f64>()
-                .map_err(|_| QueryError::InvalidFormat(format!("Invalid end number: {}", end_str)))?;
+    let t1 = times[0].clone()
+        .map_err(|_| QueryError::InvalidFormat(format!("Invalid start time: {}", start_str)))?;
 
-            let (start, end) = if n1 < n2 { (n1, n2) } else { (n2, n1) };
+    let t2 = times[1].clone()
+        .map_err(|_| QueryError::InvalidFormat(format!("Invalid end time: {}", end_str)))?;
 
-            Ok(log_num >= start && log_num <= end)
-        } else {
-            // String fallback (Lexicographical)
-             if let Some(log_s) = log_value.as_str() {
-                 Ok(log_s >= start_str && log_s <= end_str)
-             } else {
-                 Ok(false)
-             }
-        }
+    // AUTO-SWAP LOGIC: Ensure we always compare Low..High
+    let (start, end) = if t1 < t2 { (t1, t2) } else { (t2, t1) };
+
+    Ok(log_time >= start && log_time <= end)
+}
+
+fn evaluate_between(log_value: &Value, range_str: &str) -> Result<bool, QueryError> {
+    let (start_str, end_str) = split_range(range_str)?;
+
+    // Numeric comparison
+    if let Some(log_num) = log_value.as_f64() {
+        let n1 = start_str.parse::<f64>()
+            .map_err(|_| QueryError::InvalidFormat(format!("Invalid start number: {}", start_str)))?;
+        let n2 = end_str.parse::<f64>()
+            .map_err(|_| QueryError::InvalidFormat(format!("Invalid end number: {}", end_str)))?;
+
+        let (start, end) = if n1 < n2 { (n1, n2) } else { (n2, n1) };
+
+        Ok(log_num >= start && log_num <= end)
+    } else {
+        // String fallback (Lexicographical)
+         if let Some(log_s) = log_value.as_str() {
+             Ok(log_s >= start_str && log_s <= end_str)
+         } else {
+             Ok(false)
+         }
     }
 }
 
 fn compare_time_values(
-    log_entry: &Value,
-    query_time_str_raw: &str,
+    log_time: Option<DateTime<Utc>>,
+    times: &[Result<DateTime<Utc>, String>],
 ) -> Option<std::cmp::Ordering> {
-    let log_time = time_parser::extract_and_parse_timestamp(log_entry)?;
-    let query_time_str_clean = query_time_str_raw
-        .trim()
-        .trim_matches(|c| c == '"' || c == '\'');
-    let query_time = time_parser::parse_time_string(query_time_str_clean).ok()?;
-    log_time.partial_cmp(&query_time)
+    let query_time = times.first()?.as_ref().ok()?;
+    log_time?.partial_cmp(query_time)
 }
 
 fn evaluate_single_condition(
     value: &Value,
     raw_line: &str,
-    condition: &str,
+    condition: &Condition,
+    scope: &Scope,
 ) -> Result<bool, QueryError> {
-    let operator = OPERATORS.iter().find(|&&op| condition.contains(op));
-
-    if let Some(op) = operator {
-        if *op == "exists" || *op == "!exists" {
-            let field_part = condition.split(op).next().unwrap_or("").trim();
-            
-            // Handle basic num() stripping for exists check, though redundant logically
-            let field = if field_part.starts_with("num(") && field_part.ends_with(')') {
-                field_part[4..field_part.len()-1].trim()
-            } else {
-                field_part
-            };
+    let options = scope.options;
+    let op = condition.op;
+    let field = condition.field.name.as_str();
+    let force_numeric = matches!(condition.field.modifier, Some(Modifier::Num | Modifier::Dur));
+    let query_value_str = condition.value.as_str();
 
-            let field_exists = get_value_by_field(value, field).is_some();
+    // --- count(pattern): compare the number of matching keys ---
+    if condition.field.modifier == Some(Modifier::Count) {
+        let count = Value::from(count_matching_paths(value, field));
+        return match op {
+            Operator::Between => evaluate_between(&count, query_value_str),
+            Operator::NotBetween => evaluate_between(&count, query_value_str).map(|b| !b),
+            _ => ordering_matches(op, compare_values(&count, query_value_str, false)).ok_or_else(|| {
+                QueryError::InvalidFormat("count() only supports comparison and between operators.".to_string())
+            }),
+        };
+    }
 
-            return if *op == "exists" {
-                Ok(field_exists)
-            } else {
-                Ok(!field_exists)
-            };
-        }
+    // --- age(a, b) / since(ts): compare elapsed seconds against a duration ---
+    if matches!(condition.field.modifier, Some(Modifier::Age | Modifier::Since)) {
+        let elapsed = match elapsed(value, &condition.field, scope) {
+            Some(elapsed) => Value::from(elapsed.num_milliseconds() as f64 / 1e3),
+            None => return Ok(false),
+        };
+        let literal = rescale_literal(condition, query_value_str, true);
+        let literal = literal.as_deref().unwrap_or(query_value_str);
+        return match op {
+            Operator::Between => evaluate_between(&elapsed, literal),
+            Operator::NotBetween => evaluate_between(&elapsed, literal).map(|b| !b),
+            _ => ordering_matches(op, compare_values(&elapsed, literal, false)).ok_or_else(|| {
+                QueryError::InvalidFormat("age() and since() only support comparison and between operators.".into())
+            }),
+        };
+    }
 
-        let (field_raw, op_str, query_value_str) = {
-            let parts: Vec<&str> = condition.splitn(2, op).map(|s| s.trim()).collect();
-            if parts.len() < 2 {
-                return Err(QueryError::InvalidFormat(condition.to_string()));
-            }
-            (parts[0], *op, parts[1])
+    if op == Operator::Exists || op == Operator::NotExists {
+        // `http.* exists` checks for any key matching the wildcard pattern
+        let field_exists = if field.contains('*') {
+            count_matching_paths(value, field) > 0
+        } else {
+            options.resolve_field(value, field).is_some()
         };
 
-        // --- 1. Parse "num()" modifier ---
-        let (field, force_numeric) = if field_raw.starts_with("num(") && field_raw.ends_with(')') {
-            (field_raw[4..field_raw.len()-1].trim(), true)
+        return if op == Operator::Exists {
+            Ok(field_exists)
         } else {
-            (field_raw, false)
+            Ok(!field_exists)
         };
+    }
+
+    if op == Operator::IsNull || op == Operator::IsNotNull {
+        let field_value = options.resolve_field(value, field);
+        return Ok(match op {
+            Operator::IsNull => field_value.is_some_and(Value::is_null),
+            _ => field_value.is_some_and(|v| !v.is_null()),
+        });
+    }
 
-        // --- 2. Handle BETWEEN for timestamps explicitly ---
-        if TIMESTAMP_KEYS.contains(&field) {
-             if op_str == "between" {
-                 return evaluate_between(value, query_value_str, true);
-             }
-             if op_str == "!between" {
-                 return evaluate_between(value, query_value_str, true).map(|b| !b);
-             }
-        }
-
-        // --- 3. Standard Timestamp operators ---
-        if TIMESTAMP_KEYS.contains(&field) {
-            return match compare_time_values(value, query_value_str) {
-                Some(ord) => match op_str {
-                    ">" => Ok(ord == std::cmp::Ordering::Greater),
-                    "<" => Ok(ord == std::cmp::Ordering::Less),
-                    ">=" => Ok(ord != std::cmp::Ordering::Less),
-                    "<=" => Ok(ord != std::cmp::Ordering::Greater),
-                    _ => Err(QueryError::InvalidFormat(
-                        "Timestamp fields only support >, <, >=, <=, between operators.".to_string(),
-                    )),
-                },
-                None => Ok(false),
-            };
-        }
-
-        // --- 4. "text" field logic (Searching raw line) ---
-        if field == "text" {
-            let search_value_clean = query_value_str
-                .trim()
-                .trim_matches(|c| c == '"' || c == '\'');
-
-            return match op_str {
-                "contains" | "!contains" => {
-                    let lower_raw_line = raw_line.to_lowercase();
-                    let search_terms: Vec<String> = query_value_str
+    // --- 1. Handle BETWEEN for timestamps explicitly ---
+    // The named field is preferred; entries without it fall back to the other timestamp
+    // fields, unless time() pinned the comparison to this exact field. A relative literal
+    // (`uptime_since > 1d ago`) reads any other field as a time of its own.
+    let known_time_field = options.is_timestamp_field(field) || options.time.has_format(field);
+    let force_time = condition.field.modifier == Some(Modifier::Time)
+        || (!known_time_field && condition.compares_relative_time());
+    let is_timestamp_field = force_time || known_time_field;
+    let log_time = || {
+        let own = scope.field_time(value, field);
+        if force_time {
+            return own;
+        }
+        own.or_else(|| scope.fallback_time(value))
+    };
+    let times = || condition.times(scope.now, scope.time());
+    if is_timestamp_field {
+         if op == Operator::Between {
+             return evaluate_time_between(log_time(), query_value_str, &times());
+         }
+         if op == Operator::NotBetween {
+             return evaluate_time_between(log_time(), query_value_str, &times()).map(|b| !b);
+         }
+    }
+
+    // --- 2. Standard Timestamp operators ---
+    if is_timestamp_field {
+        return match compare_time_values(log_time(), &times()) {
+            Some(ord) => match op {
+                Operator::Gt => Ok(ord == std::cmp::Ordering::Greater),
+                Operator::Lt => Ok(ord == std::cmp::Ordering::Less),
+                Operator::Ge => Ok(ord != std::cmp::Ordering::Less),
+                Operator::Le => Ok(ord != std::cmp::Ordering::Greater),
+                _ => Err(QueryError::InvalidFormat(
+                    "Timestamp fields only support >, <, >=, <=, between operators.".to_string(),
+                )),
+            },
+            None => Ok(false),
+        };
+    }
+
+    // --- 3. "text" field logic (Searching raw line) ---
+    if field == "text" {
+        if condition.is_regex() {
+            let is_match = condition.regex()?.is_match(raw_line);
+            return Ok(if op == Operator::NotMatches || op == Operator::NotIEq { !is_match } else { is_match });
+        }
+
+        let search_value_clean = query_value_str
+            .trim()
+            .trim_matches(|c| c == '"' || c == '\'');
+
+        return match op {
+            Operator::Contains | Operator::NotContains => {
+                let search_terms = condition.terms.get_or_init(|| {
+                    query_value_str
                         .split(',')
                         .map(|s| {
                             s.trim()
@@ -262,152 +1317,375 @@ fn evaluate_single_condition(
                                 .to_lowercase()
                         })
                         .filter(|s| !s.is_empty())
-                        .collect();
+                        .collect()
+                });
 
-                    if search_terms.is_empty() {
-                        return Ok(true);
-                    }
+                if search_terms.is_empty() {
+                    return Ok(true);
+                }
 
-                    if op_str == "contains" {
-                        Ok(search_terms
-                            .iter()
-                            .all(|term| lower_raw_line.contains(term)))
-                    } else {
-                        Ok(search_terms
-                            .iter()
-                            .all(|term| !lower_raw_line.contains(term)))
-                    }
+                if op == Operator::Contains {
+                    Ok(search_terms
+                        .iter()
+                        .all(|term| contains_folded(raw_line, term)))
+                } else {
+                    Ok(search_terms
+                        .iter()
+                        .all(|term| !contains_folded(raw_line, term)))
                 }
-                // Support for 'text between 100..200'
-                "between" | "!between" => {
-                    let parts: Vec<&str> = query_value_str.split("..").collect();
-                    if parts.len() != 2 {
+            }
+            // Support for 'text between 100..200'
+            Operator::Between | Operator::NotBetween => {
+                let parts: Vec<&str> = query_value_str.split("..").collect();
+                if parts.len() != 2 {
+                    return Err(QueryError::InvalidFormat(format!(
+                        "Operator '{}' requires a range 'start..end'. Got: '{}'",
+                        op, query_value_str
+                    )));
+                }
+
+                let s1 = parts[0].trim().trim_matches(|c| c == '"' || c == '\'');
+                let s2 = parts[1].trim().trim_matches(|c| c == '"' || c == '\'');
+
+                let n1 = s1.parse::<f64>().map_err(|_| {
+                    QueryError::InvalidFormat(format!("Invalid start number: {}", s1))
+                })?;
+                let n2 = s2.parse::<f64>().map_err(|_| {
+                    QueryError::InvalidFormat(format!("Invalid end number: {}", s2))
+                })?;
+
+                // Auto-swap for safety
+                let (start, end) = if n1 < n2 { (n1, n2) } else { (n2, n1) };
+
+                // Extract all numbers from the raw line
+                let numbers_in_line = extract_numbers(raw_line);
+
+                // Check if ANY number in the line is within the range
+                let any_match = numbers_in_line.iter().any(|&n| n >= start && n <= end);
+
+                if op == Operator::Between {
+                    Ok(any_match)
+                } else {
+                    Ok(!any_match)
+                }
+            }
+            Operator::ContainsGe | Operator::NotContainsGe | Operator::ContainsLe | Operator::NotContainsLe => {
+                let query_num = match search_value_clean.parse::<f64>() {
+                    Ok(n) => n,
+                    Err(_) => {
                         return Err(QueryError::InvalidFormat(format!(
-                            "Operator '{}' requires a range 'start..end'. Got: '{}'",
-                            op_str, query_value_str
+                            "Operator '{}' requires a numeric value, but got '{}'",
+                            op, query_value_str
                         )));
                     }
+                };
 
-                    let s1 = parts[0].trim().trim_matches(|c| c == '"' || c == '\'');
-                    let s2 = parts[1].trim().trim_matches(|c| c == '"' || c == '\'');
+                let numbers_in_line = extract_numbers(raw_line);
 
-                    let n1 = s1.parse::<f64>().map_err(|_| {
-                        QueryError::InvalidFormat(format!("Invalid start number: {}", s1))
-                    })?;
-                    let n2 = s2.parse::<f64>().map_err(|_| {
-                        QueryError::InvalidFormat(format!("Invalid end number: {}", s2))
-                    })?;
+                match op {
+                    Operator::ContainsGe => Ok(numbers_in_line.iter().any(|&n| n >= query_num)),
+                    Operator::NotContainsGe => Ok(numbers_in_line.iter().all(|&n| n < query_num)),
+                    Operator::ContainsLe => Ok(numbers_in_line.iter().any(|&n| n <= query_num)),
+                    Operator::NotContainsLe => Ok(numbers_in_line.iter().all(|&n| n > query_num)),
+                    _ => unreachable!(),
+                }
+            }
+            Operator::Fuzzy | Operator::NotFuzzy => {
+                let is_close = fuzzy::similarity(raw_line, search_value_clean) >= options.fuzzy_threshold;
+                Ok(if op == Operator::Fuzzy { is_close } else { !is_close })
+            }
+            _ => Err(QueryError::InvalidFormat(
+                "The 'text' field only supports 'contains', 'between', 'fuzzy' and regex ('~=', 'matches') variations.".to_string(),
+            )),
+        };
+    }
 
-                    // Auto-swap for safety
-                    let (start, end) = if n1 < n2 { (n1, n2) } else { (n2, n1) };
+    // --- 4. Standard Field Logic ---
+    // A JSON null compares like a missing field: only `!=`/`isnot` match.
+    // Entries keeping their severity under another key (`severity`, `log.level`, ...) answer
+    // `level` queries with the canonical `level_num` the parsers add.
+    let resolved = options
+        .resolve_field(value, field)
+        .or_else(|| field.eq_ignore_ascii_case("level").then(|| options.resolve_field(value, "level_num")).flatten());
+    if let Some(original_value) = resolved.filter(|v| !v.is_null()) {
 
-                    // Extract all numbers from the raw line
-                    let numbers_in_line = extract_numbers(raw_line);
+        // Handle "num(field)" conversion logic
+        let temp_numeric_value;
+        let log_value = if force_numeric {
+            if original_value.is_number() {
+                original_value // Already a number
+            } else if let Some(s) = original_value.as_str() {
+                // Try parsing string as float (or as a duration string like "150ms" for dur())
+                let parsed = s.parse::<f64>().ok().or_else(|| {
+                    let unit = condition.field.duration_unit()?;
+                    units::parse_duration_nanos(s).map(|nanos| nanos / unit)
+                });
+                match parsed {
+                    Some(n) if n.is_finite() => {
+                        temp_numeric_value = Value::from(n);
+                        &temp_numeric_value
+                    },
+                    _ => return Ok(false) // Cannot force to number -> No match
+                }
+            } else {
+                // Booleans, Arrays, Objects cannot be forced to simple numbers for comparison
+                return Ok(false)
+            }
+        } else {
+            original_value
+        };
 
-                    // Check if ANY number in the line is within the range
-                    let any_match = numbers_in_line.iter().any(|&n| n >= start && n <= end);
+        // Unit-suffixed literals (`1.5s`, `100ms..2s`, `1MB`) are rewritten into plain numbers.
+        let rescaled_query_value = rescale_literal(condition, query_value_str, log_value.is_number());
+        let query_value_str = rescaled_query_value.as_deref().unwrap_or(query_value_str);
 
-                    if op_str == "between" {
-                        Ok(any_match)
-                    } else {
-                        Ok(!any_match) 
-                    }
+        // Level fields compare on the canonical severity scale (trace < ... < fatal)
+        // whenever both sides are recognisable levels. Versions compare component-wise
+        // under semver(), or automatically when the literal is spelled like `1.2.3`.
+        let force_semver = condition.field.modifier == Some(Modifier::Semver);
+        let ordering = |case_insensitive: bool| {
+            if LEVEL_KEYS.iter().any(|key| key.eq_ignore_ascii_case(field)) && !force_numeric && !force_semver {
+                if let Some(ord) = compare_levels(log_value, query_value_str) {
+                    return Some(ord);
                 }
-                "contains+" | "!contains+" | "contains-" | "!contains-" => {
-                    let query_num = match search_value_clean.parse::<f64>() {
-                        Ok(n) => n,
-                        Err(_) => {
-                            return Err(QueryError::InvalidFormat(format!(
-                                "Operator '{}' requires a numeric value, but got '{}'",
-                                op_str, query_value_str
-                            )));
-                        }
-                    };
+            }
+            if force_semver || Version::looks_like_version(query_value_str) {
+                if let Some(ord) = compare_versions(log_value, query_value_str) {
+                    return Some(ord);
+                }
+                if force_semver {
+                    return None;
+                }
+            }
+            if let Some(query_class) = parse_status_class(query_value_str) {
+                return compare_status_class(log_value, query_class);
+            }
+            if let Some(query_bool) = parse_bool_literal(query_value_str) {
+                return compare_booleans(log_value, query_bool);
+            }
+            compare_values(log_value, query_value_str, case_insensitive)
+        };
 
-                    let numbers_in_line = extract_numbers(raw_line);
+        // Field EXISTS and value prepared
+        match op {
+            Operator::Between => evaluate_between(log_value, query_value_str),
+            Operator::NotBetween => evaluate_between(log_value, query_value_str).map(|b| !b),
 
-                    match op_str {
-                        "contains+" => Ok(numbers_in_line.iter().any(|&n| n >= query_num)),
-                        "!contains+" => Ok(numbers_in_line.iter().all(|&n| n < query_num)),
-                        "contains-" => Ok(numbers_in_line.iter().any(|&n| n <= query_num)),
-                        "!contains-" => Ok(numbers_in_line.iter().all(|&n| n > query_num)),
-                        _ => unreachable!(),
-                    }
+            Operator::IEq => Ok(ordering(true) == Some(std::cmp::Ordering::Equal)),
+            Operator::NotIEq => Ok(ordering(true) != Some(std::cmp::Ordering::Equal)),
+
+            Operator::Contains => {
+                let query_clean = query_value_str.trim().trim_matches(|c| c == '"' || c == '\'');
+                match log_value {
+                    Value::String(s) => Ok(s.contains(query_clean)),
+                    _ => Ok(false),
                 }
-                _ => Err(QueryError::InvalidFormat(
-                    "The 'text' field only supports 'contains' and 'between' variations.".to_string(),
-                )),
-            };
-        }
-
-        // --- 5. Standard Field Logic ---
-        if let Some(original_value) = get_value_by_field(value, field) {
-            
-            // Handle "num(field)" conversion logic
-            let temp_numeric_value; 
-            let log_value = if force_numeric {
-                if let Some(_) = original_value.as_f64() {
-                    original_value // Already a number
-                } else if let Some(s) = original_value.as_str() {
-                    // Try parsing string as float
-                    match s.parse::<f64>() {
-                        Ok(n) if n.is_finite() => {
-                            temp_numeric_value = Some(Value::from(n));
-                            temp_numeric_value.as_ref().unwrap()
-                        },
-                        _ => return Ok(false) // Cannot force to number -> No match
-                    }
-                } else {
-                    // Booleans, Arrays, Objects cannot be forced to simple numbers for comparison
-                    return Ok(false)
+            },
+            Operator::NotContains => {
+                let query_clean = query_value_str.trim().trim_matches(|c| c == '"' || c == '\'');
+                match log_value {
+                    Value::String(s) => Ok(!s.contains(query_clean)),
+                    _ => Ok(true),
                 }
-            } else {
-                original_value
-            };
-
-            // Field EXISTS and value prepared
-            return match op_str {
-                "between" => evaluate_between(log_value, query_value_str, false),
-                "!between" => evaluate_between(log_value, query_value_str, false).map(|b| !b),
-
-                "~=" => Ok(compare_values(log_value, query_value_str, true) == Some(std::cmp::Ordering::Equal)),
-                "!~=" => Ok(compare_values(log_value, query_value_str, true) != Some(std::cmp::Ordering::Equal)),
-                
-                "contains" => {
-                    let query_clean = query_value_str.trim().trim_matches(|c| c == '"' || c == '\'');
-                    match log_value {
-                        Value::String(s) => Ok(s.contains(query_clean)),
-                        _ => Ok(false),
-                    }
-                },
-                "!contains" => {
-                    let query_clean = query_value_str.trim().trim_matches(|c| c == '"' || c == '\'');
-                    match log_value {
-                        Value::String(s) => Ok(!s.contains(query_clean)),
-                        _ => Ok(true),
-                    }
-                },
-
-                "==" | "is" => Ok(compare_values(log_value, query_value_str, false) == Some(std::cmp::Ordering::Equal)),
-                "!=" | "isnot" => Ok(compare_values(log_value, query_value_str, false) != Some(std::cmp::Ordering::Equal)),
-                ">" => Ok(compare_values(log_value, query_value_str, false) == Some(std::cmp::Ordering::Greater)),
-                "<" => Ok(compare_values(log_value, query_value_str, false) == Some(std::cmp::Ordering::Less)),
-                ">=" => Ok(compare_values(log_value, query_value_str, false).map_or(false, |ord| ord != std::cmp::Ordering::Less)),
-                "<=" => Ok(compare_values(log_value, query_value_str, false).map_or(false, |ord| ord != std::cmp::Ordering::Greater)),
-                _ => Ok(false),
-            };
-        } else {
-            // Field DOES NOT EXIST
-            return match op_str {
-                "!=" | "isnot" => Ok(true),
-                _ => Ok(false),
-            };
+            },
+
+            Operator::Fuzzy | Operator::NotFuzzy => {
+                let query_clean = query_value_str.trim().trim_matches(|c| c == '"' || c == '\'');
+                let log_text = match log_value {
+                    Value::String(s) => s.clone(),
+                    Value::Number(n) => n.to_string(),
+                    Value::Bool(b) => b.to_string(),
+                    _ => return Ok(op == Operator::NotFuzzy),
+                };
+                let is_close = fuzzy::similarity(&log_text, query_clean) >= options.fuzzy_threshold;
+                Ok(if op == Operator::Fuzzy { is_close } else { !is_close })
+            },
+
+            Operator::Matches | Operator::NotMatches => {
+                let is_match = match log_value {
+                    Value::String(s) => condition.regex()?.is_match(s),
+                    Value::Number(n) => condition.regex()?.is_match(&n.to_string()),
+                    Value::Bool(b) => condition.regex()?.is_match(&b.to_string()),
+                    _ => false,
+                };
+                Ok(if op == Operator::Matches { is_match } else { !is_match })
+            },
+
+            Operator::Eq | Operator::Is => Ok(ordering(false) == Some(std::cmp::Ordering::Equal)),
+            Operator::Ne | Operator::IsNot => Ok(ordering(false) != Some(std::cmp::Ordering::Equal)),
+            Operator::Gt => Ok(ordering(false) == Some(std::cmp::Ordering::Greater)),
+            Operator::Lt => Ok(ordering(false) == Some(std::cmp::Ordering::Less)),
+            Operator::Ge => Ok(ordering(false).is_some_and(|ord| ord != std::cmp::Ordering::Less)),
+            Operator::Le => Ok(ordering(false).is_some_and(|ord| ord != std::cmp::Ordering::Greater)),
+            _ => Ok(false),
+        }
+    } else {
+        // Field DOES NOT EXIST (or is null)
+        match op {
+            Operator::Ne | Operator::IsNot => Ok(true),
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Applies a plain comparison operator to an ordering. `None` for non-comparison operators.
+fn ordering_matches(op: Operator, ord: Option<std::cmp::Ordering>) -> Option<bool> {
+    use std::cmp::Ordering::*;
+    Some(match op {
+        Operator::Eq | Operator::Is | Operator::IEq => ord == Some(Equal),
+        Operator::Ne | Operator::IsNot | Operator::NotIEq => ord != Some(Equal),
+        Operator::Gt => ord == Some(Greater),
+        Operator::Lt => ord == Some(Less),
+        Operator::Ge => ord.is_some_and(|o| o != Less),
+        Operator::Le => ord.is_some_and(|o| o != Greater),
+        _ => return None,
+    })
+}
+
+/// Counts the dotted key paths (`http.request.method`) in the entry matching a pattern.
+/// `*` matches within one path segment, `**` across segments.
+fn count_matching_paths(value: &Value, pattern: &str) -> usize {
+    fn walk(value: &Value, prefix: &mut String, pattern: &str, count: &mut usize) {
+        let Some(map) = value.as_object() else { return };
+        for (key, child) in map {
+            let len = prefix.len();
+            if !prefix.is_empty() {
+                prefix.push('.');
+            }
+            prefix.push_str(key);
+            if wildcard_match(pattern.as_bytes(), prefix.as_bytes()) {
+                *count += 1;
+            }
+            walk(child, prefix, pattern, count);
+            prefix.truncate(len);
+        }
+    }
+
+    let mut count = 0;
+    walk(value, &mut String::new(), pattern, &mut count);
+    count
+}
+
+/// The duration an `age()` or `since()` field measures, or `None` when a timestamp is
+/// missing or unparseable. Negative when the times are in the opposite order.
+fn elapsed(value: &Value, field: &Field, scope: &Scope) -> Option<chrono::Duration> {
+    match field.name.split_once(',') {
+        Some((start, end)) if field.modifier == Some(Modifier::Age) => {
+            Some(scope.field_time(value, end.trim())? - scope.field_time(value, start.trim())?)
+        }
+        _ => Some(scope.now - scope.field_time(value, &field.name)?),
+    }
+}
+
+fn wildcard_match(pattern: &[u8], path: &[u8]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        [b'*', b'*', rest @ ..] => (0..=path.len()).any(|i| wildcard_match(rest, &path[i..])),
+        [b'*', rest @ ..] => {
+            let segment_end = path.iter().position(|&b| b == b'.').unwrap_or(path.len());
+            (0..=segment_end).any(|i| wildcard_match(rest, &path[i..]))
+        }
+        [c, rest @ ..] => path.first() == Some(c) && wildcard_match(rest, &path[1..]),
+    }
+}
+
+/// Rewrites a unit-suffixed literal, or a `start..end` range of them, into plain numbers:
+/// durations are expressed in the field's own unit and byte sizes in bytes.
+///
+/// Only numeric comparisons are rewritten: ordering operators and ranges, any operator
+/// under `num()`, `dur()`, `age()` and `since()`, or any operator against a `numeric` log
+/// value. Quoted literals stay text, so `name == 5b` on a string field and `size > "5b"`
+/// compare strings. Returns `None` when nothing in the literal carries a unit.
+fn rescale_literal(condition: &Condition, literal: &str, numeric: bool) -> Option<String> {
+    let numeric = numeric
+        || matches!(
+            condition.op,
+            Operator::Gt | Operator::Lt | Operator::Ge | Operator::Le | Operator::Between | Operator::NotBetween
+        )
+        || matches!(condition.field.modifier, Some(Modifier::Num | Modifier::Dur | Modifier::Age | Modifier::Since));
+    let quoted = |part: &str| part.trim().starts_with(['"', '\'']);
+    if !numeric || literal.split("..").any(quoted) {
+        return None;
+    }
+
+    let duration_unit = condition.field.duration_unit();
+    let convert = |part: &str| {
+        duration_unit
+            .and_then(|unit| units::parse_duration_nanos(part).map(|nanos| nanos / unit))
+            .or_else(|| units::parse_byte_size(part))
+    };
+    let plain = |part: &str| part.trim().trim_matches(|c| c == '"' || c == '\'').parse::<f64>().ok();
+
+    if let Some((start, end)) = literal.split_once("..") {
+        if convert(start).is_none() && convert(end).is_none() {
+            return None;
         }
+        let start = convert(start).or_else(|| plain(start))?;
+        let end = convert(end).or_else(|| plain(end))?;
+        Some(format!("{}..{}", start, end))
     } else {
-        Err(QueryError::InvalidFormat(condition.to_string()))
+        convert(literal).map(|n| n.to_string())
+    }
+}
+
+fn compare_levels(log_value: &Value, query_value_str_raw: &str) -> Option<std::cmp::Ordering> {
+    let log_level = match log_value {
+        Value::String(s) => Level::parse_field(s)?,
+        other => Level::from_value(other)?,
+    };
+    let query_level = Level::parse(query_value_str_raw)?;
+    Some(log_level.cmp(&query_level))
+}
+
+fn compare_versions(log_value: &Value, query_value_str_raw: &str) -> Option<std::cmp::Ordering> {
+    let log_version = Version::parse(log_value.as_str()?)?;
+    let query_version = Version::parse(query_value_str_raw)?;
+    Some(log_version.cmp(&query_version))
+}
+
+/// An unquoted `true`/`false` (any case) on the right-hand side is a boolean literal.
+fn parse_bool_literal(query_value_str_raw: &str) -> Option<bool> {
+    match query_value_str_raw.trim().to_ascii_lowercase().as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
     }
 }
 
+/// The class digit of an HTTP status class literal like `5xx` (case-insensitive).
+fn parse_status_class(query_value_str_raw: &str) -> Option<u64> {
+    let s = query_value_str_raw.trim().trim_matches(|c| c == '"' || c == '\'');
+    match s.as_bytes() {
+        [digit @ b'1'..=b'5', x1, x2] if x1.eq_ignore_ascii_case(&b'x') && x2.eq_ignore_ascii_case(&b'x') => {
+            Some(u64::from(digit - b'0'))
+        }
+        _ => None,
+    }
+}
+
+/// Compares a status code (`503`, `"503"`) or class (`"5xx"`) by class, so
+/// `status == 5xx` and `status >= 4xx` work.
+fn compare_status_class(log_value: &Value, query_class: u64) -> Option<std::cmp::Ordering> {
+    let log_class = match log_value {
+        Value::Number(n) => n.as_u64()? / 100,
+        Value::String(s) => s.parse::<u64>().ok().map(|code| code / 100).or_else(|| parse_status_class(s))?,
+        _ => return None,
+    };
+    Some(log_class.cmp(&query_class))
+}
+
+/// Compares against JSON booleans, and against "true"/"false" strings since logfmt
+/// values arrive untyped. Any other value never equals a boolean literal.
+fn compare_booleans(log_value: &Value, query_bool: bool) -> Option<std::cmp::Ordering> {
+    let log_bool = match log_value {
+        Value::Bool(b) => *b,
+        Value::String(s) if s.eq_ignore_ascii_case("true") => true,
+        Value::String(s) if s.eq_ignore_ascii_case("false") => false,
+        _ => return None,
+    };
+    Some(log_bool.cmp(&query_bool))
+}
+
 fn compare_values(
     log_value: &Value,
     query_value_str_raw: &str,
@@ -440,4 +1718,4 @@ fn compare_values(
     } else {
         Some(log_str_equivalent.as_str().cmp(query_value_clean))
     }
-}
\ No newline at end of file
+}
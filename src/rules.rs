@@ -0,0 +1,245 @@
+// src/rules.rs
+
+use crate::pipeline::Record;
+use crate::query::{Query, QueryError, QueryOptions};
+use crate::time as time_parser;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+/// How the count in the window is compared to the threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    /// More than the threshold (`>`), e.g. error bursts.
+    Above,
+    /// At least the threshold (`>=`).
+    AtLeast,
+    /// Fewer than the threshold (`<`), e.g. missing heartbeats.
+    Below,
+    /// At most the threshold (`<=`).
+    AtMost,
+}
+
+impl Comparison {
+    fn holds(&self, count: u64, threshold: u64) -> bool {
+        match self {
+            Comparison::Above => count > threshold,
+            Comparison::AtLeast => count >= threshold,
+            Comparison::Below => count < threshold,
+            Comparison::AtMost => count <= threshold,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Comparison::Above => ">",
+            Comparison::AtLeast => ">=",
+            Comparison::Below => "<",
+            Comparison::AtMost => "<=",
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// "More than `threshold` entries matching `query` within `window`".
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub query: Query,
+    pub window: Duration,
+    pub comparison: Comparison,
+    pub threshold: u64,
+}
+
+impl Rule {
+    /// A rule that fires when more than `threshold` entries match within `window`.
+    pub fn new(name: impl Into<String>, query: Query, window: Duration, threshold: u64) -> Self {
+        Rule { name: name.into(), query, window, comparison: Comparison::Above, threshold }
+    }
+
+    pub fn with_comparison(mut self, comparison: Comparison) -> Self {
+        self.comparison = comparison;
+        self
+    }
+}
+
+/// Whether a rule started or stopped holding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+/// Emitted when a rule's condition starts or stops holding.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    pub rule: String,
+    pub state: AlertState,
+    /// Matching entries in the window when the state changed.
+    pub count: u64,
+    pub comparison: Comparison,
+    pub threshold: u64,
+    pub window_start: DateTime<Utc>,
+    pub window_end: DateTime<Utc>,
+}
+
+struct RuleState {
+    rule: Rule,
+    window: chrono::Duration,
+    /// Matches per second, oldest first.
+    buckets: VecDeque<(i64, u64)>,
+    count: u64,
+    firing: bool,
+}
+
+impl RuleState {
+    fn window_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now.checked_sub_signed(self.window).unwrap_or(DateTime::<Utc>::MIN_UTC)
+    }
+
+    fn evict(&mut self, now: DateTime<Utc>) {
+        let start = self.window_start(now).timestamp();
+        while let Some(&(second, n)) = self.buckets.front() {
+            if second > start {
+                break;
+            }
+            self.count -= n;
+            self.buckets.pop_front();
+        }
+    }
+
+    fn record(&mut self, at: DateTime<Utc>) {
+        let second = at.timestamp();
+        match self.buckets.back_mut() {
+            Some((last, n)) if *last >= second => *n += 1,
+            _ => self.buckets.push_back((second, 1)),
+        }
+        self.count += 1;
+    }
+
+    /// `warmed_up` is false until a whole window has been observed, so `Below` rules
+    /// don't fire on an empty window at the start of the stream.
+    fn transition(&mut self, now: DateTime<Utc>, started: DateTime<Utc>) -> Option<Alert> {
+        let warmed_up = self.window_start(now) >= started;
+        let holds = self.rule.comparison.holds(self.count, self.rule.threshold)
+            && (warmed_up || matches!(self.rule.comparison, Comparison::Above | Comparison::AtLeast));
+        if holds == self.firing {
+            return None;
+        }
+        self.firing = holds;
+        Some(Alert {
+            rule: self.rule.name.clone(),
+            state: if holds { AlertState::Firing } else { AlertState::Resolved },
+            count: self.count,
+            comparison: self.rule.comparison,
+            threshold: self.rule.threshold,
+            window_start: self.window_start(now),
+            window_end: now,
+        })
+    }
+}
+
+/// Evaluates threshold rules incrementally over a stream of entries.
+///
+/// Time is driven by the entries' timestamps (entries without one use the latest
+/// time seen), so replaying an old file produces the alerts it would have raised.
+/// Each rule raises [`AlertState::Firing`] once when its condition starts holding and
+/// [`AlertState::Resolved`] once when it stops.
+pub struct RuleEngine {
+    rules: Vec<RuleState>,
+    options: QueryOptions,
+    started: Option<DateTime<Utc>>,
+    now: Option<DateTime<Utc>>,
+}
+
+impl RuleEngine {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        let mut engine = RuleEngine {
+            rules: Vec::new(),
+            options: QueryOptions::default(),
+            started: None,
+            now: None,
+        };
+        for rule in rules {
+            engine.add_rule(rule);
+        }
+        engine
+    }
+
+    pub fn with_options(mut self, options: QueryOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn add_rule(&mut self, rule: Rule) {
+        let window = chrono::Duration::from_std(rule.window).unwrap_or(chrono::Duration::MAX);
+        self.rules.push(RuleState { rule, window, buckets: VecDeque::new(), count: 0, firing: false });
+    }
+
+    /// Rules currently firing.
+    pub fn firing(&self) -> impl Iterator<Item = &Rule> {
+        self.rules.iter().filter(|state| state.firing).map(|state| &state.rule)
+    }
+
+    /// Feeds one entry, returning the alerts it triggered.
+    pub fn observe(&mut self, value: &Value, raw: &str) -> Result<Vec<Alert>, QueryError> {
+        let keys = &self.options.timestamp_fields;
+        let timestamp = if keys.is_empty() {
+            time_parser::extract_timestamp_with(value, &self.options.time)
+        } else {
+            time_parser::extract_timestamp_with_keys(value, keys, &self.options.time)
+        };
+        match timestamp.or(self.now) {
+            Some(at) => self.observe_at(at, value, raw),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn observe_record(&mut self, record: &Record) -> Result<Vec<Alert>, QueryError> {
+        self.observe(record.value(), record.raw())
+    }
+
+    /// Feeds one entry at an already-known time.
+    pub fn observe_at(&mut self, at: DateTime<Utc>, value: &Value, raw: &str) -> Result<Vec<Alert>, QueryError> {
+        let now = self.now.map_or(at, |now| now.max(at));
+        let started = *self.started.get_or_insert(at);
+        self.now = Some(now);
+
+        let mut alerts = Vec::new();
+        for state in &mut self.rules {
+            // Entries older than the window can no longer change the outcome.
+            let in_window = at > state.window_start(now);
+            if in_window && state.rule.query.matches_with_options(value, raw, &self.options)? {
+                state.record(at);
+            }
+            state.evict(now);
+            alerts.extend(state.transition(now, started));
+        }
+        Ok(alerts)
+    }
+
+    /// Moves the clock forward without an entry (e.g. on a timer while a stream is quiet),
+    /// so windows drain and `Below` rules can fire.
+    pub fn advance(&mut self, now: DateTime<Utc>) -> Vec<Alert> {
+        let now = self.now.map_or(now, |current| current.max(now));
+        let started = *self.started.get_or_insert(now);
+        self.now = Some(now);
+        self.rules
+            .iter_mut()
+            .filter_map(|state| {
+                state.evict(now);
+                state.transition(now, started)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,158 @@
+// src/schema.rs
+
+use crate::aggregate::distinct::{DistinctCount, DistinctResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+
+/// Distinct example values kept per field.
+const MAX_EXAMPLES: usize = 3;
+/// Exact distinct values tracked per field before switching to an estimate.
+const CARDINALITY_EXACT_LIMIT: usize = 1_000;
+
+/// JSON type of an observed value. Integers and floats are told apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    Null,
+    Bool,
+    Integer,
+    Float,
+    String,
+    Array,
+    Object,
+}
+
+impl FieldType {
+    pub fn of(value: &Value) -> FieldType {
+        match value {
+            Value::Null => FieldType::Null,
+            Value::Bool(_) => FieldType::Bool,
+            Value::Number(n) if n.is_f64() => FieldType::Float,
+            Value::Number(_) => FieldType::Integer,
+            Value::String(_) => FieldType::String,
+            Value::Array(_) => FieldType::Array,
+            Value::Object(_) => FieldType::Object,
+        }
+    }
+}
+
+/// What was observed for one field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldSchema {
+    /// Dotted path to the field (`http.status`), usable directly in queries.
+    pub path: String,
+    /// How often each type was seen.
+    pub types: BTreeMap<FieldType, u64>,
+    /// Entries that had the field, including as `null`.
+    pub present: u64,
+    /// Share of entries where the field was missing or `null`.
+    pub null_rate: f64,
+    pub examples: Vec<String>,
+    pub cardinality: DistinctResult,
+}
+
+/// The fields seen across a set of entries, sorted by path.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schema {
+    pub entries: u64,
+    pub fields: Vec<FieldSchema>,
+}
+
+/// Incrementally infers a [`Schema`]. Nested objects are walked and reported by
+/// dotted path; arrays are reported as a whole.
+#[derive(Debug, Clone, Default)]
+pub struct SchemaInference {
+    entries: u64,
+    fields: BTreeMap<String, FieldState>,
+}
+
+#[derive(Debug, Clone)]
+struct FieldState {
+    types: BTreeMap<FieldType, u64>,
+    present: u64,
+    nulls: u64,
+    examples: Vec<String>,
+    distinct: DistinctCount,
+}
+
+impl SchemaInference {
+    pub fn new() -> Self {
+        SchemaInference::default()
+    }
+
+    /// Records every field of `entry`. Non-object entries only count towards the total.
+    pub fn observe(&mut self, entry: &Value) {
+        self.entries += 1;
+        if let Value::Object(map) = entry {
+            for (key, value) in map {
+                self.observe_field(key.clone(), value);
+            }
+        }
+    }
+
+    fn observe_field(&mut self, path: String, value: &Value) {
+        if let Value::Object(map) = value {
+            if !map.is_empty() {
+                for (key, nested) in map {
+                    self.observe_field(format!("{}.{}", path, key), nested);
+                }
+                return;
+            }
+        }
+
+        let state = self.fields.entry(path).or_insert_with(|| {
+            let mut distinct = DistinctCount::new("");
+            distinct.set_exact_limit(CARDINALITY_EXACT_LIMIT);
+            FieldState { types: BTreeMap::new(), present: 0, nulls: 0, examples: Vec::new(), distinct }
+        });
+        state.present += 1;
+        *state.types.entry(FieldType::of(value)).or_insert(0) += 1;
+
+        let text = match value {
+            Value::Null => {
+                state.nulls += 1;
+                return;
+            }
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        };
+        if state.examples.len() < MAX_EXAMPLES && !state.examples.contains(&text) {
+            state.examples.push(text.clone());
+        }
+        state.distinct.insert(text);
+    }
+
+    pub fn result(&self) -> Schema {
+        let fields = self
+            .fields
+            .iter()
+            .map(|(path, state)| {
+                let missing_or_null = self.entries.saturating_sub(state.present) + state.nulls;
+                FieldSchema {
+                    path: path.clone(),
+                    types: state.types.clone(),
+                    present: state.present,
+                    null_rate: missing_or_null as f64 / self.entries.max(1) as f64,
+                    examples: state.examples.clone(),
+                    cardinality: state.distinct.result(),
+                }
+            })
+            .collect();
+        Schema { entries: self.entries, fields }
+    }
+}
+
+/// Scans `entries` and reports their fields, types, null rates, examples and cardinality.
+pub fn infer<I>(entries: I) -> Schema
+where
+    I: IntoIterator,
+    I::Item: Borrow<Value>,
+{
+    let mut inference = SchemaInference::new();
+    for entry in entries {
+        inference.observe(entry.borrow());
+    }
+    inference.result()
+}
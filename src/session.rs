@@ -0,0 +1,139 @@
+// src/session.rs
+
+use crate::aggregate::group::{key_value, MISSING_KEY};
+use crate::parsers::{Format, LogEntry};
+use crate::pipeline::{Mark, Record, Transform};
+use crate::time as time_parser;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// One burst of activity from a single key (an IP, a user, ...).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub key: String,
+    pub first: DateTime<Utc>,
+    pub last: DateTime<Utc>,
+    pub duration_ms: i64,
+    pub entries: u64,
+}
+
+struct OpenSession {
+    session: Session,
+    first_line: usize,
+}
+
+/// Assembles entries into sessions per key, starting a new session whenever the key
+/// has been inactive for longer than the gap.
+///
+/// Entries are replaced by one [`Session`] record per session, so queries
+/// (`duration_ms > 60000`, `entries >= 100`) and aggregations apply to sessions.
+/// Entries missing the key field or a timestamp are skipped. Time follows the
+/// entries' own timestamps; sessions are emitted once they can no longer grow, in
+/// the order they started.
+pub struct Sessionize {
+    fields: Vec<String>,
+    gap: chrono::Duration,
+    open: HashMap<String, OpenSession>,
+    watermark: Option<DateTime<Utc>>,
+    next_sweep: Option<DateTime<Utc>>,
+}
+
+impl Sessionize {
+    /// Sessions keyed by one field, e.g. `remote_addr`.
+    pub fn new(field: impl Into<String>, gap: Duration) -> Self {
+        Sessionize::by_fields(vec![field.into()], gap)
+    }
+
+    /// Sessions keyed by a combination of fields, e.g. `remote_addr` and `http_user_agent`.
+    pub fn by_fields(fields: Vec<String>, gap: Duration) -> Self {
+        Sessionize {
+            fields,
+            gap: chrono::Duration::from_std(gap).unwrap_or(chrono::Duration::MAX),
+            open: HashMap::new(),
+            watermark: None,
+            next_sweep: None,
+        }
+    }
+
+    fn key_for(&self, record: &Record) -> Option<String> {
+        let mut parts = Vec::with_capacity(self.fields.len());
+        for field in &self.fields {
+            let value = key_value(record.value(), field);
+            if value == MISSING_KEY {
+                return None;
+            }
+            parts.push(value);
+        }
+        Some(parts.join(" "))
+    }
+
+    fn has_expired(&self, session: &Session, now: DateTime<Utc>) -> bool {
+        session.last.checked_add_signed(self.gap).is_some_and(|end| end < now)
+    }
+
+    /// Emits sessions that can no longer grow, a few times per gap.
+    fn sweep(&mut self, out: &mut Vec<Record>) {
+        let Some(watermark) = self.watermark else { return };
+        if self.next_sweep.is_some_and(|next| watermark < next) {
+            return;
+        }
+        let step = (self.gap / 4).max(chrono::Duration::seconds(1));
+        self.next_sweep = watermark.checked_add_signed(step);
+
+        let expired: Vec<String> = self
+            .open
+            .iter()
+            .filter(|(_, open)| self.has_expired(&open.session, watermark))
+            .map(|(key, _)| key.clone())
+            .collect();
+        let mut closed: Vec<OpenSession> = expired.iter().filter_map(|key| self.open.remove(key)).collect();
+        closed.sort_by_key(|open| open.first_line);
+        out.extend(closed.into_iter().map(session_record));
+    }
+}
+
+impl Transform for Sessionize {
+    fn process(&mut self, record: Record, out: &mut Vec<Record>) {
+        let Some(ts) = time_parser::extract_and_parse_timestamp(record.value()) else { return };
+        let Some(key) = self.key_for(&record) else { return };
+        self.watermark = Some(self.watermark.map_or(ts, |w| w.max(ts)));
+
+        if let Some(open) = self.open.get(&key) {
+            if self.has_expired(&open.session, ts) {
+                if let Some(open) = self.open.remove(&key) {
+                    out.push(session_record(open));
+                }
+            }
+        }
+
+        let open = self.open.entry(key.clone()).or_insert_with(|| OpenSession {
+            session: Session { key, first: ts, last: ts, duration_ms: 0, entries: 0 },
+            first_line: record.line_number,
+        });
+        let session = &mut open.session;
+        session.entries += 1;
+        session.first = session.first.min(ts);
+        session.last = session.last.max(ts);
+        session.duration_ms = (session.last - session.first).num_milliseconds();
+
+        self.sweep(out);
+    }
+
+    fn finish(&mut self, out: &mut Vec<Record>) {
+        let mut closed: Vec<OpenSession> = self.open.drain().map(|(_, open)| open).collect();
+        closed.sort_by_key(|open| open.first_line);
+        out.extend(closed.into_iter().map(session_record));
+    }
+}
+
+fn session_record(open: OpenSession) -> Record {
+    let value = serde_json::to_value(&open.session).unwrap_or_default();
+    let raw = value.to_string();
+    Record {
+        line_number: open.first_line,
+        entry: LogEntry::structured(value, raw, Format::Json).with_line_number(open.first_line),
+        mark: Mark::Match,
+    }
+}
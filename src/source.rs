@@ -0,0 +1,180 @@
+// src/source.rs
+
+use crate::pipeline::{Pipeline, PipelineStats, Record};
+use crate::query::QueryError;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// Lines longer than this are truncated (the rest of the line is skipped) so a
+/// single runaway line can't exhaust memory.
+pub const DEFAULT_MAX_LINE_LEN: usize = 1024 * 1024;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const BZIP2_MAGIC: &[u8] = b"BZh";
+
+#[derive(Debug)]
+pub enum SourceError {
+    Io(io::Error),
+    Query(QueryError),
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SourceError::Io(e) => write!(f, "I/O error: {}", e),
+            SourceError::Query(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+impl From<io::Error> for SourceError {
+    fn from(e: io::Error) -> Self {
+        SourceError::Io(e)
+    }
+}
+
+impl From<QueryError> for SourceError {
+    fn from(e: QueryError) -> Self {
+        SourceError::Query(e)
+    }
+}
+
+/// Opens a log file, transparently decompressing gzip, zstd or bzip2 content.
+pub fn open(path: impl AsRef<Path>) -> io::Result<Box<dyn BufRead + Send>> {
+    decompress(BufReader::new(File::open(path)?))
+}
+
+/// Wraps a reader in the right decoder, detected from the stream's magic bytes.
+/// Plain text passes through untouched.
+pub fn decompress<R: BufRead + Send + 'static>(mut reader: R) -> io::Result<Box<dyn BufRead + Send>> {
+    let head = reader.fill_buf()?;
+
+    if head.starts_with(GZIP_MAGIC) {
+        #[cfg(feature = "gzip")]
+        return Ok(Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader))));
+        #[cfg(not(feature = "gzip"))]
+        return Err(unsupported("gzip"));
+    }
+    if head.starts_with(ZSTD_MAGIC) {
+        #[cfg(feature = "zstd")]
+        return Ok(Box::new(BufReader::new(zstd::stream::read::Decoder::with_buffer(reader)?)));
+        #[cfg(not(feature = "zstd"))]
+        return Err(unsupported("zstd"));
+    }
+    if head.starts_with(BZIP2_MAGIC) {
+        #[cfg(feature = "bzip2")]
+        return Ok(Box::new(BufReader::new(bzip2::bufread::MultiBzDecoder::new(reader))));
+        #[cfg(not(feature = "bzip2"))]
+        return Err(unsupported("bzip2"));
+    }
+
+    Ok(Box::new(reader))
+}
+
+/// True when `head` starts with a compression format's magic bytes.
+#[cfg(feature = "index")]
+pub(crate) fn is_compressed(head: &[u8]) -> bool {
+    [GZIP_MAGIC, ZSTD_MAGIC, BZIP2_MAGIC].iter().any(|magic| head.starts_with(magic))
+}
+
+#[allow(dead_code)]
+fn unsupported(format: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{} compressed input requires the '{}' feature", format, format),
+    )
+}
+
+/// Splits a reader into lines, accepting both `\n` and `\r\n` endings.
+/// Invalid UTF-8 is replaced rather than treated as an error.
+pub struct Lines<R> {
+    reader: R,
+    max_line_len: usize,
+    buf: Vec<u8>,
+}
+
+/// Line iterator over any reader.
+pub fn lines<R: BufRead>(reader: R) -> Lines<R> {
+    Lines { reader, max_line_len: DEFAULT_MAX_LINE_LEN, buf: Vec::new() }
+}
+
+/// Opens (and if needed decompresses) a file and iterates over its lines.
+pub fn read_lines(path: impl AsRef<Path>) -> io::Result<Lines<Box<dyn BufRead + Send>>> {
+    Ok(lines(open(path)?))
+}
+
+impl<R: BufRead> Lines<R> {
+    pub fn with_max_line_len(mut self, max_line_len: usize) -> Self {
+        self.max_line_len = max_line_len;
+        self
+    }
+
+    /// Reads one line into `self.buf`, keeping at most `max_line_len` bytes.
+    /// Returns false at end of input.
+    fn read_line(&mut self) -> io::Result<bool> {
+        self.buf.clear();
+        let mut read_any = false;
+        loop {
+            let available = match self.reader.fill_buf() {
+                Ok(available) => available,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            if available.is_empty() {
+                return Ok(read_any);
+            }
+            read_any = true;
+
+            let (chunk, consumed, done) = match available.iter().position(|&b| b == b'\n') {
+                Some(i) => (&available[..i], i + 1, true),
+                None => (available, available.len(), false),
+            };
+            let room = self.max_line_len.saturating_sub(self.buf.len());
+            self.buf.extend_from_slice(&chunk[..chunk.len().min(room)]);
+            self.reader.consume(consumed);
+
+            if done {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_line() {
+            Ok(false) => None,
+            Ok(true) => {
+                if self.buf.ends_with(b"\r") {
+                    self.buf.pop();
+                }
+                Some(Ok(String::from_utf8_lossy(&self.buf).into_owned()))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+/// Streams every line of `reader` through `pipeline`, handing each released record
+/// to `on_record`, and flushes the pipeline at the end.
+pub fn feed<R: BufRead>(
+    pipeline: &mut Pipeline,
+    reader: R,
+    mut on_record: impl FnMut(Record),
+) -> Result<PipelineStats, SourceError> {
+    let mut out = Vec::new();
+    for line in lines(reader) {
+        pipeline.process_line(&line?, &mut out)?;
+        out.drain(..).for_each(&mut on_record);
+    }
+    pipeline.finish(&mut out)?;
+    out.drain(..).for_each(&mut on_record);
+    Ok(pipeline.stats())
+}
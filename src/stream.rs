@@ -0,0 +1,91 @@
+// src/stream.rs
+
+use crate::parsers;
+use crate::pipeline::{Pipeline, Record};
+use crate::query::{Query, QueryError, QueryOptions};
+use futures_util::stream::{self, Stream, StreamExt};
+use std::collections::VecDeque;
+use std::future;
+use std::io;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+/// Splits an async reader into a stream of lines (without their line endings).
+pub fn lines<R>(reader: R) -> impl Stream<Item = io::Result<String>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    stream::unfold(reader.lines(), |mut lines| async move {
+        lines.next_line().await.transpose().map(|line| (line, lines))
+    })
+}
+
+/// Yields the lines of `lines` that match `query`, in order.
+/// The async counterpart of filtering with [`Query::matches`].
+pub fn filter_stream<'a, S>(lines: S, query: &'a Query) -> impl Stream<Item = Result<String, QueryError>> + 'a
+where
+    S: Stream<Item = String> + 'a,
+{
+    filter_stream_with_options(lines, query, QueryOptions::default_ref())
+}
+
+/// Like [`filter_stream`], but with custom [`QueryOptions`].
+pub fn filter_stream_with_options<'a, S>(
+    lines: S,
+    query: &'a Query,
+    options: &'a QueryOptions,
+) -> impl Stream<Item = Result<String, QueryError>> + 'a
+where
+    S: Stream<Item = String> + 'a,
+{
+    lines.filter_map(move |line| future::ready(keep_if_match(line, query, options)))
+}
+
+fn keep_if_match(line: String, query: &Query, options: &QueryOptions) -> Option<Result<String, QueryError>> {
+    let entry = parsers::parse_log_line(&line);
+    match entry.matches_with_options(query, options) {
+        Ok(true) => Some(Ok(line)),
+        Ok(false) => None,
+        Err(e) => Some(Err(e)),
+    }
+}
+
+/// Runs every line of `lines` through `pipeline`, yielding records as they are released.
+/// The async counterpart of [`Pipeline::run`]; buffered records are flushed when the stream ends.
+pub fn pipeline_stream<'a, S>(
+    pipeline: &'a mut Pipeline,
+    lines: S,
+) -> impl Stream<Item = Result<Record, QueryError>> + 'a
+where
+    S: Stream<Item = String> + 'a,
+{
+    let state = PipelineState { pipeline, lines: Box::pin(lines), ready: VecDeque::new(), finished: false };
+    stream::unfold(state, |mut state| async move {
+        let mut out = Vec::new();
+        loop {
+            if let Some(record) = state.ready.pop_front() {
+                return Some((Ok(record), state));
+            }
+            if state.finished {
+                return None;
+            }
+            let result = match state.lines.next().await {
+                Some(line) => state.pipeline.process_line(&line, &mut out),
+                None => {
+                    state.finished = true;
+                    state.pipeline.finish(&mut out)
+                }
+            };
+            if let Err(e) = result {
+                return Some((Err(e), state));
+            }
+            state.ready.extend(out.drain(..));
+        }
+    })
+}
+
+struct PipelineState<'a, S> {
+    pipeline: &'a mut Pipeline,
+    lines: std::pin::Pin<Box<S>>,
+    ready: VecDeque<Record>,
+    finished: bool,
+}
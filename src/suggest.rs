@@ -0,0 +1,193 @@
+// src/suggest.rs
+
+use crate::query::Operator;
+use crate::schema::{FieldSchema, FieldType, Schema};
+use crate::time as time_parser;
+use serde::{Deserialize, Serialize};
+
+/// Completions returned per call.
+const MAX_SUGGESTIONS: usize = 20;
+
+/// Relative times offered as values for timestamp fields.
+const TIME_EXAMPLES: &[&str] = &["15m ago", "1h ago", "today", "yesterday"];
+
+/// What a completion inserts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    Field,
+    Operator,
+    Value,
+}
+
+/// One as-you-type completion for a partial query.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub kind: SuggestionKind,
+    /// Text to insert.
+    pub text: String,
+    /// The field's type, or for values the field they belong to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Byte offset in the prefix where `text` starts; everything after it is replaced.
+    pub replace_from: usize,
+}
+
+/// Completes the last condition of `prefix` using an inferred [`Schema`]: field names
+/// while the field is being typed, then operators valid for the field's type, then
+/// example values.
+pub fn suggest(prefix: &str, schema: &Schema) -> Vec<Suggestion> {
+    let start = clause_start(prefix);
+    let clause = &prefix[start..];
+    let field_start = start + (clause.len() - clause.trim_start().len());
+    let clause = clause.trim_start();
+
+    // `status` (field), `status ` / `status >` (operator), `status >= 5` (value)
+    let field_end = clause
+        .find(|c: char| c.is_whitespace() || matches!(c, '=' | '!' | '<' | '>' | '~'))
+        .unwrap_or(clause.len());
+    let field = &clause[..field_end];
+    if field_end == clause.len() {
+        return fields(field, schema, field_start);
+    }
+
+    let rest = &clause[field_end..];
+    let op_start = field_start + field_end + (rest.len() - rest.trim_start().len());
+    let rest = rest.trim_start();
+    let schema_field = schema.fields.iter().find(|f| f.path.eq_ignore_ascii_case(field));
+    let op_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    // Symbolic operators can run into the value: `status>=5`
+    let op_end = match rest.find(|c: char| !matches!(c, '=' | '!' | '<' | '>' | '~')) {
+        Some(symbols) if symbols > 0 => symbols.min(op_end),
+        _ => op_end,
+    };
+    if op_end == rest.len() {
+        return operators(field, &rest[..op_end], schema_field, op_start);
+    }
+
+    let value = rest[op_end..].trim_start();
+    let value_start = prefix.len() - value.len();
+    values(field, value, schema_field, value_start)
+}
+
+/// Where the condition being typed starts: after the last `and` / `or` / `(`.
+fn clause_start(prefix: &str) -> usize {
+    let lower = prefix.to_ascii_lowercase();
+    [" and ", " or ", "&&", "||", "("]
+        .iter()
+        .filter_map(|sep| lower.rfind(sep).map(|i| i + sep.len()))
+        .max()
+        .unwrap_or(0)
+}
+
+fn fields(partial: &str, schema: &Schema, replace_from: usize) -> Vec<Suggestion> {
+    let partial = partial.to_ascii_lowercase();
+    let mut matching: Vec<&FieldSchema> =
+        schema.fields.iter().filter(|f| f.path.to_ascii_lowercase().starts_with(&partial)).collect();
+    // Fields most entries carry first
+    matching.sort_by(|a, b| b.present.cmp(&a.present).then_with(|| a.path.cmp(&b.path)));
+
+    let mut suggestions: Vec<Suggestion> = matching
+        .into_iter()
+        .map(|f| Suggestion {
+            kind: SuggestionKind::Field,
+            text: f.path.clone(),
+            detail: dominant_type(f).map(|t| type_name(t, f).to_string()),
+            replace_from,
+        })
+        .collect();
+    if "text".starts_with(&partial) {
+        suggestions.push(Suggestion {
+            kind: SuggestionKind::Field,
+            text: "text".to_string(),
+            detail: Some("raw line".to_string()),
+            replace_from,
+        });
+    }
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+fn operators(field: &str, partial: &str, schema_field: Option<&FieldSchema>, replace_from: usize) -> Vec<Suggestion> {
+    let ops: &[Operator] = match schema_field.and_then(dominant_type) {
+        _ if field == "text" => &[Operator::Contains, Operator::NotContains, Operator::Matches, Operator::Fuzzy],
+        _ if schema_field.is_some_and(is_timestamp) => {
+            &[Operator::Gt, Operator::Lt, Operator::Ge, Operator::Le, Operator::Between]
+        }
+        Some(FieldType::Integer | FieldType::Float) => &[
+            Operator::Eq, Operator::Ne, Operator::Gt, Operator::Lt, Operator::Ge, Operator::Le, Operator::Between,
+            Operator::Exists,
+        ],
+        Some(FieldType::Bool) => &[Operator::Eq, Operator::Ne, Operator::Exists],
+        Some(FieldType::Array) => &[Operator::Contains, Operator::NotContains, Operator::Exists],
+        _ => &[
+            Operator::Eq, Operator::Ne, Operator::IEq, Operator::Contains, Operator::NotContains,
+            Operator::Matches, Operator::Fuzzy, Operator::Exists, Operator::NotExists, Operator::IsNull,
+        ],
+    };
+    ops.iter()
+        .filter(|op| op.as_str().starts_with(partial))
+        .map(|op| Suggestion {
+            kind: SuggestionKind::Operator,
+            text: op.as_str().to_string(),
+            detail: None,
+            replace_from,
+        })
+        .collect()
+}
+
+fn values(field: &str, partial: &str, schema_field: Option<&FieldSchema>, replace_from: usize) -> Vec<Suggestion> {
+    let Some(schema_field) = schema_field else {
+        return Vec::new();
+    };
+    let unquoted = partial.trim_start_matches(['"', '\'']).to_ascii_lowercase();
+    let mut candidates: Vec<String> = schema_field.examples.clone();
+    match dominant_type(schema_field) {
+        _ if is_timestamp(schema_field) => candidates = TIME_EXAMPLES.iter().map(|t| t.to_string()).collect(),
+        Some(FieldType::Bool) => candidates = vec!["true".to_string(), "false".to_string()],
+        _ => {}
+    }
+    candidates
+        .into_iter()
+        .filter(|value| value.to_ascii_lowercase().starts_with(&unquoted))
+        .map(|value| Suggestion {
+            kind: SuggestionKind::Value,
+            // Quote anything the parser would otherwise split on
+            text: if value.contains(char::is_whitespace) { format!("\"{}\"", value) } else { value },
+            detail: Some(field.to_string()),
+            replace_from,
+        })
+        .take(MAX_SUGGESTIONS)
+        .collect()
+}
+
+/// The most common non-null type of a field.
+fn dominant_type(field: &FieldSchema) -> Option<FieldType> {
+    field
+        .types
+        .iter()
+        .filter(|(t, _)| **t != FieldType::Null)
+        .max_by_key(|(_, count)| **count)
+        .map(|(t, _)| *t)
+}
+
+fn type_name(t: FieldType, field: &FieldSchema) -> &'static str {
+    match t {
+        _ if is_timestamp(field) => "timestamp",
+        FieldType::Null => "null",
+        FieldType::Bool => "bool",
+        FieldType::Integer => "integer",
+        FieldType::Float => "float",
+        FieldType::String => "string",
+        FieldType::Array => "array",
+        FieldType::Object => "object",
+    }
+}
+
+/// A conventional timestamp key, or a field whose examples parse as times.
+fn is_timestamp(field: &FieldSchema) -> bool {
+    time_parser::COMMON_KEYS.contains(&field.path.as_str())
+        || (!field.examples.is_empty()
+            && dominant_type(field) == Some(FieldType::String)
+            && field.examples.iter().all(|e| time_parser::parse_absolute(e).is_some()))
+}
@@ -0,0 +1,169 @@
+// src/tail.rs
+
+use crate::pipeline::{Pipeline, Record};
+use crate::source::SourceError;
+use std::collections::VecDeque;
+use std::fs::{File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Follows a growing file like `tail -F`: emits appended lines, and survives
+/// rotation (the path now names a new file) and truncation (the file shrank).
+///
+/// Uses polling, so it works the same on every platform and filesystem.
+pub struct Tail {
+    path: PathBuf,
+    file: File,
+    identity: Option<FileIdentity>,
+    position: u64,
+    poll_interval: Duration,
+    partial: Vec<u8>,
+    ready: VecDeque<String>,
+}
+
+impl Tail {
+    /// Starts following `path` from its current end, like `tail -f`.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut tail = Tail::from_start(path)?;
+        tail.position = tail.file.seek(SeekFrom::End(0))?;
+        Ok(tail)
+    }
+
+    /// Starts following `path` from its first line.
+    pub fn from_start(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::open(&path)?;
+        let identity = FileIdentity::of(&file.metadata()?);
+        Ok(Tail {
+            path,
+            file,
+            identity,
+            position: 0,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            partial: Vec::new(),
+            ready: VecDeque::new(),
+        })
+    }
+
+    pub fn set_poll_interval(&mut self, poll_interval: Duration) {
+        self.poll_interval = poll_interval;
+    }
+
+    /// Reads whatever complete lines are available right now, without blocking.
+    pub fn poll(&mut self) -> io::Result<Vec<String>> {
+        self.read_available()?;
+        self.check_rotation()?;
+        Ok(self.ready.drain(..).collect())
+    }
+
+    fn read_available(&mut self) -> io::Result<()> {
+        let file = &mut self.file;
+
+        // Truncated in place (copytruncate): start over from the top.
+        if file.metadata()?.len() < self.position {
+            self.position = file.seek(SeekFrom::Start(0))?;
+            self.partial.clear();
+        }
+
+        let mut chunk = Vec::new();
+        let read = file.read_to_end(&mut chunk)?;
+        self.position += read as u64;
+        self.partial.extend_from_slice(&chunk);
+
+        while let Some(i) = self.partial.iter().position(|&b| b == b'\n') {
+            let mut line: Vec<u8> = self.partial.drain(..=i).collect();
+            line.pop();
+            if line.ends_with(b"\r") {
+                line.pop();
+            }
+            self.ready.push_back(String::from_utf8_lossy(&line).into_owned());
+        }
+        Ok(())
+    }
+
+    /// Switches to the new file once the path has been rotated to a different one.
+    fn check_rotation(&mut self) -> io::Result<()> {
+        let current = match std::fs::metadata(&self.path) {
+            Ok(metadata) => FileIdentity::of(&metadata),
+            // Mid-rotation the path may briefly not exist; try again on the next poll.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        if current.is_none() || current == self.identity {
+            return Ok(());
+        }
+
+        // Drain what was appended to the old file before it was rotated away.
+        self.read_available()?;
+        if !self.partial.is_empty() {
+            let line = String::from_utf8_lossy(&self.partial).into_owned();
+            self.ready.push_back(line);
+            self.partial.clear();
+        }
+
+        let file = File::open(&self.path)?;
+        self.identity = FileIdentity::of(&file.metadata()?);
+        self.file = file;
+        self.position = 0;
+        self.read_available()
+    }
+}
+
+/// Blocks between polls, so iteration never ends on its own.
+impl Iterator for Tail {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(line) = self.ready.pop_front() {
+                return Some(Ok(line));
+            }
+            if let Err(e) = self.read_available().and_then(|_| self.check_rotation()) {
+                return Some(Err(e));
+            }
+            if self.ready.is_empty() {
+                thread::sleep(self.poll_interval);
+            }
+        }
+    }
+}
+
+/// Feeds every line `tail` produces through `pipeline`, forever (or until an error).
+/// The building block for `tail -f | grep`-style commands.
+pub fn follow(
+    pipeline: &mut Pipeline,
+    tail: Tail,
+    mut on_record: impl FnMut(Record),
+) -> Result<(), SourceError> {
+    let mut out = Vec::new();
+    for line in tail {
+        pipeline.process_line(&line?, &mut out)?;
+        out.drain(..).for_each(&mut on_record);
+    }
+    Ok(())
+}
+
+/// What makes a file "the same file" across polls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileIdentity {
+    device: u64,
+    inode: u64,
+}
+
+impl FileIdentity {
+    #[cfg(unix)]
+    fn of(metadata: &Metadata) -> Option<FileIdentity> {
+        use std::os::unix::fs::MetadataExt;
+        Some(FileIdentity { device: metadata.dev(), inode: metadata.ino() })
+    }
+
+    /// Without inodes only truncation can be detected.
+    #[cfg(not(unix))]
+    fn of(_metadata: &Metadata) -> Option<FileIdentity> {
+        None
+    }
+}
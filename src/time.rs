@@ -1,52 +1,545 @@
 // src/pro/time_parser.rs
 
-use chrono::{DateTime, Utc, TimeZone};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc, Weekday};
 use humantime::parse_duration;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::time::SystemTime;
+use std::borrow::Cow;
 
 /// Parses a user-provided time string into a DateTime object.
-/// Handles both relative times ("1h ago") and absolute timestamps.
+/// Handles relative times ("1h ago", "in 2h", "-3d"), calendar expressions ("today",
+/// "yesterday", "last monday", "start of week") and absolute timestamps.
 pub fn parse_time_string(time_str: &str) -> Result<DateTime<Utc>, String> {
-    if time_str.to_lowercase() == "now" {
-        return Ok(Utc::now());
+    parse_time_string_at(time_str, Utc::now())
+}
+
+/// Like [`parse_time_string`], with relative expressions resolved against `now`.
+///
+/// Calendar expressions work on UTC days and resolve to midnight: `today`, `yesterday`,
+/// `tomorrow`, `last <weekday>` / `next <weekday>` (strictly before / after today), and
+/// `start of hour|day|week|month|year` (weeks start on Monday).
+pub fn parse_time_string_at(time_str: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    parse_time_string_with(time_str, now, TimeOptions::default_ref())
+}
+
+/// Like [`parse_time_string_at`], with calendar days and naive timestamps in
+/// `options.naive_zone`.
+pub fn parse_time_string_with(
+    time_str: &str,
+    now: DateTime<Utc>,
+    options: &TimeOptions,
+) -> Result<DateTime<Utc>, String> {
+    let lower = time_str.trim().to_lowercase();
+    if lower == "now" {
+        return Ok(now);
     }
 
-    // Try parsing as a relative duration (e.g., "15m", "2h ago")
-    let clean_str = time_str.strip_suffix(" ago").unwrap_or(time_str);
-    if let Ok(duration) = parse_duration(clean_str) {
-        let now = SystemTime::now();
-        let target_time = now - duration;
-        return Ok(target_time.into());
+    // Relative durations: "15m", "2h ago" and "-3d" are in the past, "in 2h" and "+2h" ahead
+    let (clean_str, future) = match lower.strip_prefix("in ").or_else(|| lower.strip_prefix('+')) {
+        Some(ahead) => (ahead, true),
+        None => (lower.strip_suffix(" ago").or_else(|| lower.strip_prefix('-')).unwrap_or(&lower), false),
+    };
+    if let Ok(duration) = parse_duration(clean_str.trim()) {
+        let duration = chrono::Duration::from_std(duration).map_err(|e| e.to_string())?;
+        let target_time = if future { now.checked_add_signed(duration) } else { now.checked_sub_signed(duration) };
+        return target_time.ok_or_else(|| format!("Time out of range: {}", time_str));
     }
 
-    // Try parsing as an absolute timestamp (RFC3339 / ISO 8601)
-    if let Ok(datetime) = DateTime::parse_from_rfc3339(time_str) {
-        return Ok(datetime.with_timezone(&Utc));
+    if let Some(datetime) = parse_calendar(&lower, now, options) {
+        return Ok(datetime);
+    }
+
+    // Try parsing as an absolute timestamp (RFC3339, ISO 8601 and friends)
+    if let Some(datetime) = parse_absolute_with(time_str, options) {
+        return Ok(datetime);
     }
 
     Err(format!("Could not parse time string: {}", time_str))
 }
 
-/// Extracts and parses a timestamp from a JSON log entry.
-/// Tries a list of common timestamp field names.
-pub fn extract_and_parse_timestamp(value: &Value) -> Option<DateTime<Utc>> {
-    const COMMON_KEYS: [&str; 3] = ["timestamp", "ts", "@timestamp"];
-
-    for key in COMMON_KEYS {
-        if let Some(ts_value) = value.get(key) {
-            if let Some(ts_str) = ts_value.as_str() {
-                // Parse string timestamp
-                if let Ok(datetime) = DateTime::parse_from_rfc3339(ts_str) {
-                    return Some(datetime.with_timezone(&Utc));
-                }
-            } else if let Some(ts_unix) = ts_value.as_i64() {
-                // Parse Unix timestamp (seconds)
-                if let Some(datetime) = Utc.timestamp_opt(ts_unix, 0).single() {
-                    return Some(datetime);
+/// Whether `time_str` is relative to the current time: `now`, `1d ago`, `in 2h` or a
+/// calendar expression such as `today` or `start of week`. Bare durations (`15m`) aren't,
+/// since they also read as durations.
+pub fn is_relative_time(time_str: &str) -> bool {
+    let lower = time_str.trim().to_lowercase();
+    let offset = lower.strip_suffix(" ago").or_else(|| lower.strip_prefix("in "));
+    lower == "now"
+        || offset.is_some_and(|duration| parse_duration(duration.trim()).is_ok())
+        || parse_calendar(&lower, Utc::now(), TimeOptions::default_ref()).is_some()
+}
+
+/// `today`, `last monday`, `start of week`, ... (see [`parse_time_string_at`]).
+fn parse_calendar(expr: &str, now: DateTime<Utc>, options: &TimeOptions) -> Option<DateTime<Utc>> {
+    let local_now = match options.naive_zone {
+        NaiveZone::Utc => now.naive_utc(),
+        NaiveZone::Local => now.with_timezone(&Local).naive_local(),
+        NaiveZone::Fixed(offset) => now.with_timezone(&offset).naive_local(),
+    };
+    let today = local_now.date();
+    let midnight = |date: NaiveDate| options.from_naive(date.and_hms_opt(0, 0, 0)?);
+
+    let words: Vec<&str> = expr.split_whitespace().collect();
+    match words.as_slice() {
+        ["today"] => midnight(today),
+        ["yesterday"] => midnight(today.pred_opt()?),
+        ["tomorrow"] => midnight(today.succ_opt()?),
+        [direction @ ("last" | "next"), day] => {
+            let weekday = day.parse::<Weekday>().ok()?;
+            let mut date = today;
+            loop {
+                date = if *direction == "last" { date.pred_opt()? } else { date.succ_opt()? };
+                if date.weekday() == weekday {
+                    return midnight(date);
                 }
             }
         }
+        ["start", "of", unit] => match *unit {
+            "hour" => options.from_naive(today.and_hms_opt(local_now.hour(), 0, 0)?),
+            "day" => midnight(today),
+            "week" => midnight(today - chrono::Duration::days(today.weekday().num_days_from_monday().into())),
+            "month" => midnight(today.with_day(1)?),
+            "year" => midnight(NaiveDate::from_ymd_opt(today.year(), 1, 1)?),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Calendar units for [`truncate`], in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeUnit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    /// Weeks start on Monday.
+    Week,
+}
+
+/// The start of the `unit` containing `dt`: `truncate(12:34:56, Hour)` is 12:00:00.
+pub fn truncate(dt: DateTime<Utc>, unit: TimeUnit) -> DateTime<Utc> {
+    let step = match unit {
+        TimeUnit::Second => 1,
+        TimeUnit::Minute => 60,
+        TimeUnit::Hour => 3600,
+        TimeUnit::Day => 86_400,
+        TimeUnit::Week => {
+            // 1970-01-01 was a Thursday; shift so buckets begin on Mondays.
+            let monday = DateTime::UNIX_EPOCH - chrono::Duration::days(3);
+            return bucket_start(dt, monday, std::time::Duration::from_secs(7 * 86_400));
+        }
+    };
+    round_to_bucket(dt, std::time::Duration::from_secs(step))
+}
+
+/// The start of the `step`-wide bucket containing `dt`, with buckets aligned to the
+/// Unix epoch. This is how [`Histogram`](crate::aggregate::histogram::Histogram) buckets
+/// entries, so buckets computed here line up with its. A zero step returns `dt`.
+pub fn round_to_bucket(dt: DateTime<Utc>, step: std::time::Duration) -> DateTime<Utc> {
+    bucket_start(dt, DateTime::UNIX_EPOCH, step)
+}
+
+fn bucket_start(dt: DateTime<Utc>, origin: DateTime<Utc>, step: std::time::Duration) -> DateTime<Utc> {
+    let step = step.as_nanos() as i128;
+    if step == 0 {
+        return dt;
+    }
+    let nanos = |t: DateTime<Utc>| i128::from(t.timestamp()) * 1_000_000_000 + i128::from(t.timestamp_subsec_nanos());
+    let offset = nanos(dt) - nanos(origin);
+    let start = nanos(origin) + offset.div_euclid(step) * step;
+    let secs = start.div_euclid(1_000_000_000) as i64;
+    let subsec = start.rem_euclid(1_000_000_000) as u32;
+    Utc.timestamp_opt(secs, subsec).single().unwrap_or(dt)
+}
+
+/// The zone a timestamp without an offset (`2024-01-01 12:00:00`) is read in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NaiveZone {
+    Utc,
+    /// The zone of the machine running the parser.
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl NaiveZone {
+    /// Parses `utc`, `local`, or an offset like `+02:00`, `-0500` or `+2`.
+    pub fn parse(s: &str) -> Option<NaiveZone> {
+        let s = s.trim();
+        match s.to_ascii_lowercase().as_str() {
+            "utc" | "z" | "gmt" => return Some(NaiveZone::Utc),
+            "local" => return Some(NaiveZone::Local),
+            _ => {}
+        }
+        let (sign, rest) = match s.as_bytes().first()? {
+            b'+' => (1, &s[1..]),
+            b'-' => (-1, &s[1..]),
+            _ => return None,
+        };
+        let (hours, minutes) = match rest.split_once(':') {
+            Some((h, m)) => (h, m),
+            None if rest.len() == 4 => rest.split_at(2),
+            None => (rest, "0"),
+        };
+        let seconds = hours.parse::<i32>().ok()? * 3600 + minutes.parse::<i32>().ok()? * 60;
+        FixedOffset::east_opt(sign * seconds).map(NaiveZone::Fixed)
+    }
+}
+
+impl std::fmt::Display for NaiveZone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NaiveZone::Utc => f.write_str("utc"),
+            NaiveZone::Local => f.write_str("local"),
+            NaiveZone::Fixed(offset) => write!(f, "{}", offset),
+        }
+    }
+}
+
+/// Serialized as the text [`NaiveZone::parse`] reads: `"utc"`, `"local"` or `"+02:00"`.
+impl Serialize for NaiveZone {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for NaiveZone {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        NaiveZone::parse(&s).ok_or_else(|| serde::de::Error::custom(format!("invalid time zone: {}", s)))
+    }
+}
+
+/// How timestamps are read. Carried by [`ParseOptions`](crate::parsers::ParseOptions)
+/// and [`QueryOptions`](crate::query::QueryOptions); the free functions of this module
+/// use the defaults.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeOptions {
+    /// Zone for timestamps that carry no offset, e.g. a server's local-time logs.
+    /// Defaults to UTC.
+    pub naive_zone: NaiveZone,
+    /// Unit of numeric epochs. Defaults to guessing from the magnitude.
+    pub epoch_unit: EpochUnit,
+    /// `chrono` strftime patterns (`%d.%m.%Y %H:%M:%S`) for timestamps in a field, as
+    /// `(field, pattern)` pairs; see [`with_format`](Self::with_format).
+    pub formats: Vec<(String, String)>,
+    /// The present, for timestamps without a year (BSD syslog's `Jan  2 15:04:05`).
+    /// Defaults to the clock; queries set their own `now`.
+    pub now: Option<DateTime<Utc>>,
+}
+
+impl TimeOptions {
+    pub const fn new() -> Self {
+        TimeOptions { naive_zone: NaiveZone::Utc, epoch_unit: EpochUnit::Auto, formats: Vec::new(), now: None }
+    }
+
+    /// Adds a `chrono` strftime pattern for timestamps in `field`.
+    ///
+    /// Values in that field are tried against the field's patterns, in the order added,
+    /// before the built-in formats: by [`extract_timestamp_with`] (which also looks at
+    /// `field`) and by queries, which treat the field as a timestamp and parse
+    /// literals compared to it (`logtime > "01.02.2024 00:00:00"`) with the same patterns.
+    /// Patterns without an offset are anchored to [`naive_zone`](Self::naive_zone);
+    /// date-only patterns at midnight.
+    ///
+    /// Errors on patterns `chrono` can't use.
+    pub fn with_format(mut self, field: impl Into<String>, format: impl Into<String>) -> Result<Self, String> {
+        let format = format.into();
+        if StrftimeItems::new(&format).any(|item| matches!(item, Item::Error)) {
+            return Err(format!("Invalid timestamp format: {}", format));
+        }
+        self.formats.push((field.into(), format));
+        Ok(self)
+    }
+
+    /// Whether a format was added for `field`.
+    pub fn has_format(&self, field: &str) -> bool {
+        self.formats.iter().any(|(f, _)| f == field)
+    }
+
+    /// Shared default options, for the free functions.
+    pub(crate) fn default_ref() -> &'static TimeOptions {
+        static DEFAULT: TimeOptions = TimeOptions::new();
+        &DEFAULT
+    }
+
+    /// Anchors a timestamp without an offset to [`naive_zone`](Self::naive_zone). Local
+    /// times skipped by a DST change have no instant and yield `None`; repeated ones take
+    /// the earlier instant.
+    pub fn from_naive(&self, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self.naive_zone {
+            NaiveZone::Utc => Some(naive.and_utc()),
+            NaiveZone::Local => Local.from_local_datetime(&naive).earliest().map(|dt| dt.with_timezone(&Utc)),
+            NaiveZone::Fixed(offset) => offset.from_local_datetime(&naive).single().map(|dt| dt.with_timezone(&Utc)),
+        }
+    }
+
+    /// Reads `Jan  2 15:04:05`-style text, which has no year, as the latest such time not
+    /// more than a day after [`now`](Self::now): usually in now's year, but December lines
+    /// read in early January land in the previous one.
+    pub fn from_yearless(&self, s: &str) -> Option<DateTime<Utc>> {
+        let now = self.now.unwrap_or_else(Utc::now);
+        let in_year = |year: i32| {
+            let naive = NaiveDateTime::parse_from_str(&format!("{} {}", year, s), "%Y %b %e %H:%M:%S").ok()?;
+            self.from_naive(naive)
+        };
+        match in_year(now.year()) {
+            Some(datetime) if datetime <= now + chrono::Duration::days(1) => Some(datetime),
+            _ => in_year(now.year() - 1),
+        }
+    }
+}
+
+impl Default for TimeOptions {
+    fn default() -> Self {
+        TimeOptions::new()
+    }
+}
+
+/// Anchors a timestamp without an offset to UTC; see [`TimeOptions::from_naive`].
+pub fn from_naive(naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    TimeOptions::default_ref().from_naive(naive)
+}
+
+/// Zoned formats tried after RFC 3339, as `chrono` patterns.
+const ZONED_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S%.f%:z",
+    "%Y-%m-%d %H:%M:%S%.f%z",
+    "%Y-%m-%dT%H:%M:%S%.f%z",
+    "%d/%b/%Y:%H:%M:%S %z",
+    "%Y%m%dT%H%M%S%z",
+];
+
+/// Formats without a zone, anchored with [`TimeOptions::from_naive`].
+const NAIVE_FORMATS: &[&str] = &["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f", "%Y%m%dT%H%M%S"];
+
+/// Parses an absolute timestamp, trying in order:
+///
+/// 1. RFC 3339 / ISO 8601 with an offset: `2024-01-01T12:00:00Z`, `2024-01-01 12:00:00+02:00`
+/// 2. Other zoned forms: `2024-01-01 12:00:00 +0200`-style offsets without a colon, Common
+///    Log Format `01/Jan/2024:12:00:00 +0000`, compact `20240101T120000Z`
+/// 3. RFC 2822: `Mon, 01 Jan 2024 12:00:00 +0000`
+/// 4. ISO 8601 without a zone: `2024-01-01T12:00:00`, `2024-01-01 12:00:00.123`, `20240101T120000`
+/// 5. BSD syslog `Jan  2 15:04:05`, placed in a year by [`TimeOptions::from_yearless`]
+/// 6. A bare date, `2024-01-01`, at midnight
+///
+/// Timestamps without a zone are read as UTC.
+pub fn parse_absolute(time_str: &str) -> Option<DateTime<Utc>> {
+    parse_absolute_with(time_str, TimeOptions::default_ref())
+}
+
+/// Like [`parse_absolute`], with timestamps without a zone anchored to
+/// `options.naive_zone`.
+pub fn parse_absolute_with(time_str: &str, options: &TimeOptions) -> Option<DateTime<Utc>> {
+    let s = time_str.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    // chrono's `%z` doesn't take `Z`, so compact UTC times get an explicit offset.
+    let zoned = match s.strip_suffix('Z') {
+        Some(utc) if utc.len() == 15 => Cow::Owned(format!("{}+0000", utc)),
+        _ => Cow::Borrowed(s),
+    };
+    if let Some(datetime) = ZONED_FORMATS.iter().find_map(|f| DateTime::parse_from_str(&zoned, f).ok()) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    if let Ok(datetime) = DateTime::parse_from_rfc2822(s) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    if let Some(naive) = NAIVE_FORMATS.iter().find_map(|f| NaiveDateTime::parse_from_str(s, f).ok()) {
+        return options.from_naive(naive);
+    }
+    if s.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        if let Some(datetime) = options.from_yearless(s) {
+            return Some(datetime);
+        }
+    }
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    options.from_naive(date.and_hms_opt(0, 0, 0)?)
+}
+
+/// Parses `s` with the formats `options` has for `field`.
+fn parse_custom(field: &str, s: &str, options: &TimeOptions) -> Option<DateTime<Utc>> {
+    options.formats.iter().filter(|(f, _)| f == field).find_map(|(_, format)| parse_with_format(s, format, options))
+}
+
+/// Parses `s` with one strftime pattern, zoned, naive (anchored to `options.naive_zone`)
+/// or date-only.
+pub fn parse_with_format(s: &str, format: &str, options: &TimeOptions) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if let Ok(datetime) = DateTime::parse_from_str(s, format) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(s, format) {
+        return options.from_naive(naive);
+    }
+    let date = NaiveDate::parse_from_str(s, format).ok()?;
+    options.from_naive(date.and_hms_opt(0, 0, 0)?)
+}
+
+/// Parses a timestamp found in `field`: the field's formats in `options` first, then
+/// [`parse_timestamp_value_with`].
+pub fn parse_field_timestamp(field: &str, ts_value: &Value, options: &TimeOptions) -> Option<DateTime<Utc>> {
+    if let Some(datetime) = ts_value.as_str().and_then(|s| parse_custom(field, s, options)) {
+        return Some(datetime);
+    }
+    parse_timestamp_value_with(ts_value, options)
+}
+
+/// Like [`parse_time_string_with`], for a query literal compared to `field`: the field's
+/// formats in `options` are tried first.
+pub fn parse_time_string_for(
+    field: &str,
+    time_str: &str,
+    now: DateTime<Utc>,
+    options: &TimeOptions,
+) -> Result<DateTime<Utc>, String> {
+    match parse_custom(field, time_str, options) {
+        Some(datetime) => Ok(datetime),
+        None => parse_time_string_with(time_str, now, options),
+    }
+}
+
+/// Timestamp fields tried by [`extract_and_parse_timestamp`], in order.
+pub const COMMON_KEYS: &[&str] = &["timestamp", "ts", "@timestamp", "time", "datetime", "eventTime", "_ts", "date"];
+
+/// Extracts and parses a timestamp from a JSON log entry.
+/// Tries [`COMMON_KEYS`]; other keys go to [`extract_timestamp_with_keys`], or to
+/// `ParseOptions::timestamp_keys` and `QueryOptions::timestamp_fields`.
+/// Mongo-style `{"$date": ...}` wrappers are looked through.
+pub fn extract_and_parse_timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    extract_timestamp_with(value, TimeOptions::default_ref())
+}
+
+/// Like [`extract_and_parse_timestamp`], with the timestamp read per `options` and the
+/// fields it has formats for tried last.
+pub fn extract_timestamp_with(value: &Value, options: &TimeOptions) -> Option<DateTime<Utc>> {
+    extract_timestamp_with_keys(value, COMMON_KEYS, options).or_else(|| {
+        options
+            .formats
+            .iter()
+            .find_map(|(field, format)| parse_with_format(lookup(value, field)?.as_str()?, format, options))
+    })
+}
+
+/// Like [`extract_and_parse_timestamp`], but tries the caller's keys (names, dotted
+/// paths or JSON pointers) in order, read per `options`.
+pub fn extract_timestamp_with_keys<S: AsRef<str>>(
+    value: &Value,
+    keys: &[S],
+    options: &TimeOptions,
+) -> Option<DateTime<Utc>> {
+    keys.iter().find_map(|key| parse_field_timestamp(key.as_ref(), lookup(value, key.as_ref())?, options))
+}
+
+/// A key, else a dotted path through nested objects, else a JSON pointer.
+fn lookup<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    if key.starts_with('/') {
+        return value.pointer(key);
+    }
+    value.get(key).or_else(|| {
+        key.contains('.').then(|| key.split('.').try_fold(value, |current, segment| current.get(segment)))?
+    })
+}
+
+/// Unit of a numeric (Unix epoch) timestamp.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EpochUnit {
+    /// Guessed from the magnitude: values up to 10^11 are seconds (good until the year
+    /// 5138), up to 10^14 milliseconds, up to 10^17 microseconds, above that nanoseconds.
+    #[default]
+    Auto,
+    Seconds,
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl EpochUnit {
+    /// Nanoseconds per unit, for a given magnitude under `Auto`.
+    fn nanos_per_unit(self, magnitude: f64) -> f64 {
+        match self {
+            EpochUnit::Seconds => 1e9,
+            EpochUnit::Millis => 1e6,
+            EpochUnit::Micros => 1e3,
+            EpochUnit::Nanos => 1.0,
+            EpochUnit::Auto if magnitude < 1e11 => 1e9,
+            EpochUnit::Auto if magnitude < 1e14 => 1e6,
+            EpochUnit::Auto if magnitude < 1e17 => 1e3,
+            EpochUnit::Auto => 1.0,
+        }
+    }
+}
+
+/// Parses a single field value as a timestamp.
+/// Accepts the string formats of [`parse_absolute`], `{"$date": ...}` wrappers and Unix
+/// epochs (numbers or numeric strings, integer or fractional) in seconds, milliseconds,
+/// microseconds or nanoseconds, told apart by magnitude (see [`EpochUnit::Auto`]).
+pub fn parse_timestamp_value(ts_value: &Value) -> Option<DateTime<Utc>> {
+    parse_timestamp_value_with(ts_value, TimeOptions::default_ref())
+}
+
+/// Like [`parse_timestamp_value`], with numeric values read in `options.epoch_unit` and
+/// naive strings anchored to `options.naive_zone`.
+pub fn parse_timestamp_value_with(ts_value: &Value, options: &TimeOptions) -> Option<DateTime<Utc>> {
+    let unit = options.epoch_unit;
+    match ts_value {
+        Value::Object(map) => parse_timestamp_value_with(map.get("$date")?, options),
+        Value::String(ts_str) => {
+            // Parse string timestamp
+            parse_epoch_str(ts_str, unit).or_else(|| parse_absolute_with(ts_str, options))
+        }
+        Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(int), _) => parse_epoch_int(int, unit),
+            (None, Some(float)) => parse_epoch_float(float, unit),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Integers are converted exactly, so nanosecond epochs keep their precision. Whole
+/// seconds and the remainder are kept apart, so second epochs reach past 2262, where
+/// nanoseconds since 1970 overflow an `i64`.
+fn parse_epoch_int(epoch: i64, unit: EpochUnit) -> Option<DateTime<Utc>> {
+    let nanos_per_unit = unit.nanos_per_unit(epoch.unsigned_abs() as f64) as i64;
+    let per_second = 1_000_000_000 / nanos_per_unit;
+    let subsec = epoch.rem_euclid(per_second) * nanos_per_unit;
+    Utc.timestamp_opt(epoch.div_euclid(per_second), subsec as u32).single()
+}
+
+/// Rounded to the microsecond, about the precision an `f64` epoch in seconds carries.
+fn parse_epoch_float(epoch: f64, unit: EpochUnit) -> Option<DateTime<Utc>> {
+    if !epoch.is_finite() {
+        return None;
+    }
+    let micros = (epoch * unit.nanos_per_unit(epoch.abs()) / 1e3).round();
+    if micros.abs() >= (i64::MAX / 1000) as f64 {
+        return None;
+    }
+    Utc.timestamp_micros(micros as i64).single()
+}
+
+/// Fewest integer digits a string needs to be taken as an epoch when the unit is guessed:
+/// `100000000` seconds is 1973, while shorter numbers are more likely years or counters.
+const MIN_EPOCH_DIGITS: usize = 9;
+
+/// `"1700000000"` or `"1700000000.123"`; other strings aren't epochs. With
+/// [`EpochUnit::Auto`], numbers shorter than nine digits (`"2024"`) aren't either.
+fn parse_epoch_str(s: &str, unit: EpochUnit) -> Option<DateTime<Utc>> {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+        return None;
+    }
+    let integer_digits = digits.split('.').next().unwrap_or_default().len();
+    if unit == EpochUnit::Auto && integer_digits < MIN_EPOCH_DIGITS {
+        return None;
+    }
+    match s.parse::<i64>() {
+        Ok(int) => parse_epoch_int(int, unit),
+        Err(_) => parse_epoch_float(s.parse().ok()?, unit),
     }
-    None
 }
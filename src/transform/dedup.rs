@@ -0,0 +1,134 @@
+// File: src/transform/dedup.rs
+
+use super::{insert_fields, message};
+use crate::aggregate::group::key_value;
+use crate::pipeline::{Record, Transform};
+use serde_json::json;
+use std::collections::VecDeque;
+
+/// Field added to every emitted entry, counting the entries it stands for.
+pub const REPEAT_COUNT_FIELD: &str = "repeat_count";
+
+/// What makes two entries "the same".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DedupKey {
+    /// Equal values in all of these fields (dotted paths and JSON pointers allowed).
+    Fields(Vec<String>),
+    /// Equal message once numbers, ids and addresses are masked (see [`fingerprint`]).
+    Fingerprint,
+}
+
+/// Collapses repeated entries into one representative carrying a `repeat_count`.
+///
+/// The first entry of each run is kept. Structured entries get the count as a field;
+/// unstructured lines that repeated become `{"message": ..., "repeat_count": n}`.
+pub struct Dedup {
+    key: DedupKey,
+    window: usize,
+    seen: u64,
+    groups: VecDeque<Group>,
+}
+
+struct Group {
+    key: String,
+    record: Record,
+    count: u64,
+    last_seen: u64,
+}
+
+impl Dedup {
+    /// Collapses runs of back-to-back duplicates, like `uniq -c`.
+    pub fn consecutive(key: DedupKey) -> Self {
+        Dedup::windowed(key, 1)
+    }
+
+    /// Collapses duplicates separated by fewer than `window` other entries, so interleaved
+    /// storms collapse too. Representatives are emitted in first-seen order once their key
+    /// has not repeated for `window` entries; at most `window` are held at once.
+    pub fn windowed(key: DedupKey, window: usize) -> Self {
+        Dedup { key, window: window.max(1), seen: 0, groups: VecDeque::new() }
+    }
+
+    fn key_for(&self, record: &Record) -> String {
+        match &self.key {
+            DedupKey::Fields(fields) => match record.entry.value() {
+                Some(value) => fields
+                    .iter()
+                    .map(|field| key_value(value, field))
+                    .collect::<Vec<_>>()
+                    .join("\u{1f}"),
+                None => record.raw().to_string(),
+            },
+            DedupKey::Fingerprint => fingerprint(message(record)),
+        }
+    }
+
+    fn release_expired(&mut self, out: &mut Vec<Record>) {
+        while let Some(front) = self.groups.front() {
+            let expired = self.seen - front.last_seen >= self.window as u64;
+            if !expired && self.groups.len() <= self.window {
+                break;
+            }
+            if let Some(group) = self.groups.pop_front() {
+                out.push(group.into_record());
+            }
+        }
+    }
+}
+
+impl Transform for Dedup {
+    fn process(&mut self, record: Record, out: &mut Vec<Record>) {
+        self.seen += 1;
+        let key = self.key_for(&record);
+        if let Some(group) = self.groups.iter_mut().find(|group| group.key == key) {
+            group.count += 1;
+            group.last_seen = self.seen;
+        } else {
+            self.groups.push_back(Group { key, record, count: 1, last_seen: self.seen });
+        }
+        self.release_expired(out);
+    }
+
+    fn finish(&mut self, out: &mut Vec<Record>) {
+        out.extend(self.groups.drain(..).map(Group::into_record));
+    }
+}
+
+impl Group {
+    fn into_record(self) -> Record {
+        let mut record = self.record;
+        let keep_plain = !record.entry.is_structured() && self.count == 1;
+        if !keep_plain {
+            insert_fields(&mut record, [(REPEAT_COUNT_FIELD.to_string(), json!(self.count))]);
+        }
+        record
+    }
+}
+
+/// Masks the variable parts of a message so near-identical lines compare equal:
+/// every word containing a digit (numbers, ids, hashes, IPs, UUIDs) becomes `#`.
+///
+/// `"took 15ms for user 8f3a2c"` and `"took 9ms for user 11b0e4"` share the fingerprint
+/// `"took # for user #"`.
+pub fn fingerprint(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut word = String::new();
+    let flush = |word: &mut String, out: &mut String| {
+        if word.chars().any(|c| c.is_ascii_digit()) {
+            out.push('#');
+        } else {
+            out.push_str(word);
+        }
+        word.clear();
+    };
+    for c in text.chars() {
+        if c.is_alphanumeric() || c == '_' || c == '-' {
+            word.push(c);
+        } else {
+            flush(&mut word, &mut out);
+            out.push(c);
+        }
+    }
+    flush(&mut word, &mut out);
+    out
+}
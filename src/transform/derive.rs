@@ -0,0 +1,390 @@
+// File: src/transform/derive.rs
+
+use super::insert_fields;
+use crate::aggregate::numeric_value;
+use crate::pipeline::{Record, Transform};
+use crate::query::{self, QueryError};
+use serde_json::Value;
+
+/// Adds a computed field to every entry before it is queried.
+///
+/// `derive latency_s = num(duration_ms) / 1000` or `derive url_path = split(request, " ")[1]`.
+/// Expressions support numbers, quoted strings, field names (dotted paths, `text` for the
+/// raw line), `+ - * / %` with the usual precedence, parentheses, `[index]` on arrays
+/// (negative counts from the end) and the functions `num`, `str`, `lower`, `upper`,
+/// `trim`, `len`, `split` and `round`. `+` concatenates when either side is non-numeric text.
+///
+/// When the expression can't be computed for an entry (a missing field, a non-numeric
+/// operand, division by zero) the field is left unset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Derive {
+    target: String,
+    expr: DeriveExpr,
+}
+
+/// A parsed derive expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeriveExpr {
+    Literal(Value),
+    Field(String),
+    Neg(Box<DeriveExpr>),
+    Binary(Box<DeriveExpr>, BinaryOp, Box<DeriveExpr>),
+    Index(Box<DeriveExpr>, Box<DeriveExpr>),
+    Call(String, Vec<DeriveExpr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+const FUNCTIONS: &[(&str, usize)] = &[
+    ("num", 1),
+    ("str", 1),
+    ("lower", 1),
+    ("upper", 1),
+    ("trim", 1),
+    ("len", 1),
+    ("split", 2),
+    ("round", 1),
+];
+
+impl Derive {
+    pub fn new(target: impl Into<String>, expr: DeriveExpr) -> Self {
+        Derive { target: target.into(), expr }
+    }
+
+    /// Parses `[derive] <field> = <expression>`.
+    pub fn parse(spec: &str) -> Result<Self, QueryError> {
+        let spec = spec.trim();
+        let spec = spec.strip_prefix("derive ").unwrap_or(spec);
+        let Some((target, expr)) = spec.split_once('=') else {
+            return Err(QueryError::InvalidFormat(format!(
+                "Expected 'derive <field> = <expression>'. Got: '{}'",
+                spec
+            )));
+        };
+        let target = target.trim();
+        if target.is_empty() || target.contains(char::is_whitespace) {
+            return Err(QueryError::InvalidFormat(format!("Invalid derived field name '{}'", target)));
+        }
+        Ok(Derive::new(target, DeriveExpr::parse(expr)?))
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+}
+
+impl Transform for Derive {
+    fn process(&mut self, mut record: Record, out: &mut Vec<Record>) {
+        if let Some(value) = self.expr.evaluate(&record) {
+            insert_fields(&mut record, [(self.target.clone(), value)]);
+        }
+        out.push(record);
+    }
+}
+
+impl DeriveExpr {
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.expr()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(QueryError::InvalidFormat(format!(
+                "Unexpected '{:?}' in derive expression '{}'",
+                token,
+                input.trim()
+            ))),
+        }
+    }
+
+    /// Computes the expression for one record; `None` when it can't be computed.
+    pub fn evaluate(&self, record: &Record) -> Option<Value> {
+        match self {
+            DeriveExpr::Literal(value) => Some(value.clone()),
+            DeriveExpr::Field(name) if name == "text" => Some(Value::from(record.raw())),
+            DeriveExpr::Field(name) => query::resolve_field(record.value(), name).cloned(),
+            DeriveExpr::Neg(inner) => number(-numeric_value(&inner.evaluate(record)?)?),
+            DeriveExpr::Binary(lhs, op, rhs) => binary(lhs.evaluate(record)?, *op, rhs.evaluate(record)?),
+            DeriveExpr::Index(target, index) => {
+                let Value::Array(items) = target.evaluate(record)? else { return None };
+                let index = numeric_value(&index.evaluate(record)?)? as i64;
+                let index = if index < 0 { items.len() as i64 + index } else { index };
+                items.get(usize::try_from(index).ok()?).cloned()
+            }
+            DeriveExpr::Call(name, args) => {
+                let args: Vec<Value> = args.iter().map(|arg| arg.evaluate(record)).collect::<Option<_>>()?;
+                call(name, &args)
+            }
+        }
+    }
+}
+
+fn binary(lhs: Value, op: BinaryOp, rhs: Value) -> Option<Value> {
+    if let (Some(a), Some(b)) = (numeric_value(&lhs), numeric_value(&rhs)) {
+        return match op {
+            BinaryOp::Add => number(a + b),
+            BinaryOp::Sub => number(a - b),
+            BinaryOp::Mul => number(a * b),
+            BinaryOp::Div if b != 0.0 => number(a / b),
+            BinaryOp::Rem if b != 0.0 => number(a % b),
+            BinaryOp::Div | BinaryOp::Rem => None,
+        };
+    }
+    match op {
+        BinaryOp::Add if lhs.is_string() || rhs.is_string() => {
+            Some(Value::String(format!("{}{}", text(&lhs), text(&rhs))))
+        }
+        _ => None,
+    }
+}
+
+fn call(name: &str, args: &[Value]) -> Option<Value> {
+    match (name, args) {
+        ("num", [v]) => number(numeric_value(v)?),
+        ("str", [v]) => Some(Value::String(text(v))),
+        ("lower", [v]) => Some(Value::String(text(v).to_lowercase())),
+        ("upper", [v]) => Some(Value::String(text(v).to_uppercase())),
+        ("trim", [v]) => Some(Value::String(text(v).trim().to_string())),
+        ("len", [Value::Array(items)]) => Some(Value::from(items.len())),
+        ("len", [v]) => Some(Value::from(text(v).chars().count())),
+        ("split", [v, sep]) => {
+            let sep = text(sep);
+            let parts: Vec<Value> = if sep.is_empty() {
+                text(v).split_whitespace().map(Value::from).collect()
+            } else {
+                text(v).split(sep.as_str()).map(Value::from).collect()
+            };
+            Some(Value::Array(parts))
+        }
+        ("round", [v]) => number(numeric_value(v)?.round()),
+        _ => None,
+    }
+}
+
+/// A value as plain text: strings without their quotes, everything else as JSON.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Whole results become JSON integers so `1500 / 1000 * 2` prints as `3`, not `3.0`.
+fn number(n: f64) -> Option<Value> {
+    if !n.is_finite() {
+        return None;
+    }
+    if n.fract() == 0.0 && n.abs() < 9_007_199_254_740_992.0 {
+        return Some(Value::from(n as i64));
+    }
+    Some(Value::from(n))
+}
+
+// --- Tokenizer and recursive-descent parser ---
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Ident(String),
+    Op(BinaryOp),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '\\')) => s.extend(chars.next().map(|(_, c)| c)),
+                        Some((_, q)) if q == c => break,
+                        Some((_, ch)) => s.push(ch),
+                        None => {
+                            return Err(QueryError::InvalidFormat(format!(
+                                "Unterminated string in derive expression '{}'",
+                                input.trim()
+                            )))
+                        }
+                    }
+                }
+                tokens.push(Token::Str(s));
+            }
+            c if c.is_ascii_digit() => {
+                let mut end = start;
+                while let Some(&(i, ch)) = chars.peek() {
+                    if !(ch.is_ascii_digit() || ch == '.') {
+                        break;
+                    }
+                    end = i + ch.len_utf8();
+                    chars.next();
+                }
+                let literal = &input[start..end];
+                let n = literal.parse::<f64>().map_err(|_| {
+                    QueryError::InvalidFormat(format!("Invalid number '{}' in derive expression", literal))
+                })?;
+                tokens.push(Token::Number(n));
+            }
+            c if c.is_alphabetic() || matches!(c, '_' | '@' | '$') => {
+                let mut end = start;
+                while let Some(&(i, ch)) = chars.peek() {
+                    if !(ch.is_alphanumeric() || matches!(ch, '_' | '@' | '$' | '.')) {
+                        break;
+                    }
+                    end = i + ch.len_utf8();
+                    chars.next();
+                }
+                tokens.push(Token::Ident(input[start..end].to_string()));
+            }
+            _ => {
+                chars.next();
+                tokens.push(match c {
+                    '+' => Token::Op(BinaryOp::Add),
+                    '-' => Token::Op(BinaryOp::Sub),
+                    '*' => Token::Op(BinaryOp::Mul),
+                    '/' => Token::Op(BinaryOp::Div),
+                    '%' => Token::Op(BinaryOp::Rem),
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '[' => Token::LBracket,
+                    ']' => Token::RBracket,
+                    ',' => Token::Comma,
+                    other => {
+                        return Err(QueryError::InvalidFormat(format!(
+                            "Unexpected character '{}' in derive expression '{}'",
+                            other,
+                            input.trim()
+                        )))
+                    }
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), QueryError> {
+        match self.advance() {
+            Some(token) if token == expected => Ok(()),
+            other => Err(QueryError::InvalidFormat(format!(
+                "Expected {:?} in derive expression, found {:?}",
+                expected, other
+            ))),
+        }
+    }
+
+    /// `expr := term (('+' | '-') term)*`
+    fn expr(&mut self) -> Result<DeriveExpr, QueryError> {
+        let mut lhs = self.term()?;
+        while let Some(Token::Op(op @ (BinaryOp::Add | BinaryOp::Sub))) = self.peek().cloned() {
+            self.pos += 1;
+            lhs = DeriveExpr::Binary(Box::new(lhs), op, Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    /// `term := unary (('*' | '/' | '%') unary)*`
+    fn term(&mut self) -> Result<DeriveExpr, QueryError> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Op(op @ (BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem))) = self.peek().cloned() {
+            self.pos += 1;
+            lhs = DeriveExpr::Binary(Box::new(lhs), op, Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    /// `unary := '-' unary | postfix`
+    fn unary(&mut self) -> Result<DeriveExpr, QueryError> {
+        if self.peek() == Some(&Token::Op(BinaryOp::Sub)) {
+            self.pos += 1;
+            return Ok(DeriveExpr::Neg(Box::new(self.unary()?)));
+        }
+        self.postfix()
+    }
+
+    /// `postfix := primary ('[' expr ']')*`
+    fn postfix(&mut self) -> Result<DeriveExpr, QueryError> {
+        let mut expr = self.primary()?;
+        while self.peek() == Some(&Token::LBracket) {
+            self.pos += 1;
+            let index = self.expr()?;
+            self.expect(Token::RBracket)?;
+            expr = DeriveExpr::Index(Box::new(expr), Box::new(index));
+        }
+        Ok(expr)
+    }
+
+    /// `primary := number | string | ident '(' args ')' | ident | '(' expr ')'`
+    fn primary(&mut self) -> Result<DeriveExpr, QueryError> {
+        match self.advance() {
+            Some(Token::Number(n)) => Ok(DeriveExpr::Literal(number(n).unwrap_or(Value::Null))),
+            Some(Token::Str(s)) => Ok(DeriveExpr::Literal(Value::String(s))),
+            Some(Token::LParen) => {
+                let expr = self.expr()?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                self.pos += 1;
+                let mut args = Vec::new();
+                if self.peek() != Some(&Token::RParen) {
+                    args.push(self.expr()?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.pos += 1;
+                        args.push(self.expr()?);
+                    }
+                }
+                self.expect(Token::RParen)?;
+                match FUNCTIONS.iter().find(|(f, _)| *f == name) {
+                    Some((_, arity)) if *arity == args.len() => Ok(DeriveExpr::Call(name, args)),
+                    Some((_, arity)) => Err(QueryError::InvalidFormat(format!(
+                        "Function '{}' takes {} argument(s), got {}",
+                        name,
+                        arity,
+                        args.len()
+                    ))),
+                    None => Err(QueryError::InvalidFormat(format!("Unknown function '{}'", name))),
+                }
+            }
+            Some(Token::Ident(name)) => Ok(DeriveExpr::Field(name)),
+            other => Err(QueryError::InvalidFormat(format!(
+                "Expected a value in derive expression, found {:?}",
+                other
+            ))),
+        }
+    }
+}
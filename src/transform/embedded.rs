@@ -0,0 +1,99 @@
+// File: src/transform/embedded.rs
+
+use crate::parsers::{json, logfmt};
+use crate::pipeline::{Record, Transform};
+use serde_json::{Map, Value};
+
+/// String fields that commonly carry a serialized payload (Docker's `log`, app `message`s).
+const DEFAULT_FIELDS: &[&str] = &["message", "log", "msg"];
+
+/// Parses JSON objects (and, optionally, logfmt) stringified inside string fields
+/// and merges their keys into the entry.
+///
+/// `{"log": "{\"user\":\"bob\",\"status\":500}"}` gains `user` and `status`, so they
+/// can be queried directly. Merged keys get the configured prefix (none by default);
+/// existing keys are never overwritten and the original field is kept.
+#[derive(Debug, Clone)]
+pub struct Embedded {
+    fields: Vec<String>,
+    prefix: String,
+    logfmt: bool,
+}
+
+impl Default for Embedded {
+    fn default() -> Self {
+        Embedded::new()
+    }
+}
+
+impl Embedded {
+    /// Checks `message`, `log` and `msg` for embedded JSON and logfmt.
+    pub fn new() -> Self {
+        Embedded {
+            fields: DEFAULT_FIELDS.iter().map(|f| f.to_string()).collect(),
+            prefix: String::new(),
+            logfmt: true,
+        }
+    }
+
+    /// Checks these top-level fields instead of the defaults.
+    pub fn with_fields(mut self, fields: Vec<String>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// Prepended to every merged key, e.g. `"payload."` or `"inner_"`.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Whether to also accept logfmt payloads (on by default).
+    pub fn with_logfmt(mut self, logfmt: bool) -> Self {
+        self.logfmt = logfmt;
+        self
+    }
+
+    /// Merges every embedded payload found in `entry`'s configured fields.
+    pub fn apply(&self, entry: &mut Value) {
+        let Value::Object(map) = entry else { return };
+        let payloads: Vec<Map<String, Value>> = self
+            .fields
+            .iter()
+            .filter_map(|field| map.get(field).and_then(Value::as_str))
+            .filter_map(|text| self.parse_payload(text))
+            .collect();
+        for payload in payloads {
+            for (key, value) in payload {
+                map.entry(format!("{}{}", self.prefix, key)).or_insert(value);
+            }
+        }
+    }
+
+    fn parse_payload(&self, text: &str) -> Option<Map<String, Value>> {
+        let text = text.trim();
+        if text.starts_with('{') && text.ends_with('}') {
+            if let Ok(Value::Object(map)) = json::parse_json_line(text) {
+                return Some(map);
+            }
+        }
+        if self.logfmt && text.contains('=') {
+            // Only whole-string logfmt: a bare word would be a valueless key, i.e. prose.
+            if let Ok(Value::Object(map)) = logfmt::parse_logfmt_line(text) {
+                if !map.is_empty() && map.values().all(|v| !v.is_null()) {
+                    return Some(map);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Transform for Embedded {
+    fn process(&mut self, mut record: Record, out: &mut Vec<Record>) {
+        if let Some(value) = record.entry.value_mut() {
+            self.apply(value);
+        }
+        out.push(record);
+    }
+}
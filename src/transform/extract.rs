@@ -0,0 +1,80 @@
+// File: src/transform/extract.rs
+
+use super::insert_fields;
+use crate::pipeline::{Record, Transform};
+use crate::query::{self, QueryError};
+use regex::Regex;
+use serde_json::Value;
+
+/// Pulls new fields out of a text field with a regex's named capture groups.
+///
+/// `extract message /user=(?P<user>\w+) took=(?P<took_ms>\d+)ms/` adds `user` and
+/// `took_ms` to every entry whose `message` matches. Without a field (or with the
+/// `text` pseudo-field) the raw line is searched, and unstructured lines become
+/// `{"message": <line>, ...captures}` so the captures are queryable.
+/// Captures overwrite existing fields of the same name; unmatched groups are skipped.
+#[derive(Debug, Clone)]
+pub struct Extract {
+    field: Option<String>,
+    regex: Regex,
+}
+
+impl Extract {
+    /// Searches `field`, or the raw line when `None`.
+    pub fn new(field: Option<String>, pattern: &str) -> Result<Self, QueryError> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| QueryError::InvalidFormat(format!("Invalid regex '{}': {}", pattern, e)))?;
+        if regex.capture_names().flatten().next().is_none() {
+            return Err(QueryError::InvalidFormat(format!(
+                "Extract pattern '{}' has no named capture groups (?P<name>...)",
+                pattern
+            )));
+        }
+        let field = field.filter(|f| f != "text");
+        Ok(Extract { field, regex })
+    }
+
+    /// Parses `[extract] [field] /pattern/` (the pattern may also be quoted).
+    pub fn parse(spec: &str) -> Result<Self, QueryError> {
+        let spec = spec.trim();
+        let spec = spec.strip_prefix("extract ").unwrap_or(spec).trim_start();
+        if spec.starts_with(['/', '"', '\'']) {
+            return Extract::new(None, query::unquote(spec));
+        }
+        match spec.split_once(char::is_whitespace) {
+            Some((field, pattern)) => Extract::new(Some(field.to_string()), query::unquote(pattern)),
+            None => Err(QueryError::InvalidFormat(format!(
+                "Expected 'extract [field] /pattern/'. Got: '{}'",
+                spec
+            ))),
+        }
+    }
+
+    /// The captures found in `record`, in pattern order.
+    fn captures(&self, record: &Record) -> Option<Vec<(String, Value)>> {
+        let text = match &self.field {
+            Some(field) => match query::resolve_field(record.value(), field)? {
+                Value::String(s) => s.as_str(),
+                _ => return None,
+            },
+            None => record.raw(),
+        };
+        let caps = self.regex.captures(text)?;
+        Some(
+            self.regex
+                .capture_names()
+                .flatten()
+                .filter_map(|name| caps.name(name).map(|m| (name.to_string(), Value::from(m.as_str()))))
+                .collect(),
+        )
+    }
+}
+
+impl Transform for Extract {
+    fn process(&mut self, mut record: Record, out: &mut Vec<Record>) {
+        if let Some(captures) = self.captures(&record) {
+            insert_fields(&mut record, captures);
+        }
+        out.push(record);
+    }
+}
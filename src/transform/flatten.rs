@@ -0,0 +1,70 @@
+// File: src/transform/flatten.rs
+
+use crate::pipeline::{Record, Transform};
+use serde_json::{Map, Value};
+
+/// Nesting levels flattened by default; deeper values are kept as they are.
+pub const DEFAULT_MAX_DEPTH: usize = 16;
+
+/// Converts nested objects and arrays into a single-level object with dotted and
+/// bracketed keys: `{"http":{"request":{"method":"GET"}}}` becomes
+/// `{"http.request.method":"GET"}` and `{"errors":[{"code":7}]}` becomes `{"errors[0].code":7}`.
+///
+/// Values nested deeper than `max_depth` levels, and empty objects or arrays, are kept whole.
+/// Non-object values are returned unchanged.
+pub fn flatten(value: &Value, max_depth: usize) -> Value {
+    let Value::Object(map) = value else { return value.clone() };
+    let mut out = Map::new();
+    for (key, nested) in map {
+        flatten_into(key.clone(), nested, max_depth, &mut out);
+    }
+    Value::Object(out)
+}
+
+fn flatten_into(path: String, value: &Value, depth_left: usize, out: &mut Map<String, Value>) {
+    if depth_left > 0 {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, nested) in map {
+                    flatten_into(format!("{}.{}", path, key), nested, depth_left - 1, out);
+                }
+                return;
+            }
+            Value::Array(items) if !items.is_empty() => {
+                for (i, nested) in items.iter().enumerate() {
+                    flatten_into(format!("{}[{}]", path, i), nested, depth_left - 1, out);
+                }
+                return;
+            }
+            _ => {}
+        }
+    }
+    out.insert(path, value.clone());
+}
+
+/// Pipeline stage applying [`flatten`] to every structured entry.
+#[derive(Debug, Clone)]
+pub struct Flatten {
+    max_depth: usize,
+}
+
+impl Default for Flatten {
+    fn default() -> Self {
+        Flatten::new(DEFAULT_MAX_DEPTH)
+    }
+}
+
+impl Flatten {
+    pub fn new(max_depth: usize) -> Self {
+        Flatten { max_depth }
+    }
+}
+
+impl Transform for Flatten {
+    fn process(&mut self, mut record: Record, out: &mut Vec<Record>) {
+        if let Some(value) = record.entry.value_mut() {
+            *value = flatten(value, self.max_depth);
+        }
+        out.push(record);
+    }
+}
@@ -0,0 +1,110 @@
+// File: src/transform/geoip.rs
+
+use super::insert_fields;
+use crate::pipeline::{Record, Transform};
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use serde_json::Value;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+
+/// Fields checked for a client address, in order.
+const DEFAULT_IP_FIELDS: &[&str] = &["remote_addr", "src_ip", "client_ip", "ip"];
+
+#[derive(Debug)]
+pub enum GeoIpError {
+    Database(MaxMindDBError),
+}
+
+impl fmt::Display for GeoIpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GeoIpError::Database(e) => write!(f, "GeoIP database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for GeoIpError {}
+
+impl From<MaxMindDBError> for GeoIpError {
+    fn from(e: MaxMindDBError) -> Self {
+        GeoIpError::Database(e)
+    }
+}
+
+/// Looks up the first IP field of each entry in user-supplied MaxMind databases
+/// (GeoLite2-City or -Country, and optionally GeoLite2-ASN) and adds `geo.country`
+/// (ISO code), `geo.city` (English name) and `geo.asn` fields.
+///
+/// Values may carry a port (`1.2.3.4:5678`, `[::1]:443`). Addresses missing from the
+/// database, such as private ranges, are left unenriched.
+pub struct GeoIp {
+    city: Reader<Vec<u8>>,
+    asn: Option<Reader<Vec<u8>>>,
+    fields: Vec<String>,
+}
+
+impl GeoIp {
+    /// Loads a GeoLite2-City (or -Country) database.
+    pub fn open(city_database: impl AsRef<Path>) -> Result<Self, GeoIpError> {
+        Ok(GeoIp {
+            city: Reader::open_readfile(city_database)?,
+            asn: None,
+            fields: DEFAULT_IP_FIELDS.iter().map(|f| f.to_string()).collect(),
+        })
+    }
+
+    /// Also loads a GeoLite2-ASN database, enabling `geo.asn`.
+    pub fn with_asn_database(mut self, asn_database: impl AsRef<Path>) -> Result<Self, GeoIpError> {
+        self.asn = Some(Reader::open_readfile(asn_database)?);
+        Ok(self)
+    }
+
+    /// Checks these fields for an address instead of the defaults.
+    pub fn with_fields(mut self, fields: Vec<String>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// The `geo.*` fields for one address; empty when it isn't in the databases.
+    pub fn lookup(&self, ip: IpAddr) -> Vec<(String, Value)> {
+        let mut fields = Vec::new();
+        if let Ok(city) = self.city.lookup::<geoip2::City>(ip) {
+            if let Some(iso) = city.country.and_then(|c| c.iso_code) {
+                fields.push(("geo.country".to_string(), Value::from(iso)));
+            }
+            if let Some(name) = city.city.and_then(|c| c.names).and_then(|names| names.get("en").copied()) {
+                fields.push(("geo.city".to_string(), Value::from(name)));
+            }
+        }
+        if let Some(asn) = &self.asn {
+            if let Ok(Some(number)) = asn.lookup::<geoip2::Asn>(ip).map(|a| a.autonomous_system_number) {
+                fields.push(("geo.asn".to_string(), Value::from(number)));
+            }
+        }
+        fields
+    }
+
+    fn address(&self, entry: &Value) -> Option<IpAddr> {
+        self.fields.iter().find_map(|field| parse_ip(entry.get(field)?.as_str()?))
+    }
+}
+
+impl Transform for GeoIp {
+    fn process(&mut self, mut record: Record, out: &mut Vec<Record>) {
+        if let Some(ip) = self.address(record.value()) {
+            let fields = self.lookup(ip);
+            if !fields.is_empty() {
+                insert_fields(&mut record, fields);
+            }
+        }
+        out.push(record);
+    }
+}
+
+fn parse_ip(s: &str) -> Option<IpAddr> {
+    let s = s.trim();
+    s.parse::<IpAddr>()
+        .ok()
+        .or_else(|| s.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
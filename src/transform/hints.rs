@@ -0,0 +1,32 @@
+// File: src/transform/hints.rs
+
+use super::insert_fields;
+use crate::parsers::plain::line_hints;
+use crate::pipeline::{Record, Transform};
+
+/// Gives plain-text lines the metadata found at their start (see [`line_hints`]),
+/// so time-range and level queries also work on unstructured logs.
+///
+/// `2024-05-01 12:00:03 [ERROR] [db] connection lost` becomes
+/// `{"message": <line>, "timestamp": "2024-05-01T12:00:03+00:00", "level": "error", "tags": ["db"]}`.
+/// Lines without any hint, and structured entries, pass through untouched.
+#[derive(Debug, Clone, Default)]
+pub struct Hints;
+
+impl Hints {
+    pub fn new() -> Self {
+        Hints
+    }
+}
+
+impl Transform for Hints {
+    fn process(&mut self, mut record: Record, out: &mut Vec<Record>) {
+        if !record.entry.is_structured() {
+            let hints = line_hints(record.raw());
+            if !hints.is_empty() {
+                insert_fields(&mut record, hints.fields());
+            }
+        }
+        out.push(record);
+    }
+}
@@ -0,0 +1,37 @@
+// File: src/transform/mod.rs
+
+pub mod dedup;
+pub mod derive;
+pub mod embedded;
+pub mod extract;
+pub mod flatten;
+pub mod hints;
+pub mod redact;
+pub mod remap;
+pub mod sample;
+pub mod select;
+pub mod user_agent;
+
+// MaxMind lookups, behind the 'geoip' feature
+#[cfg(feature = "geoip")]
+pub mod geoip;
+
+use crate::pipeline::Record;
+use serde_json::{Map, Value};
+
+/// The message text of a record: its message field when structured, else the raw line.
+pub(crate) fn message(record: &Record) -> &str {
+    record.entry.message()
+}
+
+/// Adds fields to a record, turning an unstructured line into `{"message": <line>}` first.
+pub(crate) fn insert_fields(record: &mut Record, fields: impl IntoIterator<Item = (String, Value)>) {
+    if !record.entry.is_structured() {
+        let mut map = Map::new();
+        map.insert("message".to_string(), Value::from(record.raw()));
+        record.entry.set_value(Value::Object(map));
+    }
+    if let Some(Value::Object(map)) = record.entry.value_mut() {
+        map.extend(fields);
+    }
+}
@@ -0,0 +1,163 @@
+// File: src/transform/redact.rs
+
+use crate::pipeline::{Record, Transform};
+use crate::query::QueryError;
+use regex::Regex;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+/// Replacement written over every detected value.
+pub const DEFAULT_MASK: &str = "[REDACTED]";
+
+/// Kinds of sensitive data recognised out of the box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detector {
+    Email,
+    Ipv4,
+    Ipv6,
+    /// 13–19 digit card numbers (spaces or dashes allowed) that pass the Luhn check.
+    CreditCard,
+    /// `Bearer <token>` credentials; the scheme word is kept.
+    BearerToken,
+    /// AWS access key ids (`AKIA...`, `ASIA...`).
+    AwsAccessKey,
+}
+
+impl Detector {
+    pub const ALL: &'static [Detector] = &[
+        Detector::Email,
+        Detector::Ipv4,
+        Detector::Ipv6,
+        Detector::CreditCard,
+        Detector::BearerToken,
+        Detector::AwsAccessKey,
+    ];
+
+    /// Parses a detector name (case-insensitive), e.g. from a config file.
+    pub fn parse(s: &str) -> Option<Detector> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "email" => Some(Detector::Email),
+            "ipv4" => Some(Detector::Ipv4),
+            "ipv6" => Some(Detector::Ipv6),
+            "credit_card" | "card" => Some(Detector::CreditCard),
+            "bearer_token" | "bearer" => Some(Detector::BearerToken),
+            "aws_access_key" | "aws" => Some(Detector::AwsAccessKey),
+            _ => None,
+        }
+    }
+
+    fn regex(&self) -> &'static Regex {
+        static PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+        let patterns = PATTERNS.get_or_init(|| {
+            [
+                r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b",
+                r"\b(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\b",
+                r"(?i)\b(?:[0-9a-f]{1,4}:){7}[0-9a-f]{1,4}\b|(?i)\b(?:[0-9a-f]{1,4}:){1,7}:(?:[0-9a-f]{1,4}(?::[0-9a-f]{1,4}){0,6})?\b|::(?:[0-9a-f]{1,4}:){0,6}[0-9a-f]{1,4}\b",
+                r"\b\d(?:[ -]?\d){12,18}\b",
+                r"(?i)(\bbearer\s+)[a-z0-9\-._~+/]+=*",
+                r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b",
+            ]
+            .iter()
+            .map(|p| Regex::new(p).expect("Invalid redaction regex"))
+            .collect()
+        });
+        &patterns[*self as usize]
+    }
+}
+
+/// Masks sensitive values in the structured entry and the raw line, so filtered
+/// logs can be shared.
+///
+/// Every string in the entry is scanned (keys are left alone), as is the raw line.
+#[derive(Debug, Clone)]
+pub struct Redact {
+    detectors: Vec<Detector>,
+    custom: Vec<Regex>,
+    mask: String,
+}
+
+impl Default for Redact {
+    fn default() -> Self {
+        Redact::new()
+    }
+}
+
+impl Redact {
+    /// Redacts with every built-in detector.
+    pub fn new() -> Self {
+        Redact::with_detectors(Detector::ALL.to_vec())
+    }
+
+    pub fn with_detectors(detectors: Vec<Detector>) -> Self {
+        Redact { detectors, custom: Vec::new(), mask: DEFAULT_MASK.to_string() }
+    }
+
+    /// Also masks every match of `pattern`.
+    pub fn add_pattern(&mut self, pattern: &str) -> Result<(), QueryError> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| QueryError::InvalidFormat(format!("Invalid regex '{}': {}", pattern, e)))?;
+        self.custom.push(regex);
+        Ok(())
+    }
+
+    pub fn set_mask(&mut self, mask: impl Into<String>) {
+        self.mask = mask.into();
+    }
+
+    /// Returns `text` with every detected value masked.
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for detector in &self.detectors {
+            let regex = detector.regex();
+            if !regex.is_match(&text) {
+                continue;
+            }
+            text = match detector {
+                Detector::BearerToken => regex.replace_all(&text, format!("${{1}}{}", self.mask)).into_owned(),
+                Detector::CreditCard => regex
+                    .replace_all(&text, |caps: &regex::Captures| {
+                        if luhn_valid(&caps[0]) { self.mask.clone() } else { caps[0].to_string() }
+                    })
+                    .into_owned(),
+                _ => regex.replace_all(&text, regex::NoExpand(&self.mask)).into_owned(),
+            };
+        }
+        for regex in &self.custom {
+            text = regex.replace_all(&text, regex::NoExpand(&self.mask)).into_owned();
+        }
+        text
+    }
+
+    /// Masks every string in `value`, at any depth.
+    pub fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::String(s) => *s = self.redact(s),
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_value(item)),
+            Value::Object(map) => map.values_mut().for_each(|item| self.redact_value(item)),
+            _ => {}
+        }
+    }
+}
+
+impl Transform for Redact {
+    fn process(&mut self, mut record: Record, out: &mut Vec<Record>) {
+        let raw = self.redact(record.raw());
+        record.entry.set_raw(raw);
+        if let Some(value) = record.entry.value_mut() {
+            self.redact_value(value);
+        }
+        out.push(record);
+    }
+}
+
+/// Luhn checksum over the digits of `s`, ignoring separators.
+fn luhn_valid(s: &str) -> bool {
+    let digits: Vec<u32> = s.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| if i % 2 == 1 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d })
+        .sum();
+    sum.is_multiple_of(10)
+}
@@ -0,0 +1,126 @@
+// File: src/transform/remap.rs
+
+use crate::pipeline::{Record, Transform};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Shipped sets of renames towards a common schema.
+///
+/// Generic keys such as `name` or `log` mean different things across sources and aren't
+/// mapped; add a [`Remap::rename`] for sources where they hold the logger or message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Profile {
+    /// The flat names loglens queries assume: `message`, `level`, `timestamp`, `logger`,
+    /// `trace_id`, `span_id`.
+    Canonical,
+    /// Elastic Common Schema: `message`, `log.level`, `@timestamp`, `service.name`, ...
+    Ecs,
+    /// OpenTelemetry log data model and semantic conventions: `body`, `severity_text`,
+    /// `timestamp`, `trace_id`, `http.request.method`, ...
+    OpenTelemetry,
+}
+
+impl Profile {
+    /// `(target, sources)` pairs; the first source present is renamed to the target.
+    fn mappings(&self) -> &'static [(&'static str, &'static [&'static str])] {
+        match self {
+            Profile::Canonical => &[
+                ("message", &["msg", "@m", "@mt", "Message", "body"]),
+                ("level", &["severity", "@l", "lvl", "loglevel", "log.level", "severity_text", "SeverityText", "Level"]),
+                ("timestamp", &["@t", "ts", "time", "@timestamp", "Timestamp", "datetime"]),
+                ("logger", &["logger_name", "log.logger", "Logger"]),
+                ("trace_id", &["traceId", "trace.id", "TraceId", "dd.trace_id"]),
+                ("span_id", &["spanId", "span.id", "SpanId", "dd.span_id"]),
+            ],
+            Profile::Ecs => &[
+                ("message", &["msg", "@m", "@mt", "Message", "body"]),
+                ("log.level", &["level", "severity", "@l", "lvl", "loglevel", "severity_text"]),
+                ("@timestamp", &["timestamp", "@t", "ts", "time", "datetime"]),
+                ("log.logger", &["logger", "logger_name"]),
+                ("service.name", &["service", "app", "service_name"]),
+                ("host.name", &["host", "hostname"]),
+                ("trace.id", &["trace_id", "traceId", "dd.trace_id"]),
+                ("span.id", &["span_id", "spanId", "dd.span_id"]),
+                ("error.message", &["error", "err"]),
+                ("http.request.method", &["method", "http_method"]),
+                ("http.response.status_code", &["status", "status_code", "http_status"]),
+                ("url.path", &["path", "uri", "url_path"]),
+                ("client.ip", &["remote_addr", "client_ip", "ip"]),
+                ("user_agent.original", &["http_user_agent", "user_agent", "ua"]),
+            ],
+            Profile::OpenTelemetry => &[
+                ("body", &["message", "msg", "@m", "@mt"]),
+                ("severity_text", &["level", "severity", "@l", "lvl", "loglevel", "log.level"]),
+                ("timestamp", &["@t", "ts", "time", "@timestamp", "datetime"]),
+                ("trace_id", &["traceId", "trace.id", "dd.trace_id"]),
+                ("span_id", &["spanId", "span.id", "dd.span_id"]),
+                ("service.name", &["service", "app", "service_name"]),
+                ("host.name", &["host", "hostname"]),
+                ("exception.message", &["error", "err"]),
+                ("http.request.method", &["method", "http_method"]),
+                ("http.response.status_code", &["status", "status_code", "http_status"]),
+                ("url.path", &["path", "uri", "url_path"]),
+                ("client.address", &["remote_addr", "client_ip", "ip"]),
+                ("user_agent.original", &["http_user_agent", "user_agent", "ua"]),
+            ],
+        }
+    }
+}
+
+/// Renames top-level keys so one query works across sources with different conventions.
+///
+/// Rules run in order. A rule only fires when its target key is not already present,
+/// so entries that already follow the schema are left alone. Targets are written as
+/// literal (possibly dotted) keys, which queries resolve like nested paths.
+#[derive(Debug, Clone, Default)]
+pub struct Remap {
+    rules: Vec<(String, Vec<String>)>,
+}
+
+impl Remap {
+    pub fn new() -> Self {
+        Remap::default()
+    }
+
+    /// A remap preloaded with a shipped profile.
+    pub fn profile(profile: Profile) -> Self {
+        Remap::new().with_profile(profile)
+    }
+
+    /// Adds a shipped profile's rules after the current ones.
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        for (target, sources) in profile.mappings() {
+            self.rules.push((target.to_string(), sources.iter().map(|s| s.to_string()).collect()));
+        }
+        self
+    }
+
+    /// Adds a rule renaming `from` to `to`.
+    pub fn rename(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.rules.push((to.into(), vec![from.into()]));
+        self
+    }
+
+    /// Applies every rule to `entry` in place.
+    pub fn apply(&self, entry: &mut Value) {
+        let Value::Object(map) = entry else { return };
+        for (target, sources) in &self.rules {
+            if map.contains_key(target) {
+                continue;
+            }
+            if let Some(value) = sources.iter().find_map(|source| map.remove(source)) {
+                map.insert(target.clone(), value);
+            }
+        }
+    }
+}
+
+impl Transform for Remap {
+    fn process(&mut self, mut record: Record, out: &mut Vec<Record>) {
+        if let Some(value) = record.entry.value_mut() {
+            self.apply(value);
+        }
+        out.push(record);
+    }
+}
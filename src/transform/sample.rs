@@ -0,0 +1,132 @@
+// File: src/transform/sample.rs
+
+use crate::aggregate::group::key_value;
+use crate::pipeline::{Record, Transform};
+
+/// Keeps the first `n` records and drops the rest.
+#[derive(Debug, Clone)]
+pub struct Head {
+    remaining: usize,
+}
+
+impl Head {
+    pub fn new(n: usize) -> Self {
+        Head { remaining: n }
+    }
+
+    /// True once `n` records have passed, so callers can stop reading early.
+    pub fn is_full(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+impl Transform for Head {
+    fn process(&mut self, record: Record, out: &mut Vec<Record>) {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            out.push(record);
+        }
+    }
+}
+
+/// Keeps a fixed fraction of records, chosen by hashing so the same input always
+/// yields the same sample.
+///
+/// By default the raw line is hashed. Hashing a field instead (`trace_id`, `user`)
+/// keeps or drops all records sharing its value together.
+#[derive(Debug, Clone)]
+pub struct Ratio {
+    threshold: u64,
+    field: Option<String>,
+}
+
+impl Ratio {
+    /// `ratio` is clamped to `0.0..=1.0`.
+    pub fn new(ratio: f64) -> Self {
+        let ratio = ratio.clamp(0.0, 1.0);
+        let threshold = if ratio >= 1.0 { u64::MAX } else { (ratio * u64::MAX as f64) as u64 };
+        Ratio { threshold, field: None }
+    }
+
+    /// Samples by the value of `field` rather than by the whole line.
+    pub fn by_field(ratio: f64, field: impl Into<String>) -> Self {
+        Ratio { field: Some(field.into()), ..Ratio::new(ratio) }
+    }
+
+    fn keeps(&self, record: &Record) -> bool {
+        let hash = match &self.field {
+            Some(field) => fnv1a(key_value(record.value(), field).as_bytes()),
+            None => fnv1a(record.raw().as_bytes()),
+        };
+        self.threshold == u64::MAX || hash < self.threshold
+    }
+}
+
+impl Transform for Ratio {
+    fn process(&mut self, record: Record, out: &mut Vec<Record>) {
+        if self.keeps(&record) {
+            out.push(record);
+        }
+    }
+}
+
+/// A uniform random sample of `n` records from the whole input (Algorithm R).
+///
+/// Holds at most `n` records and releases them, in input order, when the input ends.
+/// The generator is seeded, so a given seed and input always give the same sample.
+#[derive(Debug)]
+pub struct Reservoir {
+    size: usize,
+    seen: u64,
+    rng: SplitMix64,
+    kept: Vec<Record>,
+}
+
+impl Reservoir {
+    pub fn new(size: usize) -> Self {
+        Reservoir::with_seed(size, 0)
+    }
+
+    pub fn with_seed(size: usize, seed: u64) -> Self {
+        Reservoir { size, seen: 0, rng: SplitMix64(seed), kept: Vec::with_capacity(size) }
+    }
+}
+
+impl Transform for Reservoir {
+    fn process(&mut self, record: Record, _out: &mut Vec<Record>) {
+        self.seen += 1;
+        if self.kept.len() < self.size {
+            self.kept.push(record);
+            return;
+        }
+        let slot = self.rng.next_u64() % self.seen;
+        if let Some(kept) = self.kept.get_mut(slot as usize) {
+            *kept = record;
+        }
+    }
+
+    fn finish(&mut self, out: &mut Vec<Record>) {
+        let mut kept = std::mem::take(&mut self.kept);
+        kept.sort_by_key(|record| record.line_number);
+        out.extend(kept);
+    }
+}
+
+/// 64-bit FNV-1a: tiny and, unlike `DefaultHasher`, stable across Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// Small seeded generator for reproducible reservoirs.
+#[derive(Debug, Clone)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
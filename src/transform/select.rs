@@ -0,0 +1,73 @@
+// File: src/transform/select.rs
+
+use crate::pipeline::{Record, Transform};
+use crate::query::{self, QueryError};
+use serde_json::{Map, Value};
+
+/// Trims entries down to the requested fields, in the requested order.
+///
+/// `select timestamp, level, http.status as status` keeps three fields. Nested paths
+/// (dotted or JSON pointer) are looked up like in queries; without a rename the path
+/// itself becomes the output key. Fields an entry lacks are left out. Unstructured
+/// lines are treated as `{"message": <line>}`.
+#[derive(Debug, Clone)]
+pub struct Select {
+    fields: Vec<(String, String)>,
+}
+
+impl Select {
+    /// Keeps `fields` under their own names.
+    pub fn new(fields: Vec<String>) -> Self {
+        Select { fields: fields.into_iter().map(|field| (field.clone(), field)).collect() }
+    }
+
+    /// Keeps the value at `path` under the key `name`.
+    pub fn with_field(mut self, path: impl Into<String>, name: impl Into<String>) -> Self {
+        self.fields.push((path.into(), name.into()));
+        self
+    }
+
+    /// Parses `[select] field [as name], ...`.
+    pub fn parse(spec: &str) -> Result<Self, QueryError> {
+        let spec = spec.trim();
+        let spec = spec.strip_prefix("select ").unwrap_or(spec);
+        let mut select = Select { fields: Vec::new() };
+        for item in spec.split(',') {
+            let words: Vec<&str> = item.split_whitespace().collect();
+            let (path, name) = match words.as_slice() {
+                [path] => (*path, *path),
+                [path, kw, name] if kw.eq_ignore_ascii_case("as") => (*path, *name),
+                _ => {
+                    return Err(QueryError::InvalidFormat(format!(
+                        "Expected 'field' or 'field as name' in select list. Got: '{}'",
+                        item.trim()
+                    )))
+                }
+            };
+            select = select.with_field(query::unquote(path), query::unquote(name));
+        }
+        Ok(select)
+    }
+
+    /// The projected entry.
+    pub fn apply(&self, entry: &Value) -> Value {
+        let mut out = Map::new();
+        for (path, name) in &self.fields {
+            if let Some(value) = query::resolve_field(entry, path) {
+                out.insert(name.clone(), value.clone());
+            }
+        }
+        Value::Object(out)
+    }
+}
+
+impl Transform for Select {
+    fn process(&mut self, mut record: Record, out: &mut Vec<Record>) {
+        let projected = match record.entry.value() {
+            Some(value) => self.apply(value),
+            None => self.apply(&serde_json::json!({ "message": record.raw() })),
+        };
+        record.entry.set_value(projected);
+        out.push(record);
+    }
+}
@@ -0,0 +1,183 @@
+// File: src/transform/user_agent.rs
+
+use super::insert_fields;
+use crate::pipeline::{Record, Transform};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Fields checked for a user-agent string, in order.
+const DEFAULT_UA_FIELDS: &[&str] = &["http_user_agent", "user_agent", "user_agent.original", "ua"];
+
+/// Substrings (lowercase) of automated clients: crawlers, monitors, scripts and libraries.
+const BOT_MARKERS: &[&str] = &[
+    "bot", "crawl", "spider", "slurp", "scrapy", "facebookexternalhit", "headless",
+    "pingdom", "uptime", "monitor", "curl/", "wget/", "python-requests", "python-urllib",
+    "go-http-client", "java/", "okhttp", "libwww", "httpclient", "axios/", "node-fetch", "postman",
+];
+
+/// `(token, name)` pairs in priority order: several browsers also claim to be Chrome or Safari.
+const BROWSERS: &[(&str, &str)] = &[
+    ("Edg/", "Edge"),
+    ("EdgA/", "Edge"),
+    ("EdgiOS/", "Edge"),
+    ("Edge/", "Edge"),
+    ("OPR/", "Opera"),
+    ("Opera/", "Opera"),
+    ("SamsungBrowser/", "Samsung Internet"),
+    ("YaBrowser/", "Yandex"),
+    ("FxiOS/", "Firefox"),
+    ("Firefox/", "Firefox"),
+    ("CriOS/", "Chrome"),
+    ("Chrome/", "Chrome"),
+];
+
+/// What a user-agent string says about the client.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserAgent {
+    /// Browser, crawler or client library (`Chrome`, `Googlebot`, `curl`), if recognised.
+    pub browser: Option<String>,
+    pub version: Option<String>,
+    /// Operating system family (`Windows`, `macOS`, `iOS`, `Android`, `Linux`, `Chrome OS`).
+    pub os: Option<String>,
+    /// `desktop`, `mobile`, `tablet`, `bot` or `other`.
+    pub device: String,
+    pub is_bot: bool,
+}
+
+/// Parses a user-agent string with a small set of well-known rules: good enough to
+/// split traffic by browser, OS, device class and bots, without a regex database.
+pub fn parse_user_agent(ua: &str) -> UserAgent {
+    let lower = ua.to_ascii_lowercase();
+    let is_bot = BOT_MARKERS.iter().any(|marker| lower.contains(marker));
+
+    let (browser, version) = if is_bot {
+        bot_name(ua)
+    } else {
+        browser_name(ua)
+    };
+    let os = os_name(ua);
+
+    let device = if is_bot {
+        "bot"
+    } else if ua.contains("iPad") || ua.contains("Tablet") || (ua.contains("Android") && !ua.contains("Mobile")) {
+        "tablet"
+    } else if ua.contains("Mobi") || ua.contains("iPhone") {
+        "mobile"
+    } else if browser.is_some() || os.is_some() {
+        "desktop"
+    } else {
+        "other"
+    };
+
+    UserAgent { browser, version, os, device: device.to_string(), is_bot }
+}
+
+fn browser_name(ua: &str) -> (Option<String>, Option<String>) {
+    for (token, name) in BROWSERS {
+        if let Some(version) = version_after(ua, token) {
+            return (Some(name.to_string()), Some(version));
+        }
+    }
+    if ua.contains("Safari/") {
+        return (Some("Safari".to_string()), version_after(ua, "Version/"));
+    }
+    if let Some(version) = version_after(ua, "MSIE ").or_else(|| ua.contains("Trident/").then(|| version_after(ua, "rv:")).flatten()) {
+        return (Some("Internet Explorer".to_string()), Some(version));
+    }
+    (None, None)
+}
+
+/// The product token naming the bot: `Googlebot/2.1` in `Mozilla/5.0 (compatible; Googlebot/2.1; ...)`,
+/// or the leading `curl/8.4.0`.
+fn bot_name(ua: &str) -> (Option<String>, Option<String>) {
+    let product = ua
+        .split(|c: char| c.is_whitespace() || matches!(c, ';' | '(' | ')' | ','))
+        .filter(|token| token.contains('/') && !token.starts_with("Mozilla/") && !token.contains("://"))
+        .find(|token| {
+            let lower = token.to_ascii_lowercase();
+            BOT_MARKERS.iter().any(|marker| lower.contains(marker.trim_end_matches('/')))
+        })
+        .or_else(|| ua.split_whitespace().next().filter(|token| !token.starts_with("Mozilla/")));
+
+    match product.and_then(|p| p.split_once('/')) {
+        Some((name, version)) => (Some(name.to_string()), Some(version.to_string()).filter(|v| !v.is_empty())),
+        None => (product.map(str::to_string), None),
+    }
+}
+
+fn os_name(ua: &str) -> Option<String> {
+    let os = if ua.contains("Windows") {
+        "Windows"
+    } else if ua.contains("iPhone") || ua.contains("iPad") || ua.contains("iPod") {
+        "iOS"
+    } else if ua.contains("Android") {
+        "Android"
+    } else if ua.contains("CrOS") {
+        "Chrome OS"
+    } else if ua.contains("Mac OS X") || ua.contains("Macintosh") {
+        "macOS"
+    } else if ua.contains("Linux") {
+        "Linux"
+    } else {
+        return None;
+    };
+    Some(os.to_string())
+}
+
+/// The dotted version number right after `token`, if `token` occurs.
+fn version_after(ua: &str, token: &str) -> Option<String> {
+    let start = ua.find(token)? + token.len();
+    let version: String = ua[start..].chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+    let version = version.trim_end_matches('.');
+    (!version.is_empty()).then(|| version.to_string())
+}
+
+/// Adds `ua.browser`, `ua.version`, `ua.os`, `ua.device` and `ua.is_bot` from the
+/// first user-agent field present (`http_user_agent` by default).
+#[derive(Debug, Clone)]
+pub struct UserAgentEnrich {
+    fields: Vec<String>,
+}
+
+impl Default for UserAgentEnrich {
+    fn default() -> Self {
+        UserAgentEnrich::new()
+    }
+}
+
+impl UserAgentEnrich {
+    pub fn new() -> Self {
+        UserAgentEnrich { fields: DEFAULT_UA_FIELDS.iter().map(|f| f.to_string()).collect() }
+    }
+
+    /// Checks these fields for a user agent instead of the defaults.
+    pub fn with_fields(mut self, fields: Vec<String>) -> Self {
+        self.fields = fields;
+        self
+    }
+}
+
+impl Transform for UserAgentEnrich {
+    fn process(&mut self, mut record: Record, out: &mut Vec<Record>) {
+        let ua = self
+            .fields
+            .iter()
+            .find_map(|field| record.value().get(field).and_then(Value::as_str))
+            .filter(|ua| !ua.is_empty() && *ua != "-")
+            .map(parse_user_agent);
+
+        if let Some(ua) = ua {
+            insert_fields(
+                &mut record,
+                [
+                    ("ua.browser".to_string(), ua.browser.map_or(Value::Null, Value::from)),
+                    ("ua.version".to_string(), ua.version.map_or(Value::Null, Value::from)),
+                    ("ua.os".to_string(), ua.os.map_or(Value::Null, Value::from)),
+                    ("ua.device".to_string(), Value::from(ua.device)),
+                    ("ua.is_bot".to_string(), Value::from(ua.is_bot)),
+                ],
+            );
+        }
+        out.push(record);
+    }
+}
@@ -0,0 +1,49 @@
+// src/units.rs
+
+/// Splits a literal like `1.5s` into its numeric part and unit suffix.
+fn split_number_unit(s: &str) -> Option<(f64, &str)> {
+    let s = s.trim().trim_matches(|c| c == '"' || c == '\'');
+    let idx = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+        .unwrap_or(s.len());
+    let number = s[..idx].parse::<f64>().ok()?;
+    Some((number, s[idx..].trim()))
+}
+
+/// Parses a human duration literal (`250us`, `100ms`, `1.5s`, `2m`, `1h`, `1d`) into nanoseconds.
+/// Nanoseconds keep common literals exact when rescaled to another unit.
+/// A unit is required: bare numbers return `None`.
+pub fn parse_duration_nanos(s: &str) -> Option<f64> {
+    let (number, unit) = split_number_unit(s)?;
+    let scale = match unit {
+        "ns" => 1.0,
+        "us" | "µs" => 1e3,
+        "ms" => 1e6,
+        "s" | "sec" | "secs" => 1e9,
+        "m" | "min" | "mins" => 60e9,
+        "h" | "hr" | "hrs" => 3600e9,
+        "d" => 86400e9,
+        _ => return None,
+    };
+    Some(number * scale)
+}
+
+/// Parses a byte-size literal (`512B`, `10KB`, `1.5MB`, `1MiB`, `2GiB`) into bytes.
+/// SI units (`KB`, `MB`, ...) are powers of 1000, IEC units (`KiB`, `MiB`, ...) powers of 1024.
+/// Units are case-insensitive and required: bare numbers return `None`.
+pub fn parse_byte_size(s: &str) -> Option<f64> {
+    let (number, unit) = split_number_unit(s)?;
+    let scale = match unit.to_ascii_lowercase().as_str() {
+        "b" => 1.0,
+        "kb" => 1e3,
+        "mb" => 1e6,
+        "gb" => 1e9,
+        "tb" => 1e12,
+        "kib" => 1024.0,
+        "mib" => 1024.0 * 1024.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        "tib" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some(number * scale)
+}
@@ -0,0 +1,82 @@
+// src/version.rs
+
+use std::cmp::Ordering;
+
+/// A semantic version (`MAJOR.MINOR.PATCH[-PRERELEASE][+BUILD]`).
+///
+/// Parsing is lenient: a leading `v` is accepted and missing minor/patch
+/// components default to zero. Build metadata is ignored for ordering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Vec<String>,
+}
+
+impl Version {
+    pub fn parse(s: &str) -> Option<Version> {
+        let s = s.trim().trim_matches(|c| c == '"' || c == '\'');
+        let s = s.strip_prefix(['v', 'V']).unwrap_or(s);
+        let s = s.split('+').next()?;
+        let (core, pre) = match s.split_once('-') {
+            Some((core, pre)) => (core, pre.split('.').map(str::to_string).collect()),
+            None => (s, Vec::new()),
+        };
+
+        let mut parts = core.split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().map_or(Some(0), |p| p.parse().ok())?;
+        let patch = parts.next().map_or(Some(0), |p| p.parse().ok())?;
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(Version { major, minor, patch, pre })
+    }
+
+    /// True when `s` is spelled like a full version (`1.2.3`, `v2.0.0-rc.1`) rather than
+    /// a plain number, so comparisons can switch to version ordering automatically.
+    pub fn looks_like_version(s: &str) -> bool {
+        let s = s.trim().trim_matches(|c| c == '"' || c == '\'');
+        let core = s.strip_prefix(['v', 'V']).unwrap_or(s);
+        core.split(['-', '+']).next().is_some_and(|c| c.matches('.').count() == 2)
+            && Version::parse(s).is_some()
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                // A pre-release sorts before the release it precedes
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => compare_pre(&self.pre, &other.pre),
+            })
+    }
+}
+
+/// Compares pre-release identifiers: numeric ones numerically and below alphanumeric ones.
+fn compare_pre(a: &[String], b: &[String]) -> Ordering {
+    for (x, y) in a.iter().zip(b) {
+        let ord = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => x.cmp(y),
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    a.len().cmp(&b.len())
+}
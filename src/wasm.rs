@@ -1,52 +1,583 @@
 use wasm_bindgen::prelude::*;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use crate::{parsers, query, LogEntry};
+use crate::aggregate::group::{GroupBy, GroupedStats};
+use crate::aggregate::histogram::{Histogram, HistogramResult};
+use crate::aggregate::{Aggregator, Stats};
+use crate::parsers::{Format, LogEntry, ParseOptions};
+use crate::transform::redact::{Detector, Redact};
+use crate::{parsers, query, schema, suggest};
+use std::sync::OnceLock;
 
 // This struct helps the JavaScript frontend understand the result easily.
-// We derive Serialize so we can return it as a JSON string.
-#[derive(serde::Serialize)]
+// It is handed over as a plain JS object, so the frontend never has to JSON.parse it.
+#[derive(Serialize)]
 struct WasmResult {
     is_match: bool,
+    parsed: bool,              // False for plain text, which only text queries can match
     parsed_log: Option<Value>, // Returns the structured JSON/Logfmt object
-    error: Option<String>,     // Returns query syntax errors if any
-}
-
-#[wasm_bindgen]
-pub fn run_query(log_line: &str, query: &str) -> String {
-    // 1. Parse the log line (Automatic detection)
-    let entry = parsers::parse_log_line(log_line);
-
-    match entry {
-        LogEntry::Structured(value) => {
-            // 2. Run the query against the structured data
-            match query::evaluate(&value, log_line, query) {
-                Ok(is_match) => {
-                    let result = WasmResult {
-                        is_match,
-                        parsed_log: Some(value),
-                        error: None,
-                    };
-                    serde_json::to_string(&result).unwrap_or_default()
-                },
-                Err(e) => {
-                    // Query syntax error (e.g., missing quote)
-                    let result = WasmResult {
-                        is_match: false,
-                        parsed_log: Some(value), // We still return the data so the user sees how it was parsed
-                        error: Some(format!("Query Error: {}", e)),
-                    };
-                    serde_json::to_string(&result).unwrap_or_default()
+    error: Option<WasmError>,  // Returns query syntax errors if any
+}
+
+// Errors reach JS as `{ kind, message }` so the frontend can branch on `kind`
+// instead of matching message text.
+#[derive(Serialize)]
+struct WasmError {
+    kind: &'static str, // "query" (syntax or evaluation errors)
+    message: String,
+}
+
+impl WasmError {
+    fn query(e: query::QueryError) -> Self {
+        WasmError { kind: "query", message: e.to_string() }
+    }
+}
+
+// Serializes maps as plain objects (not ES `Map`s) so results look like JSON.parse output.
+fn to_js<T: Serialize>(value: &T) -> JsValue {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .unwrap_or(JsValue::NULL)
+}
+
+// The JS-side configuration object; every key is optional.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct WasmConfigSpec {
+    timestamp_keys: Vec<String>,            // Extra fields to read timestamps from
+    format: Option<String>,                 // Lock every line to one parser ("json", "nginx", ...)
+    case_insensitive_fields: Option<bool>,  // `level` also finds `Level` (default true)
+    redact: Vec<String>,                    // Detector names ("email", "ipv4", ...) or "all"
+    redact_patterns: Vec<String>,           // Extra regexes to mask
+    mask: Option<String>,                   // Replaces redacted values (default "[REDACTED]")
+}
+
+/// Parser and query settings built once on the JS side and passed to every call,
+/// e.g. `new WasmConfig({ format: "nginx", redact: ["email"] })`.
+///
+/// Redaction applies to the parsed objects handed back to JS; queries and highlight
+/// ranges still see the original line.
+#[wasm_bindgen]
+#[derive(Clone, Default)]
+pub struct WasmConfig {
+    parse: ParseOptions,
+    query: query::QueryOptions,
+    format: Option<Format>,
+    redact: Option<Redact>,
+}
+
+#[wasm_bindgen]
+impl WasmConfig {
+    #[wasm_bindgen(constructor)]
+    pub fn new(options: JsValue) -> Result<WasmConfig, JsValue> {
+        let spec: WasmConfigSpec = if options.is_undefined() || options.is_null() {
+            WasmConfigSpec::default()
+        } else {
+            serde_wasm_bindgen::from_value(options).map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?
+        };
+
+        let mut config = WasmConfig::default();
+        config.parse.timestamp_keys = spec.timestamp_keys.clone();
+        config.query.timestamp_fields.extend(spec.timestamp_keys);
+        if let Some(case_insensitive) = spec.case_insensitive_fields {
+            config.query.case_insensitive_fields = case_insensitive;
+        }
+        if let Some(name) = spec.format {
+            let format = Format::parse(&name).ok_or_else(|| JsValue::from_str(&format!("Unknown format '{}'", name)))?;
+            config.format = Some(format);
+        }
+
+        if !spec.redact.is_empty() || !spec.redact_patterns.is_empty() {
+            let mut detectors = Vec::new();
+            for name in &spec.redact {
+                if name == "all" {
+                    detectors.extend_from_slice(Detector::ALL);
+                    continue;
                 }
+                let detector = Detector::parse(name)
+                    .ok_or_else(|| JsValue::from_str(&format!("Unknown detector '{}'", name)))?;
+                detectors.push(detector);
+            }
+            let mut redact = Redact::with_detectors(detectors);
+            for pattern in &spec.redact_patterns {
+                redact.add_pattern(pattern).map_err(|e| JsValue::from_str(&e.to_string()))?;
             }
+            if let Some(mask) = spec.mask {
+                redact.set_mask(mask);
+            }
+            config.redact = Some(redact);
+        }
+        Ok(config)
+    }
+}
+
+impl WasmConfig {
+    // What the free functions use: automatic detection, default query options.
+    fn default_ref() -> &'static WasmConfig {
+        static DEFAULT: OnceLock<WasmConfig> = OnceLock::new();
+        DEFAULT.get_or_init(WasmConfig::default)
+    }
+
+    fn parse(&self, line: &str) -> LogEntry {
+        match self.format {
+            Some(format) => parsers::parse_log_line_as(line, format).unwrap_or_else(|| LogEntry::unstructured(line)),
+            None => parsers::parse_log_line_with(line, &self.parse),
+        }
+    }
+
+    fn matches(&self, entry: &LogEntry, query: &query::Query) -> Result<bool, query::QueryError> {
+        entry.matches_with_options(query, &self.query)
+    }
+
+    // The parsed object as handed to JS, redacted if configured.
+    fn output(&self, entry: LogEntry) -> Option<Value> {
+        let mut value = entry.into_value()?;
+        if let Some(redact) = &self.redact {
+            redact.redact_value(&mut value);
+        }
+        Some(value)
+    }
+}
+
+#[wasm_bindgen]
+pub fn run_query(log_line: &str, query: &str) -> JsValue {
+    run_query_with(log_line, query, WasmConfig::default_ref())
+}
+
+/// Like `run_query`, with a `WasmConfig`.
+#[wasm_bindgen]
+pub fn run_query_with(log_line: &str, query: &str, config: &WasmConfig) -> JsValue {
+    // 1. Parse the log line (Automatic detection, unless the config locks a format)
+    let entry = config.parse(log_line);
+
+    // 2. Run the query. Unstructured lines are still searched as text
+    //    (`error`, `text contains "timeout"`); field conditions just don't match them.
+    let result = match query::Query::parse(query).and_then(|query| config.matches(&entry, &query)) {
+        Ok(is_match) => WasmResult {
+            is_match,
+            parsed: entry.is_structured(),
+            parsed_log: config.output(entry),
+            error: None,
+        },
+        // Query syntax error (e.g., missing quote)
+        Err(e) => WasmResult {
+            is_match: false,
+            parsed: entry.is_structured(),
+            parsed_log: config.output(entry), // We still return the data so the user sees how it was parsed
+            error: Some(WasmError::query(e)),
         },
-        LogEntry::Unstructured(_) => {
-            // Parsing failed (not JSON or Logfmt)
-            let result = WasmResult {
-                is_match: false,
-                parsed_log: None,
-                error: Some("Could not parse log structure. Is it valid JSON or Logfmt?".to_string()),
+    };
+    to_js(&result)
+}
+
+// How a single line was parsed, for "preview" panes.
+#[derive(Serialize)]
+struct WasmParsedLine {
+    format: &'static str,      // Detected format name ("json", "logfmt", "nginx", "syslog", "plain")
+    parsed_log: Option<Value>, // The structured object (null for plain text)
+    timestamp: Option<String>, // Extracted timestamp as RFC 3339, if one was found
+}
+
+/// Parses a line without a query and reports its format, fields and timestamp.
+#[wasm_bindgen]
+pub fn parse_line(log_line: &str) -> JsValue {
+    parse_line_with(log_line, WasmConfig::default_ref())
+}
+
+/// Like `parse_line`, with a `WasmConfig`.
+#[wasm_bindgen]
+pub fn parse_line_with(log_line: &str, config: &WasmConfig) -> JsValue {
+    let entry = config.parse(log_line);
+    let result = WasmParsedLine {
+        format: entry.format().as_str(),
+        timestamp: entry.timestamp().map(|ts| ts.to_rfc3339()),
+        parsed_log: config.output(entry),
+    };
+    to_js(&result)
+}
+
+// Result of a batch call: one entry per matching line, in input order.
+#[derive(Serialize)]
+struct WasmBatchResult {
+    matches: Vec<usize>,             // 0-based indices of the matching lines
+    parsed_logs: Vec<Option<Value>>, // Structured object for each match (null for plain text)
+    error: Option<WasmError>,        // Query syntax or evaluation error, if any
+}
+
+/// Parses and evaluates every newline-separated line in one call, so large pastes
+/// don't pay a JS↔WASM round trip per line.
+#[wasm_bindgen]
+pub fn run_query_batch(lines: &str, query: &str) -> JsValue {
+    batch(lines.lines(), query, WasmConfig::default_ref())
+}
+
+/// Like `run_query_batch`, with a `WasmConfig`.
+#[wasm_bindgen]
+pub fn run_query_batch_with(lines: &str, query: &str, config: &WasmConfig) -> JsValue {
+    batch(lines.lines(), query, config)
+}
+
+/// Like `run_query_batch`, for a JS array of lines.
+#[wasm_bindgen]
+pub fn run_query_lines(lines: Vec<String>, query: &str) -> JsValue {
+    batch(lines.iter().map(String::as_str), query, WasmConfig::default_ref())
+}
+
+fn batch<'a>(lines: impl Iterator<Item = &'a str>, query: &str, config: &WasmConfig) -> JsValue {
+    // The query is parsed once for the whole batch
+    match query::Query::parse(query) {
+        Ok(query) => batch_with(lines, &query, config),
+        Err(e) => {
+            let result = WasmBatchResult {
+                matches: Vec::new(),
+                parsed_logs: Vec::new(),
+                error: Some(WasmError::query(e)),
             };
-            serde_json::to_string(&result).unwrap_or_default()
+            to_js(&result)
+        }
+    }
+}
+
+fn batch_with<'a>(lines: impl Iterator<Item = &'a str>, query: &query::Query, config: &WasmConfig) -> JsValue {
+    let mut result = WasmBatchResult { matches: Vec::new(), parsed_logs: Vec::new(), error: None };
+    for (index, line) in lines.enumerate() {
+        let entry = config.parse(line);
+        match config.matches(&entry, query) {
+            Ok(true) => {
+                result.matches.push(index);
+                result.parsed_logs.push(config.output(entry));
+            }
+            Ok(false) => {}
+            Err(e) => {
+                // Evaluation errors (e.g. a bad time literal) are the same for every line
+                result.error = Some(WasmError::query(e));
+                break;
+            }
         }
     }
-}
\ No newline at end of file
+    to_js(&result)
+}
+
+// One satisfied condition, with ranges as JS string indices (UTF-16 code units),
+// ready for `line.slice(start, end)`.
+#[derive(Serialize)]
+struct WasmHighlight {
+    condition: String,
+    fields: Vec<String>,       // Field paths in the parsed object that satisfied it
+    ranges: Vec<(usize, usize)>,
+}
+
+/// Where `query` matched within `log_line`: one entry per satisfied condition, empty
+/// when the line doesn't match.
+#[wasm_bindgen]
+pub fn highlight(log_line: &str, query: &str) -> Result<JsValue, JsValue> {
+    let query = query::Query::parse(query).map_err(|e| to_js(&WasmError::query(e)))?;
+    highlights(log_line, &query, WasmConfig::default_ref())
+}
+
+fn highlights(log_line: &str, query: &query::Query, config: &WasmConfig) -> Result<JsValue, JsValue> {
+    let entry = config.parse(log_line);
+    let found = query
+        .highlights_with_options(entry.value().unwrap_or(&Value::Null), entry.raw(), &config.query)
+        .map_err(|e| to_js(&WasmError::query(e)))?;
+    let utf16 = |byte: usize| entry.raw()[..byte].encode_utf16().count();
+    let found: Vec<WasmHighlight> = found
+        .into_iter()
+        .map(|h| WasmHighlight {
+            condition: h.condition,
+            fields: h.fields,
+            ranges: h.ranges.into_iter().map(|(start, end)| (utf16(start), utf16(end))).collect(),
+        })
+        .collect();
+    Ok(to_js(&found))
+}
+
+/// Infers the fields, types and example values of newline-separated lines, as the
+/// schema `suggest` expects.
+#[wasm_bindgen]
+pub fn infer_schema(lines: &str) -> JsValue {
+    let entries = lines.lines().filter_map(|line| parsers::parse_log_line(line).into_value());
+    to_js(&schema::infer(entries))
+}
+
+/// As-you-type completions for `query_prefix`: field names, operators valid for the
+/// field's type, or example values. `schema_json` is a JSON-encoded schema, such as
+/// `JSON.stringify(infer_schema(sample))`.
+#[wasm_bindgen]
+pub fn suggest(query_prefix: &str, schema_json: &str) -> Result<JsValue, JsValue> {
+    let schema: schema::Schema = serde_json::from_str(schema_json)
+        .map_err(|e| JsValue::from_str(&format!("Invalid schema: {}", e)))?;
+    Ok(to_js(&suggest::suggest(query_prefix, &schema)))
+}
+
+/// A query compiled once on the JS side and evaluated against many lines.
+#[wasm_bindgen]
+pub struct WasmQuery {
+    query: query::Query,
+    config: WasmConfig,
+}
+
+#[wasm_bindgen]
+impl WasmQuery {
+    /// Parses the query, throwing a `{ kind, message }` error on bad syntax so the UI
+    /// can report it right away.
+    #[wasm_bindgen(constructor)]
+    pub fn new(query: &str) -> Result<WasmQuery, JsValue> {
+        WasmQuery::with_config(query, WasmConfig::default_ref())
+    }
+
+    /// Like `new`, parsing and evaluating lines with `config`.
+    pub fn with_config(query: &str, config: &WasmConfig) -> Result<WasmQuery, JsValue> {
+        query::Query::parse(query)
+            .map(|query| WasmQuery { query, config: config.clone() })
+            .map_err(|e| to_js(&WasmError::query(e)))
+    }
+
+    /// Parses `log_line` and reports whether it matches.
+    pub fn matches(&self, log_line: &str) -> Result<bool, JsValue> {
+        self.config
+            .matches(&self.config.parse(log_line), &self.query)
+            .map_err(|e| to_js(&WasmError::query(e)))
+    }
+
+    /// Like `run_query_batch`, reusing this compiled query.
+    pub fn matches_batch(&self, lines: &str) -> JsValue {
+        batch_with(lines.lines(), &self.query, &self.config)
+    }
+
+    /// Like `highlight`, reusing this compiled query.
+    pub fn highlight(&self, log_line: &str) -> Result<JsValue, JsValue> {
+        highlights(log_line, &self.query, &self.config)
+    }
+}
+
+// Everything a `WasmAggregator` has computed so far, ready for charting.
+#[derive(Serialize)]
+struct WasmAggregateResult {
+    lines: u64,   // Lines pushed
+    matched: u64, // Lines that passed the filter and were aggregated
+    stats: Option<Stats>,
+    groups: Option<GroupedStats>,
+    histogram: Option<HistogramResult>,
+}
+
+/// Feeds lines into the aggregation subsystem from JS: numeric stats (count, avg,
+/// percentiles), group-by counts and a time histogram, over the lines matching an
+/// optional filter query.
+#[wasm_bindgen]
+pub struct WasmAggregator {
+    config: WasmConfig,
+    filter: Option<query::Query>,
+    stats: Option<Aggregator>,
+    groups: Option<GroupBy>,
+    histogram: Option<Histogram>,
+    lines: u64,
+    matched: u64,
+}
+
+#[wasm_bindgen]
+impl WasmAggregator {
+    /// Aggregates every line, or only those matching `filter` when it is non-empty.
+    #[wasm_bindgen(constructor)]
+    pub fn new(filter: Option<String>) -> Result<WasmAggregator, JsValue> {
+        WasmAggregator::with_config(filter, WasmConfig::default_ref())
+    }
+
+    /// Like `new`, parsing and filtering lines with `config`.
+    pub fn with_config(filter: Option<String>, config: &WasmConfig) -> Result<WasmAggregator, JsValue> {
+        let filter = match filter.filter(|f| !f.trim().is_empty()) {
+            Some(f) => Some(query::Query::parse(&f).map_err(|e| to_js(&WasmError::query(e)))?),
+            None => None,
+        };
+        Ok(WasmAggregator {
+            config: config.clone(),
+            filter,
+            stats: None,
+            groups: None,
+            histogram: None,
+            lines: 0,
+            matched: 0,
+        })
+    }
+
+    /// Computes count, sum, min/max, avg and percentiles over a numeric `field`.
+    pub fn stats(&mut self, field: &str) {
+        self.stats = Some(Aggregator::new(field));
+    }
+
+    /// Counts entries per combination of `keys`, with stats over `field` when given.
+    pub fn group_by(&mut self, keys: Vec<String>, field: Option<String>) {
+        self.groups = Some(GroupBy::new(keys, field));
+    }
+
+    /// Buckets entries by timestamp every `interval` (`1m`, `5m`, `1h`), optionally
+    /// counting per value of `group_field` in each bucket.
+    pub fn histogram(&mut self, interval: &str, group_field: Option<String>) -> Result<(), JsValue> {
+        let interval = humantime::parse_duration(interval.trim())
+            .map_err(|e| JsValue::from_str(&format!("Invalid interval '{}': {}", interval, e)))?;
+        self.histogram = Some(match group_field {
+            Some(field) => Histogram::grouped(interval, field),
+            None => Histogram::new(interval),
+        });
+        Ok(())
+    }
+
+    /// Parses one line and, if it passes the filter, feeds it to every aggregation.
+    pub fn push(&mut self, log_line: &str) -> Result<(), JsValue> {
+        self.lines += 1;
+        let entry = self.config.parse(log_line);
+        if let Some(filter) = &self.filter {
+            if !self.config.matches(&entry, filter).map_err(|e| to_js(&WasmError::query(e)))? {
+                return Ok(());
+            }
+        }
+        self.matched += 1;
+
+        let value = entry.value().unwrap_or(&Value::Null);
+        if let Some(stats) = &mut self.stats {
+            stats.observe(value);
+        }
+        if let Some(groups) = &mut self.groups {
+            groups.observe(value);
+        }
+        if let Some(histogram) = &mut self.histogram {
+            histogram.observe(value);
+        }
+        Ok(())
+    }
+
+    /// Pushes every newline-separated line in one call.
+    pub fn push_batch(&mut self, lines: &str) -> Result<(), JsValue> {
+        lines.lines().try_for_each(|line| self.push(line))
+    }
+
+    /// The results so far; aggregation can continue afterwards.
+    pub fn result(&self) -> JsValue {
+        to_js(&WasmAggregateResult {
+            lines: self.lines,
+            matched: self.matched,
+            stats: self.stats.as_ref().map(Aggregator::stats),
+            groups: self.groups.as_ref().map(GroupBy::stats),
+            histogram: self.histogram.as_ref().map(Histogram::result),
+        })
+    }
+}
+
+/// Incremental processing of a large file pushed in chunks (e.g. `File.slice()` reads),
+/// so the UI stays responsive and can show progress.
+///
+/// Lines split across chunk boundaries (even mid UTF-8 character, for byte chunks) are
+/// held back until the rest arrives; `finish` flushes a final line without a newline.
+#[wasm_bindgen]
+pub struct WasmSession {
+    query: query::Query,
+    config: WasmConfig,
+    pending: Vec<u8>,                // Trailing partial line from the last chunk
+    lines: usize,                    // Complete lines processed so far
+    matches: Vec<usize>,             // Every matching line index so far
+    new_matches: Vec<usize>,         // Matches not yet handed out by `take_matches`
+    new_logs: Vec<Option<Value>>,    // Parsed objects for `new_matches`
+    cancelled: bool,
+}
+
+#[wasm_bindgen]
+impl WasmSession {
+    #[wasm_bindgen(constructor)]
+    pub fn new(query: &str) -> Result<WasmSession, JsValue> {
+        WasmSession::with_config(query, WasmConfig::default_ref())
+    }
+
+    /// Like `new`, parsing and evaluating lines with `config`.
+    pub fn with_config(query: &str, config: &WasmConfig) -> Result<WasmSession, JsValue> {
+        let query = query::Query::parse(query).map_err(|e| to_js(&WasmError::query(e)))?;
+        Ok(WasmSession {
+            query,
+            config: config.clone(),
+            pending: Vec::new(),
+            lines: 0,
+            matches: Vec::new(),
+            new_matches: Vec::new(),
+            new_logs: Vec::new(),
+            cancelled: false,
+        })
+    }
+
+    /// Processes a text chunk. Returns how many new matches it produced.
+    pub fn push_chunk(&mut self, chunk: &str) -> Result<usize, JsValue> {
+        self.push_bytes(chunk.as_bytes())
+    }
+
+    /// Processes a raw byte chunk (an `ArrayBuffer` slice). Returns how many new matches
+    /// it produced. Invalid UTF-8 is replaced rather than rejected.
+    pub fn push_bytes(&mut self, chunk: &[u8]) -> Result<usize, JsValue> {
+        if self.cancelled {
+            return Ok(0);
+        }
+        let before = self.matches.len();
+        self.pending.extend_from_slice(chunk);
+        let Some(last_newline) = self.pending.iter().rposition(|&b| b == b'\n') else {
+            return Ok(0);
+        };
+        let rest = self.pending.split_off(last_newline + 1);
+        let complete = std::mem::replace(&mut self.pending, rest);
+        for line in complete[..last_newline].split(|&b| b == b'\n') {
+            self.process(&String::from_utf8_lossy(line))?;
+        }
+        Ok(self.matches.len() - before)
+    }
+
+    /// Processes the final line when the input doesn't end with a newline.
+    pub fn finish(&mut self) -> Result<usize, JsValue> {
+        if self.cancelled || self.pending.is_empty() {
+            return Ok(0);
+        }
+        let before = self.matches.len();
+        let line = std::mem::take(&mut self.pending);
+        self.process(&String::from_utf8_lossy(&line))?;
+        Ok(self.matches.len() - before)
+    }
+
+    /// Stops processing; later chunks are ignored. Matches found so far are kept.
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+        self.pending.clear();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// Complete lines processed so far.
+    pub fn lines_processed(&self) -> usize {
+        self.lines
+    }
+
+    /// Indices (0-based) of every matching line so far.
+    pub fn matches(&self) -> Vec<usize> {
+        self.matches.clone()
+    }
+
+    /// Matches found since the last call, with their parsed objects, in the same shape
+    /// as `run_query_batch`.
+    pub fn take_matches(&mut self) -> JsValue {
+        to_js(&WasmBatchResult {
+            matches: std::mem::take(&mut self.new_matches),
+            parsed_logs: std::mem::take(&mut self.new_logs),
+            error: None,
+        })
+    }
+}
+
+impl WasmSession {
+    fn process(&mut self, line: &str) -> Result<(), JsValue> {
+        let index = self.lines;
+        self.lines += 1;
+        let entry = self.config.parse(line.strip_suffix('\r').unwrap_or(line));
+        if self.config.matches(&entry, &self.query).map_err(|e| to_js(&WasmError::query(e)))? {
+            self.matches.push(index);
+            self.new_matches.push(index);
+            self.new_logs.push(self.config.output(entry));
+        }
+        Ok(())
+    }
+}